@@ -0,0 +1,322 @@
+//! Client for the server's `Admin` service. Like the player CLI, every command prints JSON lines on
+//! stdout and exits with a non-zero status when the server refuses it.
+
+use std::{error::Error, process::ExitCode};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use helpers::{
+  auth::Session,
+  chesstactoe::{
+    admin_client, game_event::Kind, tic_tac_toe, Adjudication, AdminGameInfo,
+    AdminListGamesRequest, AdminListLobbiesRequest, AdminListQueueRequest, ChatMessage, Color,
+    DumpGameRequest, EndGameRequest, GameEvent, GameOutcome, GameSettings, KickPlayerRequest,
+    SendNoticeRequest,
+  },
+};
+use serde_json::{json, Value};
+use tonic::{transport::Channel, Status};
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Operate a Chess-Tac-Toe server")]
+struct Args {
+  /// Server to connect to
+  #[arg(
+    short,
+    long,
+    env = "CHESSTACTOE_SERVER",
+    default_value = "http://localhost:50051"
+  )]
+  server: String,
+  /// The server's admin token
+  #[arg(short, long, env = "CHESSTACTOE_ADMIN_TOKEN", hide_env_values = true)]
+  token: String,
+  #[command(subcommand)]
+  command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+  /// Print every game in memory, finished ones waiting to be archived included
+  Games,
+  /// Print the lobbies waiting for someone to join
+  Lobbies,
+  /// Print the players waiting in the matchmaking queue
+  Queue,
+  /// Print a game's position, chat and full event log
+  Dump { id: String },
+  /// End a game with a result, or abort it without one
+  End {
+    id: String,
+    #[arg(long, value_enum)]
+    result: Result,
+    /// Told to both players
+    #[arg(long, default_value = "")]
+    reason: String,
+  },
+  /// Forfeit a player's games and take them out of lobbies and the queue
+  Kick {
+    username: String,
+    /// Told to the player and their opponents
+    #[arg(long, default_value = "")]
+    reason: String,
+  },
+  /// Show a message to everyone watching a board
+  Notice { text: String },
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Result {
+  Abort,
+  White,
+  Black,
+  Draw,
+}
+
+impl From<Result> for Adjudication {
+  fn from(result: Result) -> Self {
+    match result {
+      Result::Abort => Adjudication::Abort,
+      Result::White => Adjudication::WhiteWins,
+      Result::Black => Adjudication::BlackWins,
+      Result::Draw => Adjudication::Draw,
+    }
+  }
+}
+
+fn print(line: Value) {
+  println!("{line}");
+}
+
+fn message(status: Status) -> Box<dyn Error> {
+  status.message().into()
+}
+
+fn color(color: i32) -> Value {
+  match Color::from_i32(color) {
+    Some(Color::White) => json!("white"),
+    Some(Color::Black) => json!("black"),
+    None => Value::Null,
+  }
+}
+
+fn settings(settings: &Option<GameSettings>) -> Value {
+  let settings = settings.clone().unwrap_or_default();
+  let time_control = settings.time_control.unwrap_or_default();
+
+  json!({
+    "initial": time_control.initial,
+    "increment": time_control.increment,
+    "rated": settings.rated,
+  })
+}
+
+fn game(info: &AdminGameInfo) -> Value {
+  json!({
+    "event": "game",
+    "id": info.id,
+    "white": info.white,
+    "black": info.black,
+    "whiteSeat": info.white_seat,
+    "blackSeat": info.black_seat,
+    "settings": settings(&info.settings),
+    "public": info.public,
+    "next": color(info.next),
+    "moves": info.moves,
+    "finished": info.finished,
+    "whiteConnected": info.white_connected,
+    "blackConnected": info.black_connected,
+  })
+}
+
+fn chat(message: &ChatMessage) -> Value {
+  json!({
+    "color": color(message.color),
+    "username": message.username,
+    "text": message.text,
+    "sentAt": message.sent_at,
+  })
+}
+
+fn event(event: &GameEvent) -> Value {
+  let (kind, details) = match &event.kind {
+    Some(Kind::Created(created)) => (
+      "created",
+      json!({ "white": created.white, "black": created.black, "settings": settings(&created.settings) }),
+    ),
+    Some(Kind::Joined(side)) => ("joined", color(*side)),
+    Some(Kind::Move(played)) => (
+      "move",
+      json!({ "color": color(played.color), "board": played.board, "alg": played.alg }),
+    ),
+    Some(Kind::Chat(message)) => ("chat", chat(message)),
+    Some(Kind::RematchOffered(side)) => ("rematchOffered", color(*side)),
+    Some(Kind::RematchDeclined(side)) => ("rematchDeclined", color(*side)),
+    Some(Kind::RematchAccepted(side)) => ("rematchAccepted", color(*side)),
+    Some(Kind::TakebackRequested(side)) => ("takebackRequested", color(*side)),
+    Some(Kind::TakebackDeclined(side)) => ("takebackDeclined", color(*side)),
+    Some(Kind::TakebackAccepted(side)) => ("takebackAccepted", color(*side)),
+    Some(Kind::DrawOffered(side)) => ("drawOffered", color(*side)),
+    Some(Kind::DrawDeclined(side)) => ("drawDeclined", color(*side)),
+    Some(Kind::DrawAccepted(side)) => ("drawAccepted", color(*side)),
+    Some(Kind::Resigned(side)) => ("resigned", color(*side)),
+    Some(Kind::TimedOut(side)) => ("timedOut", color(*side)),
+    Some(Kind::Forfeit(winner)) => ("forfeit", color(*winner)),
+    Some(Kind::Adjudicated(adjudicated)) => {
+      let outcome = match adjudicated.outcome() {
+        GameOutcome::WhiteWon => json!("white"),
+        GameOutcome::BlackWon => json!("black"),
+        GameOutcome::Drawn => json!("draw"),
+        GameOutcome::Abandoned => json!("abandoned"),
+      };
+
+      (
+        "adjudicated",
+        json!({ "result": outcome, "reason": adjudicated.reason }),
+      )
+    }
+    None => ("unknown", Value::Null),
+  };
+
+  json!({ "seq": event.seq, "at": event.at, "kind": kind, "details": details })
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+  match run(Args::parse()).await {
+    Ok(()) => ExitCode::SUCCESS,
+    Err(err) => {
+      eprintln!("{err}");
+      ExitCode::FAILURE
+    }
+  }
+}
+
+async fn run(args: Args) -> std::result::Result<(), Box<dyn Error>> {
+  let channel = Channel::from_shared(args.server)?.connect().await?;
+
+  // The admin token goes out as the bearer token, like a player's session.
+  let session = Session::default();
+  session.set_token(&args.token);
+
+  let mut admin = admin_client::AdminClient::with_interceptor(channel, session);
+
+  match args.command {
+    Command::Games => {
+      let games = admin
+        .list_games(AdminListGamesRequest {})
+        .await
+        .map_err(message)?
+        .into_inner()
+        .games;
+
+      for info in &games {
+        print(game(info));
+      }
+    }
+    Command::Lobbies => {
+      let lobbies = admin
+        .list_lobbies(AdminListLobbiesRequest {})
+        .await
+        .map_err(message)?
+        .into_inner()
+        .lobbies;
+
+      for lobby in lobbies {
+        print(json!({
+          "event": "lobby",
+          "code": lobby.code,
+          "host": lobby.host,
+          "hostSeat": lobby.host_seat,
+          "settings": settings(&lobby.settings),
+          "public": lobby.public,
+          "waitingSecs": lobby.waiting_secs,
+        }));
+      }
+    }
+    Command::Queue => {
+      let entries = admin
+        .list_queue(AdminListQueueRequest {})
+        .await
+        .map_err(message)?
+        .into_inner()
+        .entries;
+
+      for entry in entries {
+        print(json!({
+          "event": "queued",
+          "seat": entry.seat,
+          "username": entry.username,
+          "rating": entry.rating,
+          "settings": settings(&entry.settings),
+          "waitingSecs": entry.waiting_secs,
+        }));
+      }
+    }
+    Command::Dump { id } => {
+      let dump = admin
+        .dump_game(DumpGameRequest { id })
+        .await
+        .map_err(message)?
+        .into_inner();
+
+      let board = dump.game.unwrap_or_default();
+
+      let result = match board.end_result {
+        Some(tic_tac_toe::EndResult::Color(winner)) => color(winner),
+        Some(tic_tac_toe::EndResult::Draw(_)) => json!("draw"),
+        _ => Value::Null,
+      };
+
+      let mut line = dump.info.as_ref().map(game).unwrap_or_default();
+      line["event"] = json!("dump");
+      line["result"] = result;
+      line["boards"] = board.chesses.iter().map(|chess| json!(chess.fen)).collect();
+      line["history"] = json!(board.moves);
+      line["chat"] = dump.chat.iter().map(chat).collect();
+      line["events"] = dump.events.iter().map(event).collect();
+
+      print(line);
+    }
+    Command::End { id, result, reason } => {
+      admin
+        .end_game(EndGameRequest {
+          id: id.clone(),
+          result: Adjudication::from(result) as i32,
+          reason,
+        })
+        .await
+        .map_err(message)?;
+
+      print(json!({ "event": "ended", "id": id }));
+    }
+    Command::Kick { username, reason } => {
+      let kicked = admin
+        .kick_player(KickPlayerRequest {
+          username: username.clone(),
+          reason,
+        })
+        .await
+        .map_err(message)?
+        .into_inner();
+
+      print(json!({
+        "event": "kicked",
+        "username": username,
+        "games": kicked.games,
+        "lobbies": kicked.lobbies,
+        "queued": kicked.queued,
+      }));
+    }
+    Command::Notice { text } => {
+      let sent = admin
+        .send_notice(SendNoticeRequest { text })
+        .await
+        .map_err(message)?
+        .into_inner();
+
+      print(json!({ "event": "notice", "recipients": sent.recipients }));
+    }
+  }
+
+  Ok(())
+}
@@ -0,0 +1,121 @@
+//! JSON lines printed on stdout, one object per line with an `event` field telling them apart.
+
+use helpers::chesstactoe::{
+  chess, tic_tac_toe, ChatMessage, Color, MidGameRequest, MovePieceResponse, MoveResult, Request,
+  SubscribeBoardResponse,
+};
+use serde_json::{json, Value};
+
+fn color(color: i32) -> Value {
+  match Color::from_i32(color) {
+    Some(Color::White) => json!("white"),
+    Some(Color::Black) => json!("black"),
+    None => Value::Null,
+  }
+}
+
+/// `null` while the board is still being played.
+fn board_result(end: Option<&chess::EndResult>) -> Value {
+  match end {
+    Some(chess::EndResult::Color(winner)) => color(*winner),
+    Some(chess::EndResult::Draw(_)) => json!("draw"),
+    Some(chess::EndResult::None(_)) | None => Value::Null,
+  }
+}
+
+/// `null` while the game is still being played.
+pub fn game_result(end: Option<&tic_tac_toe::EndResult>) -> Value {
+  match end {
+    Some(tic_tac_toe::EndResult::Color(winner)) => color(*winner),
+    Some(tic_tac_toe::EndResult::Draw(_)) => json!("draw"),
+    Some(tic_tac_toe::EndResult::None(_)) | None => Value::Null,
+  }
+}
+
+fn requestee(request: &Option<Request>) -> Value {
+  request
+    .as_ref()
+    .map_or(Value::Null, |request| color(request.requestee))
+}
+
+fn mid_game_request(request: &MidGameRequest) -> Value {
+  json!({
+    "takeback": requestee(&request.takeback),
+    "draw": requestee(&request.draw),
+    "rematch": requestee(&request.rematch),
+  })
+}
+
+pub fn chat(chat: &[ChatMessage]) -> Value {
+  chat
+    .iter()
+    .map(|message| {
+      json!({
+        "color": color(message.color),
+        "username": message.username,
+        "text": message.text,
+        "sentAt": message.sent_at,
+      })
+    })
+    .collect()
+}
+
+pub fn lobby(seat: &str, code: &str) -> Value {
+  json!({ "event": "lobby", "seat": seat, "code": code })
+}
+
+pub fn waiting(seat: &str) -> Value {
+  json!({ "event": "waiting", "seat": seat })
+}
+
+pub fn started(seat: &str) -> Value {
+  json!({ "event": "started", "seat": seat })
+}
+
+/// Boards are listed in the order the server numbers them, so `boards[n]` is board `n` of `move`.
+pub fn board(response: &SubscribeBoardResponse) -> Value {
+  let game = response.game.as_ref();
+
+  let boards: Vec<Value> = game
+    .map(|game| {
+      game
+        .chesses
+        .iter()
+        .map(|chess| json!({ "fen": chess.fen, "result": board_result(chess.end_result.as_ref()) }))
+        .collect()
+    })
+    .unwrap_or_default();
+
+  json!({
+    "event": "board",
+    "color": color(response.color),
+    "next": game.map_or(Value::Null, |game| color(game.next)),
+    "lastMove": game.map_or(Value::Null, |game| json!(game.last_move)),
+    "moves": game.map_or(Value::Null, |game| json!(game.moves)),
+    "result": game_result(game.and_then(|game| game.end_result.as_ref())),
+    "boards": boards,
+    "request": response.request.as_ref().map_or(Value::Null, mid_game_request),
+    "chat": chat(&response.chat),
+    "seat": Some(&response.seat).filter(|seat| !seat.is_empty()),
+    "notice": Some(&response.notice).filter(|notice| !notice.is_empty()),
+  })
+}
+
+pub fn move_result(response: &MovePieceResponse) -> Value {
+  let result = match response.successful() {
+    MoveResult::ResultSuccessful => "successful",
+    MoveResult::ResultIllegal => "illegal",
+    MoveResult::ResultError => "error",
+  };
+
+  json!({
+    "event": "move",
+    "result": result,
+    "reason": Some(&response.reason).filter(|reason| !reason.is_empty()),
+  })
+}
+
+/// Also what the client's replay viewer loads, through `moves`.
+pub fn export(fen: &str, moves: &[String], chat_history: &[ChatMessage]) -> Value {
+  json!({ "event": "export", "fen": fen, "moves": moves, "chat": chat(chat_history) })
+}
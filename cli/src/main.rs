@@ -0,0 +1,429 @@
+//! Non-interactive client for driving games from scripts. Every command prints JSON lines on
+//! stdout, see [`json`], and exits with a non-zero status when the server rejects it.
+//!
+//! The server counts a player as present only while a board stream is open for their seat. Commands
+//! like `move` don't open one, so a script has to keep a `watch` running, or the game is forfeited
+//! once the disconnect grace period runs out.
+
+mod json;
+
+use std::{error::Error, process::ExitCode};
+
+use clap::{Args as ClapArgs, Parser, Subcommand};
+use helpers::{
+  auth::Session,
+  chesstactoe::{
+    auth_client::AuthClient, game_client, game_event::Kind, join_response::GameStatus, DrawRequest,
+    GameSettings, GetGameEventsRequest, JoinLobbyRequest, JoinRequest, JoinResponse, LoginRequest,
+    MakeLobbyRequest, MovePieceRequest, MoveResult, RegisterRequest, RematchRequest, ResignRequest,
+    SubscribeBoardRequest, SubscribeBoardResponse, TakeBackRequest, TimeControl,
+  },
+  tictactoe::TicTacToe,
+};
+use serde_json::Value;
+use tonic::{codegen::InterceptedService, transport::Channel, Status, Streaming};
+
+type GameClient = game_client::GameClient<InterceptedService<Channel, Session>>;
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Scriptable Chess-Tac-Toe client")]
+struct Args {
+  /// Server to connect to
+  #[arg(
+    short,
+    long,
+    env = "CHESSTACTOE_SERVER",
+    default_value = "http://localhost:50051"
+  )]
+  server: String,
+  #[arg(short, long, env = "CHESSTACTOE_USERNAME")]
+  username: String,
+  #[arg(short, long, env = "CHESSTACTOE_PASSWORD", hide_env_values = true)]
+  password: String,
+  /// Create the account before logging in
+  #[arg(long)]
+  register: bool,
+  #[command(subcommand)]
+  command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+  /// Open a lobby and wait for someone to join it with the printed code
+  MakeLobby {
+    #[command(flatten)]
+    settings: Settings,
+    /// List the lobby for everyone to see
+    #[arg(long)]
+    public: bool,
+    #[command(flatten)]
+    follow: Follow,
+  },
+  /// Join a lobby by its code
+  Join {
+    code: String,
+    #[command(flatten)]
+    follow: Follow,
+  },
+  /// Wait in the matchmaking queue for an opponent
+  Queue {
+    #[command(flatten)]
+    settings: Settings,
+    #[command(flatten)]
+    follow: Follow,
+  },
+  /// Play a move on one of the boards, numbered 0 to 8 in reading order
+  Move {
+    board: u32,
+    /// Move in long algebraic notation, e.g. e2e4, Ng1xf3 or O-O
+    alg: String,
+    /// Seat printed when the game started
+    #[arg(long, env = "CHESSTACTOE_SEAT")]
+    seat: String,
+  },
+  /// Offer a rematch once the game is over, or accept the opponent's offer. `watch --keep-going`
+  /// on the old seat follows the new game and prints its seat
+  Rematch {
+    #[arg(long, env = "CHESSTACTOE_SEAT")]
+    seat: String,
+    /// Withdraw the offer or turn down the opponent's
+    #[arg(long)]
+    decline: bool,
+  },
+  /// Ask the opponent to undo the last move, or answer their request
+  Takeback {
+    #[arg(long, env = "CHESSTACTOE_SEAT")]
+    seat: String,
+    /// Accept the opponent's request
+    #[arg(long, conflicts_with = "decline")]
+    accept: bool,
+    /// Withdraw the request or turn down the opponent's
+    #[arg(long)]
+    decline: bool,
+  },
+  /// Offer a draw, or accept the opponent's offer
+  Draw {
+    #[arg(long, env = "CHESSTACTOE_SEAT")]
+    seat: String,
+    /// Withdraw the offer or turn down the opponent's
+    #[arg(long)]
+    decline: bool,
+  },
+  /// Give the game up
+  Resign {
+    #[arg(long, env = "CHESSTACTOE_SEAT")]
+    seat: String,
+  },
+  /// Print the board after every move until the game ends
+  ///
+  /// This is what keeps the player present: without a `watch` open on the seat, the server
+  /// forfeits the game once the disconnect grace period runs out, whatever else is played from it.
+  Watch {
+    #[arg(long, env = "CHESSTACTOE_SEAT")]
+    seat: String,
+    /// Keep watching after the game ends, following any rematch
+    #[arg(long)]
+    keep_going: bool,
+  },
+  /// Print the position as a FEN that `TicTacToe::from_fen` reads back, with the moves and chat
+  ///
+  /// Reads the game's log, so it doesn't disturb a `watch` on the same seat.
+  Export {
+    /// Seat of a game being played, or the ID of any game that's public or was yours
+    #[arg(long, env = "CHESSTACTOE_SEAT")]
+    seat: String,
+  },
+}
+
+#[derive(Debug, ClapArgs)]
+struct Settings {
+  /// Initial time in seconds, 0 for no clock
+  #[arg(long, default_value_t = 0)]
+  initial: u32,
+  /// Seconds added after every move
+  #[arg(long, default_value_t = 0)]
+  increment: u32,
+  #[arg(long)]
+  rated: bool,
+}
+
+impl From<Settings> for GameSettings {
+  fn from(settings: Settings) -> Self {
+    GameSettings {
+      time_control: Some(TimeControl {
+        initial: settings.initial,
+        increment: settings.increment,
+      }),
+      rated: settings.rated,
+      ..Default::default()
+    }
+  }
+}
+
+#[derive(Debug, ClapArgs)]
+struct Follow {
+  /// Keep printing the board once the game starts, like `watch`. Without it, start a `watch` to
+  /// stay present in the game
+  #[arg(long)]
+  watch: bool,
+}
+
+fn print(line: Value) {
+  println!("{line}");
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+  match run(Args::parse()).await {
+    Ok(()) => ExitCode::SUCCESS,
+    Err(err) => {
+      eprintln!("{err}");
+      ExitCode::FAILURE
+    }
+  }
+}
+
+async fn run(args: Args) -> Result<(), Box<dyn Error>> {
+  let channel = Channel::from_shared(args.server)?.connect().await?;
+
+  let mut auth = AuthClient::new(channel.clone());
+
+  let (username, password) = (args.username, args.password);
+
+  let login = if args.register {
+    auth.register(RegisterRequest { username, password }).await
+  } else {
+    auth.login(LoginRequest { username, password }).await
+  }
+  .map_err(message)?
+  .into_inner();
+
+  let session = Session::default();
+  session.set_token(&login.token);
+
+  let mut client = game_client::GameClient::with_interceptor(channel, session);
+
+  match args.command {
+    Command::MakeLobby {
+      settings,
+      public,
+      follow,
+    } => {
+      let request = MakeLobbyRequest {
+        settings: Some(settings.into()),
+        public,
+      };
+
+      let mut stream = client
+        .make_lobby(request)
+        .await
+        .map_err(message)?
+        .into_inner();
+
+      while let Some(response) = stream.message().await.map_err(message)? {
+        let Some(join_response) = response.join_response else {
+          continue;
+        };
+
+        if join_response.status() == GameStatus::NotReady {
+          print(json::lobby(&join_response.uuid, &response.room_id));
+        } else {
+          return started(&mut client, join_response, follow).await;
+        }
+      }
+
+      Err("Lobby closed before anyone joined".into())
+    }
+    Command::Join { code, follow } => {
+      let stream = client
+        .join_lobby(JoinLobbyRequest { code })
+        .await
+        .map_err(message)?
+        .into_inner();
+
+      wait(&mut client, stream, follow).await
+    }
+    Command::Queue { settings, follow } => {
+      let request = JoinRequest {
+        settings: Some(settings.into()),
+      };
+
+      let stream = client.join(request).await.map_err(message)?.into_inner();
+
+      wait(&mut client, stream, follow).await
+    }
+    Command::Move { board, alg, seat } => {
+      let response = client
+        .move_piece(MovePieceRequest {
+          board,
+          alg,
+          uuid: seat,
+        })
+        .await
+        .map_err(message)?
+        .into_inner();
+
+      print(json::move_result(&response));
+
+      if response.successful() != MoveResult::ResultSuccessful {
+        return Err(format!("Move was not played: {}", response.reason).into());
+      }
+
+      Ok(())
+    }
+    Command::Rematch { seat, decline } => {
+      client
+        .rematch(RematchRequest {
+          uuid: seat,
+          decline,
+        })
+        .await
+        .map_err(message)?;
+
+      Ok(())
+    }
+    Command::Takeback {
+      seat,
+      accept,
+      decline,
+    } => {
+      client
+        .take_back(TakeBackRequest {
+          uuid: seat,
+          is_response: accept || decline,
+          accepted: accept,
+        })
+        .await
+        .map_err(message)?;
+
+      Ok(())
+    }
+    Command::Draw { seat, decline } => {
+      client
+        .offer_draw(DrawRequest {
+          uuid: seat,
+          decline,
+        })
+        .await
+        .map_err(message)?;
+
+      Ok(())
+    }
+    Command::Resign { seat } => {
+      client
+        .resign(ResignRequest { uuid: seat })
+        .await
+        .map_err(message)?;
+
+      Ok(())
+    }
+    Command::Watch { seat, keep_going } => watch(&mut client, seat, keep_going).await,
+    Command::Export { seat } => {
+      let events = client
+        .get_game_events(GetGameEventsRequest { id: seat, since: 0 })
+        .await
+        .map_err(message)?
+        .into_inner()
+        .events;
+
+      let mut moves = vec![];
+      let mut chat = vec![];
+
+      for event in events {
+        match event.kind {
+          Some(Kind::Move(played)) => moves.push(format!("{} {}", played.board, played.alg)),
+          Some(Kind::TakebackAccepted(_)) => {
+            moves.pop();
+          }
+          Some(Kind::Chat(message)) => chat.push(message),
+          _ => {}
+        }
+      }
+
+      let position = TicTacToe::replay(moves.iter().map(String::as_str))?
+        .pop()
+        .map(|played| played.position)
+        .unwrap_or_default();
+
+      print(json::export(&position.to_fen()?, &moves, &chat));
+
+      Ok(())
+    }
+  }
+}
+
+/// Only the status message, the rest of a `Status` is noise for someone reading a script's output.
+fn message(status: Status) -> Box<dyn Error> {
+  status.message().into()
+}
+
+/// Waits on a join stream until the game starts.
+async fn wait(
+  client: &mut GameClient,
+  mut stream: Streaming<JoinResponse>,
+  follow: Follow,
+) -> Result<(), Box<dyn Error>> {
+  while let Some(response) = stream.message().await.map_err(message)? {
+    if response.status() == GameStatus::NotReady {
+      print(json::waiting(&response.uuid));
+    } else {
+      return started(client, response, follow).await;
+    }
+  }
+
+  Err("Stopped waiting before the game started".into())
+}
+
+async fn started(
+  client: &mut GameClient,
+  response: JoinResponse,
+  follow: Follow,
+) -> Result<(), Box<dyn Error>> {
+  print(json::started(&response.uuid));
+
+  if follow.watch {
+    watch(client, response.uuid, false).await?;
+  }
+
+  Ok(())
+}
+
+async fn subscribe(
+  client: &mut GameClient,
+  seat: String,
+) -> Result<Streaming<SubscribeBoardResponse>, Box<dyn Error>> {
+  Ok(
+    client
+      .subscribe_board(SubscribeBoardRequest { uuid: seat })
+      .await
+      .map_err(message)?
+      .into_inner(),
+  )
+}
+
+/// A seat has a single live subscription, so watching from here takes over from any other watcher.
+async fn watch(
+  client: &mut GameClient,
+  seat: String,
+  keep_going: bool,
+) -> Result<(), Box<dyn Error>> {
+  let mut stream = subscribe(client, seat).await?;
+
+  while let Some(response) = stream.message().await.map_err(message)? {
+    let over = !json::game_result(
+      response
+        .game
+        .as_ref()
+        .and_then(|game| game.end_result.as_ref()),
+    )
+    .is_null();
+
+    print(json::board(&response));
+
+    if over && !keep_going {
+      break;
+    }
+  }
+
+  Ok(())
+}
@@ -1,121 +1,230 @@
 use std::{collections::HashMap, sync::Arc};
 
 use base64::Engine;
-use dioxus::prelude::*;
+use dioxus::{html::input_data::keyboard_types::Key, prelude::*};
 use futures::stream::StreamExt;
 use helpers::{
   chess::{ChessBoard, PieceName},
-  chesstactoe::{game_client::GameClient, Color, MovePieceRequest},
-  Coordinates,
+  chesstactoe::{chess::EndResult, Color},
+  tictactoe::TicTacToe,
+  Coordinates, Error,
 };
 use include_dir::{include_dir, Dir};
 use once_cell::sync::Lazy;
-use tokio::sync::Mutex;
-use tonic::transport::Channel;
+use utils::source::GameSource;
 
 fn on_piece_click(
-  ev: MouseEvent,
   square: (usize, usize),
   selected: &UseState<Option<(usize, usize)>>,
   chess: &ChessBoard,
-  side: Color,
+  turn: Turn,
   ct: &Coroutine<String>,
   promotion_data: PromotionData,
 ) {
   if selected.is_none() {
     selected.modify(|_| Some(square))
   }
-  on_click(ev, square, selected, chess, side, ct, promotion_data);
+  on_click(square, selected, chess, turn, ct, promotion_data);
 }
 
+#[derive(Clone, Copy)]
 struct PromotionData<'a> {
   promotin: &'a UseState<bool>,
   promotion_square: &'a UseState<Option<(usize, usize)>>,
+  rejection: &'a UseState<Option<String>>,
+}
+
+#[derive(Clone, Copy)]
+struct Turn<'a> {
+  side: Color,
+  next: Color,
+  board: u32,
+  /// Where moves go while it's the opponent's turn, if premoving is possible here.
+  premoves: Option<&'a UseRef<Vec<Premove>>>,
+}
+
+/// A move queued on a board while the opponent is thinking. It's sent right after their next
+/// move if it's still legal then, otherwise the whole queue is dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Premove {
+  pub board: u32,
+  pub from: Coordinates,
+  pub to: Coordinates,
+  /// Queen when not picked.
+  pub promotion: Option<PieceName>,
+}
+
+/// Takes the first queued premove, returning the board and notation to send if it's legal in
+/// `game`. An illegal one cancels everything queued, since the later moves counted on it.
+pub fn next_premove(
+  premoves: &UseRef<Vec<Premove>>,
+  game: &TicTacToe,
+  side: Color,
+) -> Option<(u32, String)> {
+  if premoves.read().is_empty() {
+    return None;
+  }
+
+  let premove = premoves.write().remove(0);
+  let board = premove.board as usize;
+  let chess = &game.chesses[board / 3][board % 3];
+
+  let alg = chess
+    .notation(premove.from, premove.to, premove.promotion)
+    .filter(|alg| chess.validate_move(alg, side).unwrap_or(false));
+
+  if alg.is_none() {
+    premoves.write().clear();
+  }
+
+  alg.map(|alg| (premove.board, alg))
+}
+
+/// Queues the move when it's the opponent's turn. The piece must be ours, or be on its way to
+/// `from` through an earlier premove.
+fn queue_premove(
+  chess: &ChessBoard,
+  turn: Turn,
+  from: Coordinates,
+  to: Coordinates,
+  promotion: Option<PieceName>,
+) -> bool {
+  let Some(premoves) = turn.premoves else {
+    return false;
+  };
+
+  if turn.next == turn.side || chess.end != EndResult::None(true) || from == to {
+    return false;
+  }
+
+  let ours = matches!(chess.board[from.row][from.col], Some(piece) if piece.color == turn.side)
+    || premoves
+      .read()
+      .iter()
+      .any(|premove| premove.board == turn.board && premove.to == from);
+
+  if ours {
+    premoves.write().push(Premove {
+      board: turn.board,
+      from,
+      to,
+      promotion,
+    });
+  }
+
+  ours
+}
+
+/// Why no move can be made on this board right now, if there's a reason.
+fn turn_rejection(chess: &ChessBoard, turn: Turn) -> Option<String> {
+  if chess.end != EndResult::None(true) {
+    Some("This board is already decided".to_owned())
+  } else if turn.next != turn.side {
+    Some("It's not your turn".to_owned())
+  } else {
+    None
+  }
+}
+
+/// Why moving from `from` to `to` isn't allowed, shown instead of silently dropping the move.
+fn rejection_reason(chess: &ChessBoard, from: Coordinates, to: Coordinates, turn: Turn) -> String {
+  let piece = chess.board[from.row][from.col];
+
+  if let Some(reason) = turn_rejection(chess, turn) {
+    reason
+  } else if piece.map(|piece| piece.color) != Some(turn.side) {
+    "That's not your piece".to_owned()
+  } else if let Some(err @ (Error::PiecePinned | Error::KingInCheck)) = chess
+    .notation(from, to, None)
+    .and_then(|alg| chess.check_move(&alg, turn.side).err())
+  {
+    err.to_string()
+  } else {
+    let tile = ChessBoard::get_tile(to).unwrap_or_default();
+    let name = piece
+      .map(|piece| piece.name.to_string())
+      .unwrap_or_default();
+    format!("The {} can't move to {tile}", name.to_lowercase())
+  }
 }
 
 fn on_click(
-  _ev: MouseEvent,
   square: (usize, usize),
   selected: &UseState<Option<(usize, usize)>>,
   chess: &ChessBoard,
-  side: Color,
+  turn: Turn,
+  ct: &Coroutine<String>,
+  promotion_data: PromotionData,
+) {
+  let Some(from) = *selected.get() else {
+    return;
+  };
+
+  try_move(from, square, selected, chess, turn, ct, promotion_data);
+}
+
+/// Sends the move from `from` to `square`, or opens the promotion picker first. Clicks, drops and
+/// the keyboard all end up here.
+fn try_move(
+  from: (usize, usize),
+  square: (usize, usize),
+  selected: &UseState<Option<(usize, usize)>>,
+  chess: &ChessBoard,
+  turn: Turn,
   ct: &Coroutine<String>,
   PromotionData {
     promotin,
     promotion_square,
+    rejection,
   }: PromotionData,
 ) {
-  if selected.is_none() {
-    return;
-  }
   promotin.set(false);
+  rejection.set(None);
 
-  let square = Coordinates::new(square);
-
-  let attacked_piece = chess.board[square.row][square.col];
-  let selected_piece = chess.board[selected.unwrap().0][selected.unwrap().1];
-  let x = match attacked_piece {
-    Some(_) => "x",
-    None if selected_piece.unwrap().name == PieceName::PAWN && chess.en_passant == Some(square) => {
-      "x"
-    }
-    None => "",
-  };
-
-  let piece_name = match selected_piece.unwrap().name {
-    PieceName::ROOK => "R",
-    PieceName::KNIGHT => "N",
-    PieceName::BISHOP => "B",
-    PieceName::QUEEN => "Q",
-    PieceName::KING => "K",
-    PieceName::PAWN => "",
-  };
-
-  let selected_coords = Coordinates::new(selected.unwrap());
+  if from == square {
+    selected.set(None);
+    return;
+  }
 
-  let end_tile = ChessBoard::get_tile(square).unwrap();
-  let start_tile = ChessBoard::get_tile(selected_coords).unwrap();
+  let from = Coordinates::new(from);
+  let to = Coordinates::new(square);
 
-  eprintln!("{piece_name}{start_tile}{x}{end_tile}");
-  eprintln!("{:?}", chess.en_passant);
+  if queue_premove(chess, turn, from, to, None) {
+    selected.set(None);
+    return;
+  }
 
-  let mut alg = format!("{piece_name}{start_tile}{x}{end_tile}");
+  let alg = chess.notation(from, to, None);
+  let is_valid = alg.as_ref().is_some_and(|alg| {
+    turn.next == turn.side && chess.validate_move(alg, turn.side).unwrap_or(false)
+  });
 
-  if piece_name == "K"
-    && ((side == Color::White && (start_tile == "e1" && (end_tile == "g1" || end_tile == "h1")))
-      || (side == Color::Black && (start_tile == "e8" && (end_tile == "g8" || end_tile == "h8"))))
-  {
-    alg = "O-O".to_owned()
-  } else if piece_name == "K"
-    && ((side == Color::White && (start_tile == "e1" && (end_tile == "c1" || end_tile == "a1")))
-      || (side == Color::Black && (start_tile == "e8" && (end_tile == "c8" || end_tile == "a8"))))
-  {
-    alg = "O-O-O".to_owned()
+  if !is_valid {
+    // Picking another of our pieces changes the selection instead.
+    if matches!(chess.board[to.row][to.col], Some(piece) if piece.color == turn.side) {
+      selected.set(Some(square));
+    } else {
+      rejection.set(Some(rejection_reason(chess, from, to, turn)));
+      selected.set(None);
+    }
+    return;
   }
 
-  if piece_name.is_empty()
-    && ((side == Color::White && square.row == 7) || (side == Color::Black && square.row == 0))
-  {
+  let is_promotion = matches!(chess.board[from.row][from.col], Some(piece) if piece.name == PieceName::PAWN)
+    && (to.row == 0 || to.row == 7);
+
+  if is_promotion {
+    selected.set(Some((from.row, from.col)));
     promotin.set(true);
-    promotion_square.set(Some((square.row, square.col)));
+    promotion_square.set(Some(square));
     return;
   }
 
-  // eprintln!("{:?}, {:?}, {alg}", side, square);
-
-  let is_valid = chess.validate_move(&alg, side);
-
-  if is_valid.is_ok() && is_valid.unwrap() {
-    unsafe {
-      static mut COUNT: usize = 0;
-      COUNT += 1;
-      println!("{COUNT}");
-    }
-
+  if let Some(alg) = alg {
     ct.send(alg);
   }
 
-  selected.modify(|_| None)
+  selected.set(None)
 }
 
 fn promote(
@@ -125,110 +234,191 @@ fn promote(
   PromotionData {
     promotin,
     promotion_square,
+    ..
   }: PromotionData,
-  target_piece: &str,
+  target: PieceName,
 ) {
-  if promotion_square.is_none() {
-    panic!("AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA")
-  }
-  let promotion_square_uw = promotion_square.get().unwrap();
-  let attacked_piece = chess.board[promotion_square_uw.0][promotion_square_uw.1];
-  let selected_piece = chess.board[selected.unwrap().0][selected.unwrap().1];
-  let x = match attacked_piece {
-    Some(_) => "x",
-    None => "",
+  let (Some(from), Some(to)) = (*selected.get(), *promotion_square.get()) else {
+    return;
   };
 
-  let piece_name = match selected_piece.unwrap().name {
-    PieceName::PAWN => "",
-    _ => panic!("AAAAAAAAAouch"),
-  };
+  if let Some(alg) = chess.notation(Coordinates::new(from), Coordinates::new(to), Some(target)) {
+    ct.send(alg);
+  }
 
-  let target_name = match target_piece {
-    "WhiteKnight" | "BlackKnight" => "N",
-    "WhiteBishop" | "BlackBishop" => "B",
-    "WhiteRook" | "BlackRook" => "R",
-    "WhiteQueen" | "BlackQueen" => "Q",
-    "WhiteKing" | "BlackKing" => "K",
-    _ => panic!("mifaszstas {}", target_piece),
+  promotin.set(false);
+  promotion_square.set(None);
+  selected.set(None);
+}
+
+const PROMOTIONS: [(PieceName, &str); 5] = [
+  (PieceName::KNIGHT, "Knight"),
+  (PieceName::BISHOP, "Bishop"),
+  (PieceName::ROOK, "Rook"),
+  (PieceName::QUEEN, "Queen"),
+  (PieceName::KING, "King"),
+];
+
+/// Piece picked by typing its letter, like `q` for a queen.
+fn promotion_from_letter(letter: &str) -> Option<PieceName> {
+  PROMOTIONS
+    .iter()
+    .map(|(piece, _)| *piece)
+    .find(|piece| piece.letter().eq_ignore_ascii_case(letter))
+}
+
+/// Squares of a move typed as `e2e4` or `e7e8q`, with the promotion piece when one is given.
+fn typed_squares(typed: &str) -> Option<(Coordinates, Coordinates, Option<PieceName>)> {
+  if !typed.is_ascii() || !(4..=5).contains(&typed.len()) {
+    return None;
+  }
+
+  let from = ChessBoard::get_square(&typed[0..2]).ok()?;
+  let to = ChessBoard::get_square(&typed[2..4]).ok()?;
+
+  let promotion = match &typed[4..] {
+    "" => None,
+    letter => Some(promotion_from_letter(letter)?),
   };
 
-  let selected_coords = Coordinates::new(selected.unwrap());
+  Some((from, to, promotion))
+}
 
-  let end_tile = ChessBoard::get_tile(Coordinates {
-    row: promotion_square_uw.0,
-    col: promotion_square_uw.1,
-  })
-  .unwrap();
-  let start_tile = ChessBoard::get_tile(selected_coords).unwrap();
+struct KeyboardData<'a> {
+  cursor: &'a UseState<Option<(usize, usize)>>,
+  typed: &'a UseState<String>,
+}
 
-  eprintln!("{piece_name}{start_tile}{x}{end_tile}{target_name}");
+/// Keyboard move entry: arrow keys move a cursor and Enter picks the square under it, or a move
+/// typed in notation is sent on Enter. While promoting, a piece letter picks the piece.
+fn on_key(
+  ev: KeyboardEvent,
+  KeyboardData { cursor, typed }: KeyboardData,
+  selected: &UseState<Option<(usize, usize)>>,
+  chess: &ChessBoard,
+  turn: Turn,
+  ct: &Coroutine<String>,
+  promotion_data: PromotionData,
+) {
+  let key = ev.key();
+
+  if *promotion_data.promotin.get() {
+    match key {
+      Key::Character(letter) => {
+        if let Some(piece) = promotion_from_letter(&letter) {
+          promote(selected, chess, ct, promotion_data, piece);
+        }
+      }
+      Key::Escape => {
+        promotion_data.promotin.set(false);
+        selected.set(None);
+      }
+      _ => {}
+    }
+    return;
+  }
 
-  let alg = format!("{piece_name}{start_tile}{x}{end_tile}{target_name}");
+  // Rows and columns as the player sees them, so up is always towards the opponent.
+  let (up, right): (isize, isize) = match turn.side {
+    Color::White => (1, 1),
+    Color::Black => (-1, -1),
+  };
 
-  ct.send(alg);
+  let step = match key {
+    Key::ArrowUp => Some((up, 0)),
+    Key::ArrowDown => Some((-up, 0)),
+    Key::ArrowRight => Some((0, right)),
+    Key::ArrowLeft => Some((0, -right)),
+    _ => None,
+  };
 
-  promotin.set(false);
+  if let Some((rows, cols)) = step {
+    let home = match turn.side {
+      Color::White => (0, 4),
+      Color::Black => (7, 4),
+    };
+    let (row, col) = cursor.get().or(*selected.get()).unwrap_or(home);
 
-  promotion_square.set(None);
-}
+    cursor.set(Some((
+      row.saturating_add_signed(rows).min(7),
+      col.saturating_add_signed(cols).min(7),
+    )));
+    return;
+  }
 
-static VALID_MOVES: Lazy<HashMap<String, Vec<(isize, isize)>>> = Lazy::new(|| {
-  let mut map = HashMap::new();
-
-  let rook_moves = (-8..=8)
-    .map(|val| vec![(0, val), (val, 0)])
-    .filter(|el| !el.contains(&(0, 0)))
-    .reduce(|rest, next| [rest, next].concat())
-    .unwrap();
-
-  let bishop_moves = (1..=8)
-    .map(|val| vec![(val, -val), (-val, val), (val, val), (-val, -val)])
-    .reduce(|rest, next| [rest, next].concat())
-    .unwrap();
-
-  let pawn_moves = vec![
-    (1, 0),
-    (-1, 0),
-    (2, 0),
-    (-2, 0),
-    (1, 1),
-    (1, -1),
-    (-1, 1),
-    (-1, -1),
-  ];
-
-  let knight_moves = vec![
-    (2, 1),
-    (-2, 1),
-    (-2, -1),
-    (2, -1),
-    (1, 2),
-    (-1, 2),
-    (-1, -2),
-    (1, -2),
-  ];
-
-  let king_moves = vec![
-    (0, 1),
-    (1, 1),
-    (1, 0),
-    (1, -1),
-    (0, -1),
-    (-1, -1),
-    (-1, 0),
-    (-1, 1),
-  ];
-
-  map.insert("R".into(), rook_moves.clone());
-  map.insert("N".into(), knight_moves);
-  map.insert("B".into(), bishop_moves.clone());
-  map.insert("Q".into(), [rook_moves, bishop_moves].concat());
-  map.insert("K".into(), king_moves);
-  map.insert("P".into(), pawn_moves);
-
-  map
-});
+  match key {
+    Key::Enter if !typed.is_empty() => {
+      let text = typed.get().clone();
+      typed.set(String::new());
+
+      let (from, to) = match typed_squares(&text) {
+        Some((from, to, Some(piece))) => {
+          promotion_data.rejection.set(None);
+
+          if queue_premove(chess, turn, from, to, Some(piece)) {
+            return;
+          }
+
+          let alg = chess.notation(from, to, Some(piece)).unwrap_or_default();
+          if turn.next == turn.side && chess.validate_move(&alg, turn.side).unwrap_or(false) {
+            ct.send(alg);
+          } else {
+            promotion_data
+              .rejection
+              .set(Some(rejection_reason(chess, from, to, turn)));
+          }
+          return;
+        }
+        Some((from, to, None)) => ((from.row, from.col), (to.row, to.col)),
+        // Anything else is passed on as the notation the server takes, like `O-O` or `Ng1f3`.
+        None => {
+          if turn.next == turn.side && chess.validate_move(&text, turn.side).unwrap_or(false) {
+            promotion_data.rejection.set(None);
+            ct.send(text);
+          } else {
+            let reason = turn_rejection(chess, turn)
+              .unwrap_or_else(|| format!("{text} isn't a legal move here"));
+            promotion_data.rejection.set(Some(reason));
+          }
+          return;
+        }
+      };
+
+      try_move(from, to, selected, chess, turn, ct, promotion_data);
+    }
+    Key::Enter => {
+      let Some(square) = *cursor.get() else {
+        return;
+      };
+
+      match *selected.get() {
+        Some(from) => try_move(from, square, selected, chess, turn, ct, promotion_data),
+        None if chess.board[square.0][square.1].is_some() => selected.set(Some(square)),
+        None => {}
+      }
+    }
+    Key::Escape => {
+      typed.set(String::new());
+      selected.set(None);
+      promotion_data.rejection.set(None);
+
+      if let Some(premoves) = turn.premoves {
+        premoves.write().clear();
+      }
+    }
+    Key::Backspace => typed.modify(|text| {
+      let mut text = text.clone();
+      text.pop();
+      text
+    }),
+    Key::Character(text)
+      if typed.len() < 8 && text.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') =>
+    {
+      typed.modify(|typed| typed.clone() + &text)
+    }
+    _ => {}
+  }
+}
 
 #[derive(Props)]
 pub struct ChessProps<'a> {
@@ -238,6 +428,12 @@ pub struct ChessProps<'a> {
   pub onclick: Option<EventHandler<'a, MouseEvent>>,
   pub last_move: String,
   pub last: Color,
+  /// Shows the board without letting the player move, for looking at earlier positions.
+  #[props(default)]
+  pub readonly: bool,
+  /// Queue shared by all boards of the game. Without it moves can only be made on our turn.
+  #[props(default, !optional)]
+  pub premoves: Option<UseRef<Vec<Premove>>>,
 }
 
 pub fn ChessBoard<'a>(cx: Scope<'a, ChessProps>) -> Element<'a> {
@@ -267,10 +463,8 @@ pub fn ChessBoard<'a>(cx: Scope<'a, ChessProps>) -> Element<'a> {
 
   let (last_board, last_move) = (split_move.next(), split_move.next());
 
-  let client = cx
-    .use_hook(|| cx.consume_context::<Arc<Mutex<GameClient<Channel>>>>())
-    .clone()
-    .unwrap();
+  // Missing where nothing can be played, like the replay viewer.
+  let source = cx.use_hook(|| cx.consume_context::<GameSource>()).clone();
 
   if cx.props.side == Color::White {
     board.reverse();
@@ -284,49 +478,67 @@ pub fn ChessBoard<'a>(cx: Scope<'a, ChessProps>) -> Element<'a> {
 
   let promotin = use_state(cx, || false);
 
-  let piece_name = if selected.is_some() {
-    let selected = selected.get().unwrap();
+  let rejection = use_state(cx, || None::<String>);
 
-    let (col, row) = match cx.props.side {
-      Color::White => (7 - selected.0, selected.1),
-      Color::Black => (selected.0, 7 - selected.1),
-    };
+  let turn = Turn {
+    side: cx.props.side,
+    next: match cx.props.last {
+      Color::White => Color::Black,
+      Color::Black => Color::White,
+    },
+    board: cx.props.board_num,
+    premoves: cx.props.premoves.as_ref(),
+  };
 
-    match board[col][row] {
-      Some(piece) => match piece.name {
-        PieceName::ROOK => "R",
-        PieceName::KNIGHT => "N",
-        PieceName::BISHOP => "B",
-        PieceName::QUEEN => "Q",
-        PieceName::KING => "K",
-        PieceName::PAWN => "",
-      },
-      None => "",
-    }
-  } else {
-    ""
+  // Ghost arrows of the premoves on this board, from and to square centres in view coordinates.
+  let arrows = cx
+    .props
+    .premoves
+    .as_ref()
+    .map(|premoves| {
+      let view = |square: Coordinates| match cx.props.side {
+        Color::White => (square.col as f32 + 0.5, 7.5 - square.row as f32),
+        Color::Black => (7.5 - square.col as f32, square.row as f32 + 0.5),
+      };
+
+      premoves
+        .read()
+        .iter()
+        .filter(|premove| premove.board == cx.props.board_num)
+        .map(|premove| (view(premove.from), view(premove.to)))
+        .collect::<Vec<_>>()
+    })
+    .unwrap_or_default();
+  let arrows = &arrows;
+
+  let legal_moves = match *selected.get() {
+    Some(square) if !cx.props.readonly => cx
+      .props
+      .chess
+      .legal_moves(Coordinates::new(square), cx.props.side),
+    _ => vec![],
   };
 
+  let legal_moves = &legal_moves;
+
+  let checked = [Color::White, Color::Black].map(|color| cx.props.chess.in_check(color));
+
   let board_num = cx.props.board_num;
 
-  let ct: &Coroutine<String> = use_coroutine(cx, |mut rx: UnboundedReceiver<String>| async move {
-    tokio::spawn(async move {
+  let ct: &Coroutine<String> = use_coroutine(cx, |mut rx: UnboundedReceiver<String>| {
+    let rejection = rejection.to_owned();
+
+    async move {
       while let Some(alg) = rx.next().await {
-        let res = client
-          .lock()
-          .await
-          .move_piece(MovePieceRequest {
-            board: board_num,
-            alg,
-            uuid: utils::get_uuid().unwrap(),
-          })
-          .await;
-
-        println!("{res:?}");
+        let Some(source) = &source else {
+          continue;
+        };
+
+        if let Err(reason) = source.move_piece(board_num, alg).await {
+          rejection.set(Some(reason));
+        }
       }
-    })
-    .await
-    .unwrap();
+    }
   });
 
   let pre = match cx.props.side {
@@ -334,29 +546,52 @@ pub fn ChessBoard<'a>(cx: Scope<'a, ChessProps>) -> Element<'a> {
     Color::Black => "Black",
   };
 
-  let valid_promotions =
-    ["Knight", "Bishop", "Rook", "Queen", "King"].map(|piece| pre.to_owned() + piece);
+  let valid_promotions = PROMOTIONS.map(|(piece, name)| (piece, pre.to_owned() + name));
 
   let promotion_square = use_state(cx, || None::<(usize, usize)>);
 
+  let cursor = use_state(cx, || None::<(usize, usize)>);
+
+  let typed = use_state(cx, String::new);
+
   cx.render(rsx!{
-    div { class: "chess-container", onclick: |_| promotin.set(false),
+    div { class: "chess-container", tabindex: "0", onclick: |_| promotin.set(false),
+        onkeydown: move |ev| if !cx.props.readonly {
+          on_key(ev, KeyboardData { cursor, typed }, selected, cx.props.chess, turn, ct, PromotionData { promotin, promotion_square, rejection })
+        },
+        onfocusout: |_| cursor.set(None),
         match promotin.get() {
           true => rsx!{dialog {class: "promotin-dialog" , open: *promotin.get(), rsx!{
-            valid_promotions.iter().map(|name| {
+            valid_promotions.iter().map(|(piece, name)| {
               let src = IMAGES.get(name).unwrap();
-              let name = name.clone();
+              let piece = *piece;
+              let letter = piece.letter();
               rsx!(
                 img {
                   class: "piece-image",
+                  title: "{letter}",
                   src: "{src}",
-                  onclick: move |_| promote(selected, cx.props.chess, ct, PromotionData{ promotion_square, promotin }, &name),
+                  onclick: move |_| promote(selected, cx.props.chess, ct, PromotionData{ promotion_square, promotin, rejection }, piece),
                 }
               )
             })
           }}},
           false => rsx!(""),
         },
+        rejection.get().as_ref().map(|reason| rsx!(div { class: "move-rejection", "{reason}" })),
+        (!typed.is_empty()).then(|| rsx!(div { class: "move-input", "{typed}" })),
+        (!arrows.is_empty()).then(|| rsx!(svg { class: "premove-arrows", view_box: "0 0 8 8",
+            defs {
+                marker { id: "premove-head", marker_width: "3", marker_height: "3", ref_x: "1.5", ref_y: "1.5", orient: "auto",
+                    polygon { points: "0 0, 3 1.5, 0 3" }
+                }
+            }
+            arrows.iter().map(|((x1, y1), (x2, y2))| rsx!(line {
+                x1: "{x1}", y1: "{y1}", x2: "{x2}", y2: "{y2}",
+                stroke_width: "0.15",
+                marker_end: "url(#premove-head)",
+            }))
+        })),
         board.iter().enumerate().map(|(row_idx, row)| {
             let map = IMAGES.clone();
             rsx!(
@@ -369,31 +604,15 @@ pub fn ChessBoard<'a>(cx: Scope<'a, ChessProps>) -> Element<'a> {
                         };
                         let real_row = if cx.props.side == Color::White {7-row_idx} else {row_idx};
                         let real_col = if cx.props.side == Color::White {cell_idx} else {7-cell_idx};
-                        let valid_move = if selected.is_some() && VALID_MOVES.get(if piece_name.is_empty() {"P"} else {piece_name}).unwrap().iter().any(|la_move| {
-                            let square = selected.unwrap();
-                            square.0.saturating_add_signed(la_move.0) == real_row && square.1.saturating_add_signed(la_move.1) == real_col
-                        }) {
-                            let end_tile = ChessBoard::get_tile(Coordinates::new((real_row, real_col))).unwrap();
-
-                            let starting_tile = ChessBoard::get_tile(Coordinates::new(selected.unwrap())).unwrap();
-
-                            let x = if cell.is_some() || piece_name.is_empty() && cx.props.chess.en_passant == Some(Coordinates::new((real_row, real_col))) {
-                                "x"
-                            } else {
-                              ""
-                            };
-
-                            let move_str = format!("{piece_name}{starting_tile}{x}{end_tile}");
-
-                            let valid = cx.props.chess.validate_move(&move_str, cx.props.side);
-
-                            match valid {
-                                Ok(true) => "valid",
-                                _ => ""
-                            }
-
-                        } else {
-                            ""
+                        let legal_move = legal_moves.iter().find(|(to, _)| to.row == real_row && to.col == real_col);
+                        let valid_move = match legal_move {
+                            Some((_, alg)) if alg.contains('x') => "valid capture",
+                            Some(_) => "valid",
+                            None => "",
+                        };
+                        let check = match cell {
+                            Some(piece) if piece.name == PieceName::KING && checked[piece.color as usize] => "check",
+                            _ => "",
                         };
 
                         let is_last_board = match last_board {
@@ -424,11 +643,13 @@ pub fn ChessBoard<'a>(cx: Scope<'a, ChessProps>) -> Element<'a> {
                             None => false
                         };
 
-                        let class = format!("chess-cell {} {} {} {}", 
+                        let class = format!("chess-cell {} {} {} {} {} {}", 
                             if (row_idx+cell_idx)%2==0 {"light"} else {"dark"}, 
                             if selected.is_some() && selected.unwrap().0 == real_row && selected.unwrap().1 == real_col {"selected"} else {""},
                             valid_move,
-                            if is_last_board && is_last_move {"last"} else {""}
+                            if is_last_board && is_last_move {"last"} else {""},
+                            check,
+                            if *cursor.get() == Some((real_row, real_col)) {"cursor"} else {""}
                         );
                         if !piece.is_empty() {
                             let src = map.get(&piece).unwrap().to_owned();
@@ -437,8 +658,23 @@ pub fn ChessBoard<'a>(cx: Scope<'a, ChessProps>) -> Element<'a> {
                                     class: "{class}",
                                     onclick: move |ev| { if let Some(onclick) = cx.props.onclick.as_ref() {
                                         onclick.call(ev.clone());
-                                        }; on_piece_click(ev, (real_row, real_col), selected, cx.props.chess, cx.props.side, ct, PromotionData { promotin, promotion_square })},
+                                        }; if !cx.props.readonly { on_piece_click((real_row, real_col), selected, cx.props.chess, turn, ct, PromotionData { promotin, promotion_square, rejection }) }},
+                                    prevent_default: "ondragover ondrop",
+                                    ondragover: |_| {},
+                                    ondrop: move |_| if !cx.props.readonly {
+                                        if let Some(from) = *selected.get() {
+                                            if from != (real_row, real_col) {
+                                                try_move(from, (real_row, real_col), selected, cx.props.chess, turn, ct, PromotionData { promotin, promotion_square, rejection })
+                                            }
+                                        }
+                                    },
                                     img {
+                                        draggable: "{!cx.props.readonly}",
+                                        prevent_default: "ondragover ondrop",
+                                        ondragstart: move |_| if !cx.props.readonly {
+                                            rejection.set(None);
+                                            selected.set(Some((real_row, real_col)));
+                                        },
                                         src: "{src}",
                                         height: "100%",
                                         width: "100%",
@@ -452,7 +688,14 @@ pub fn ChessBoard<'a>(cx: Scope<'a, ChessProps>) -> Element<'a> {
                                     class: "{class}", 
                                     onclick: move |ev| {if let Some(onclick) = cx.props.onclick.as_ref() {
                                         onclick.call(ev.clone());
-                                    }; on_click(ev, (real_row, real_col), selected, cx.props.chess, cx.props.side, ct, PromotionData { promotin, promotion_square })},
+                                    }; if !cx.props.readonly { on_click((real_row, real_col), selected, cx.props.chess, turn, ct, PromotionData { promotin, promotion_square, rejection }) }},
+                                    prevent_default: "ondragover ondrop",
+                                    ondragover: |_| {},
+                                    ondrop: move |_| if !cx.props.readonly {
+                                        if let Some(from) = *selected.get() {
+                                            try_move(from, (real_row, real_col), selected, cx.props.chess, turn, ct, PromotionData { promotin, promotion_square, rejection })
+                                        }
+                                    },
                                 }
                             )
                         }
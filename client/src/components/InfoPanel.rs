@@ -1 +1,117 @@
+use dioxus::{html::input_data::keyboard_types::Key, prelude::*};
+use helpers::{
+  chesstactoe::{ChatMessage, Color},
+  tictactoe::PlayedMove,
+};
+use utils::source::GameSource;
 
+/// Same as the server's default limit, longer messages get rejected.
+const MAX_MESSAGE_LENGTH: usize = 300;
+
+#[derive(Props, PartialEq)]
+pub struct InfoPanelProps {
+  /// Filled by the board subscription in `TicBoard`.
+  pub chat: UseRef<Vec<ChatMessage>>,
+  pub history: UseRef<Vec<PlayedMove>>,
+  /// Clicking a move shows its position on the boards until this is cleared.
+  pub preview: UseState<Option<usize>>,
+}
+
+pub fn InfoPanel(cx: Scope<InfoPanelProps>) -> Element {
+  let source = &*cx.use_hook(|| cx.consume_context::<GameSource>().unwrap());
+
+  let message = use_state(cx, String::new);
+  let error = use_state(cx, || None::<String>);
+
+  let send = move || {
+    let text = message.get().trim().to_owned();
+
+    if text.is_empty() {
+      return;
+    }
+
+    let source = source.clone();
+    let error = error.clone();
+
+    message.set(String::new());
+
+    cx.spawn(async move {
+      match source.send_chat(text).await {
+        Ok(_) => error.set(None),
+        Err(reason) => error.set(Some(reason)),
+      }
+    });
+  };
+
+  // Boards are numbered from 1 here, the way players see them.
+  let moves: Vec<String> = cx
+    .props
+    .history
+    .read()
+    .iter()
+    .map(|played| format!("{}: {}", played.board + 1, played.san))
+    .collect();
+
+  let preview = &cx.props.preview;
+
+  cx.render(rsx! {
+    div { class: "info-panel",
+        div { class: "move-list",
+            moves.chunks(2).enumerate().map(|(number, pair)| {
+                rsx!(div { class: "move-row",
+                    span { class: "move-number", "{number + 1}." }
+                    pair.iter().enumerate().map(move |(offset, san)| {
+                        let index = number * 2 + offset;
+                        let class = if *preview.get() == Some(index) { "move selected" } else { "move" };
+                        rsx!(span { class: "{class}", onclick: move |_| preview.set(Some(index)), "{san}" })
+                    })
+                })
+            })
+        }
+        if source.is_local() {
+            rsx!(div { class: "local-controls",
+                button {
+                    class: "join-lobby-button",
+                    onclick: move |_| {
+                        preview.set(None);
+                        error.set(source.undo().err());
+                    },
+                    "Undo"
+                }
+            })
+        }
+        div { class: "chat-messages",
+            cx.props.chat.read().iter().rev().map(|message| {
+                let class = match Color::from_i32(message.color) {
+                    _ if message.username.is_empty() => "chat-message notice",
+                    Some(Color::Black) => "chat-message black",
+                    _ => "chat-message white",
+                };
+                rsx!(div { class: "{class}",
+                    span { class: "chat-username", "{message.username}" }
+                    " {message.text}"
+                })
+            })
+        }
+        if let Some(error) = error.get() {
+            rsx!(div { class: "login-error", "{error}" })
+        }
+        if !source.is_local() {
+            rsx!(div { class: "chat-input",
+                input {
+                    value: "{message}",
+                    maxlength: "{MAX_MESSAGE_LENGTH}",
+                    placeholder: "Say something",
+                    oninput: move |ev| { message.set(ev.value.clone()) },
+                    onkeydown: move |ev| {
+                        if ev.key() == Key::Enter {
+                            send()
+                        }
+                    }
+                }
+                button { class: "join-lobby-button", onclick: move |_| send(), "Send" }
+            })
+        }
+    }
+  })
+}
@@ -0,0 +1,37 @@
+use dioxus::prelude::*;
+
+#[derive(Props, PartialEq)]
+pub struct ServerPickerProps {
+  pub server: UseState<String>,
+}
+
+pub fn ServerPicker(cx: Scope<ServerPickerProps>) -> Element {
+  let recent_servers = utils::get_config().recent_servers;
+
+  let input = use_state(cx, || cx.props.server.get().clone());
+
+  cx.render(rsx! {
+    div { class: "server-picker",
+        input {
+            class: "login-input",
+            list: "recent-servers",
+            value: "{input}",
+            oninput: move |ev| { input.set(ev.value.clone()) },
+            placeholder: "Server"
+        }
+        datalist { id: "recent-servers",
+            recent_servers.iter().map(|server| rsx!(option { value: "{server}" }))
+        }
+        button {
+            class: "join-lobby-button",
+            onclick: move |_| {
+                let server = input.get().trim().to_owned();
+                if !server.is_empty() {
+                    cx.props.server.set(server)
+                }
+            },
+            "Connect"
+        }
+    }
+  })
+}
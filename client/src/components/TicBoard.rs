@@ -1,21 +1,29 @@
-use std::sync::Arc;
-
-use base64::Engine;
 use dioxus::prelude::*;
 use dioxus_free_icons::{icons::io_icons::IoArrowBack, Icon};
 
 use helpers::{
-  chesstactoe::{chess::EndResult, game_client::GameClient, Color, SubscribeBoardRequest},
-  tictactoe::TicTacToe,
+  chesstactoe::{tic_tac_toe, ChatMessage, Color},
+  tictactoe::{PlayedMove, TicTacToe},
+};
+use utils::source::GameSource;
+
+use crate::components::{
+  ChessBoard::{next_premove, ChessBoard, Premove},
+  TicGrid::TicGrid,
 };
-use once_cell::sync::Lazy;
-use tokio::sync::Mutex;
-use tonic::transport::Channel;
 
-use crate::components::ChessBoard::ChessBoard;
+#[derive(Props, PartialEq)]
+pub struct TicBoardProps {
+  /// Chat messages arrive on the board subscription, they're collected here for the `InfoPanel`.
+  pub chat: UseRef<Vec<ChatMessage>>,
+  /// Replayed from the move list the server sends with every update.
+  pub history: UseRef<Vec<PlayedMove>>,
+  /// Move of `history` whose position is shown instead of the live game.
+  pub preview: UseState<Option<usize>>,
+}
 
-pub fn TicBoard(cx: Scope) -> Element {
-  let client = cx.use_hook(|| cx.consume_context::<Arc<Mutex<GameClient<Channel>>>>());
+pub fn TicBoard(cx: Scope<TicBoardProps>) -> Element {
+  let source = &*cx.use_hook(|| cx.consume_context::<GameSource>());
 
   let side = use_state(cx, || Color::White as i32);
   let set_side = side.setter();
@@ -29,42 +37,160 @@ pub fn TicBoard(cx: Scope) -> Element {
   let next = use_state(cx, || Color::White as i32);
   let set_next = next.setter();
 
-  let client = client.clone();
+  let result = use_state(cx, || None::<tic_tac_toe::EndResult>);
+  let set_result = result.setter();
+
+  // Colour asked to accept a rematch, if someone offered one.
+  let rematch = use_state(cx, || None::<i32>);
+  let set_rematch = rematch.setter();
+
+  let selected_board = use_state(cx, || None::<usize>);
+  let set_selected_board = selected_board.setter();
+
+  let premoves = use_ref(cx, Vec::<Premove>::new);
+
+  let rematch_source = source;
+
+  let rematch_label = match source {
+    Some(source) if source.is_local() => "New game",
+    _ => "Rematch",
+  };
+
+  let source = source.clone();
+
+  let chat = cx.props.chat.clone();
+  let history = cx.props.history.clone();
+  let preview = cx.props.preview.clone();
+  let queued = premoves.clone();
 
   use_future(cx, (), |_| async move {
-    if client.is_some() {
-      let mut res = client
-        .unwrap()
-        .lock()
-        .await
-        .subscribe_board(SubscribeBoardRequest {
-          uuid: utils::get_uuid().unwrap(),
-        })
-        .await
-        .unwrap()
-        .into_inner();
-
-      while let Ok(Some(msg)) = res.message().await {
-        // println!("{msg:?}");
+    if let Some(source) = source {
+      let Ok(mut updates) = source.subscribe().await else {
+        return;
+      };
+
+      // Premoves only go out after a new move, not on other updates like chat.
+      let mut seen_moves = 0;
+
+      while let Some(msg) = updates.next().await {
+        // The server moved this stream on to a rematch.
+        if !msg.seat.is_empty() {
+          utils::set_uuid(&msg.seat);
+          set_selected_board(None);
+          preview.set(None);
+          queued.write().clear();
+        }
+
+        let moves = &msg.game.as_ref().unwrap().moves;
+        history.set(TicTacToe::replay(moves.iter().map(String::as_str)).unwrap_or_default());
 
         set_board(Some(TicTacToe::from(msg.game.as_ref().unwrap().clone())));
         set_side(msg.color);
         set_last_move(msg.game.as_ref().unwrap().last_move.clone());
         set_next(msg.game.as_ref().unwrap().next);
+        set_result(msg.game.as_ref().unwrap().end_result.clone());
+        set_rematch(
+          msg
+            .request
+            .as_ref()
+            .and_then(|request| request.rematch.as_ref())
+            .map(|rematch| rematch.requestee),
+        );
+
+        if !msg.chat.is_empty() {
+          chat.write().extend(msg.chat);
+        }
+
+        // Shown among the chat, without a username.
+        if !msg.notice.is_empty() {
+          chat.write().push(ChatMessage {
+            text: msg.notice.clone(),
+            ..Default::default()
+          });
+        }
+
+        let game = msg.game.as_ref().unwrap();
+        let new_move = game.moves.len() != seen_moves;
+        seen_moves = game.moves.len();
+
+        if !matches!(
+          game.end_result,
+          Some(tic_tac_toe::EndResult::None(_)) | None
+        ) {
+          queued.write().clear();
+        } else if game.next == msg.color && new_move {
+          let position = TicTacToe::from(game.clone());
+
+          if let Some((board, alg)) =
+            next_premove(&queued, &position, Color::from_i32(msg.color).unwrap())
+          {
+            if source.move_piece(board, alg).await.is_err() {
+              queued.write().clear();
+            }
+          }
+        }
       }
     }
   });
 
-  let selected_board = use_state(cx, || None::<usize>);
+  let send_rematch = move |decline: bool| {
+    let Some(source) = rematch_source.clone() else {
+      return;
+    };
+
+    cx.spawn(async move {
+      if let Err(reason) = source.rematch(decline).await {
+        tracing::warn!(%reason, "Rematch failed");
+      }
+    });
+  };
 
-  static O: Lazy<String> = Lazy::new(|| {
-    base64::engine::general_purpose::STANDARD.encode(include_bytes!("../assets/Blue_O.svg"))
+  let result_text = match result.get() {
+    Some(tic_tac_toe::EndResult::Color(color)) => Some(format!(
+      "{} won",
+      Color::from_i32(*color).unwrap_or(Color::White)
+    )),
+    Some(tic_tac_toe::EndResult::Draw(_)) => Some("Draw".to_owned()),
+    _ => None,
+  };
+
+  let previewed = cx
+    .props
+    .preview
+    .get()
+    .and_then(|index| cx.props.history.read().get(index).cloned());
+
+  let readonly = previewed.is_some();
+
+  let (shown_move, last) = match &previewed {
+    Some(played) => (format!("{} {}", played.board, played.alg), played.color),
+    None => (
+      (*last_move).to_string(),
+      Color::from_i32(1 - **next).unwrap(),
+    ),
+  };
+
+  // Kept in a hook so the elements below can borrow them.
+  let shown = cx.use_hook(|| (None, String::new()));
+  *shown = (previewed, shown_move);
+  let (previewed, shown_move) = &*shown;
+
+  let banner = previewed.as_ref().map(|_| {
+    rsx!(div { class: "preview-banner",
+        "Viewing an earlier position"
+        button { class: "join-lobby-button", onclick: move |_| cx.props.preview.set(None), "Back to the game" }
+    })
   });
-  static X: Lazy<String> = Lazy::new(|| {
-    base64::engine::general_purpose::STANDARD.encode(include_bytes!("../assets/Red_X.svg"))
+
+  let queued = premoves.read().len();
+  let premove_bar = (queued > 0 && !readonly).then(|| {
+    rsx!(div { class: "premove-bar",
+        "{queued} premove(s) queued"
+        button { class: "join-lobby-button", onclick: move |_| premoves.write().clear(), "Cancel" }
+    })
   });
 
-  match board.get() {
+  match previewed.as_ref().map(|played| &played.position).or(board.get().as_ref()) {
     Some(board) => match selected_board.get() {
         Some(board_num) => cx.render(rsx!{
             div { class: "board-view",
@@ -72,54 +198,50 @@ pub fn TicBoard(cx: Scope) -> Element {
                     Icon { width: 50, height: 50, icon: IoArrowBack }
                 }
                 ChessBoard {
-                    last: Color::from_i32(1 - **next).unwrap(),
+                    last: last,
                     side: Color::from_i32(**side).unwrap(),
                     chess: &board.chesses[board_num / 3][board_num % 3],
                     board_num: (*board_num).try_into().unwrap(),
-                    last_move: (*last_move).to_string()
+                    last_move: shown_move.clone(),
+                    readonly: readonly,
+                    premoves: Some(premoves.clone())
                 }
+                banner
+                premove_bar
             }
         }),
         None => cx.render(rsx!{
             div { class: "tic-container",
-                (0..3).map(|col| {
-              let o_src = O.clone();
-              let x_src = X.clone();
-              rsx!{
-                div {
-                  class: "tic-col",
-                  (0..3).map(|row| {
-                    let class = format!("tic-cell {}", if (col+row)%2 == 1 {"light"} else {"dark"});
-                    let chess_board = rsx!(ChessBoard {last: Color::from_i32(1-**next).unwrap(), onclick: move |_| {selected_board.set(Some(col*3+row))}, side: Color::from_i32(**side).unwrap(), chess: &board.chesses[col][row], board_num: (col*3+row).try_into().unwrap(), last_move: (*last_move).to_string()});
-                    let o_src = format!(
-                      "data:image/svg+xml;base64, {}",
-                      o_src.clone()
-                    );
-                    let x_src = format!(
-                      "data:image/svg+xml;base64, {}",
-                      x_src.clone()
-                    );
-                    let child = match &board.chesses[col][row].end {
-                      EndResult::Color(color) => {
-                        match Color::from_i32(*color).unwrap() {
-                          Color::White => rsx!(img { class: "won_img", src: "{x_src}" }),
-                          Color::Black => rsx!(img { class: "won_img", src: "{o_src}" })
+                banner,
+                premove_bar,
+                if let Some(text) = result_text.as_ref().filter(|_| !readonly) {
+                    rsx!(dialog { class: "game-result-dialog", open: true,
+                        div { "{text}" }
+                        match rematch.get() {
+                            Some(requestee) if *requestee == **side => rsx!(div { class: "rematch-offer",
+                                "Your opponent wants a rematch"
+                                button { class: "join-lobby-button", onclick: move |_| send_rematch(false), "Accept" }
+                                button { class: "join-lobby-button", onclick: move |_| send_rematch(true), "Decline" }
+                            }),
+                            Some(_) => rsx!(div { class: "rematch-offer",
+                                "Rematch offered"
+                                button { class: "join-lobby-button", onclick: move |_| send_rematch(true), "Cancel" }
+                            }),
+                            None => rsx!(div { class: "rematch-offer",
+                                button { onclick: move |_| send_rematch(false), "{rematch_label}" }
+                            }),
                         }
-                      },
-                      EndResult::Draw(_) => rsx!(""),
-                      EndResult::None(_) => rsx!(""),
-                    };
-                    rsx!{
-                      div {
-                        class: "{class}",
-                        child,
-                        chess_board
-                      }
-                    }
-                  })
+                    })
+                }
+                TicGrid {
+                    game: board,
+                    side: Color::from_i32(**side).unwrap(),
+                    last: last,
+                    last_move: shown_move.clone(),
+                    readonly: readonly,
+                    premoves: Some(premoves.clone()),
+                    onselect: move |board_num| selected_board.set(Some(board_num))
                 }
-              }
-            })
             }
         }),
     } ,
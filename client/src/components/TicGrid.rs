@@ -0,0 +1,94 @@
+use base64::Engine;
+use dioxus::prelude::*;
+use helpers::{
+  chesstactoe::{chess::EndResult, Color},
+  tictactoe::TicTacToe,
+};
+use once_cell::sync::Lazy;
+
+use crate::components::ChessBoard::{ChessBoard, Premove};
+
+#[derive(Props)]
+pub struct TicGridProps<'a> {
+  pub game: &'a TicTacToe,
+  pub side: Color,
+  /// Colour that played `last_move`.
+  pub last: Color,
+  pub last_move: String,
+  #[props(default)]
+  pub readonly: bool,
+  #[props(default, !optional)]
+  pub premoves: Option<UseRef<Vec<Premove>>>,
+  /// Board drawn out, like one a replay just saw decided.
+  #[props(default, !optional)]
+  pub highlight: Option<usize>,
+  /// Called with the number of the board clicked.
+  pub onselect: EventHandler<'a, usize>,
+}
+
+/// The nine boards, with the X or O of each decided one on top.
+pub fn TicGrid<'a>(cx: Scope<'a, TicGridProps<'a>>) -> Element<'a> {
+  static O: Lazy<String> = Lazy::new(|| {
+    base64::engine::general_purpose::STANDARD.encode(include_bytes!("../assets/Blue_O.svg"))
+  });
+  static X: Lazy<String> = Lazy::new(|| {
+    base64::engine::general_purpose::STANDARD.encode(include_bytes!("../assets/Red_X.svg"))
+  });
+
+  let board = cx.props.game;
+
+  cx.render(rsx! {
+    (0..3).map(|col| {
+      let o_src = O.clone();
+      let x_src = X.clone();
+      rsx!{
+        div {
+          class: "tic-col",
+          (0..3).map(|row| {
+            let board_num = col * 3 + row;
+            let class = format!(
+              "tic-cell {} {}",
+              if (col+row)%2 == 1 {"light"} else {"dark"},
+              if cx.props.highlight == Some(board_num) {"decided"} else {""}
+            );
+            let chess_board = rsx!(ChessBoard {
+              last: cx.props.last,
+              onclick: move |_| cx.props.onselect.call(board_num),
+              side: cx.props.side,
+              chess: &board.chesses[col][row],
+              board_num: board_num.try_into().unwrap(),
+              last_move: cx.props.last_move.clone(),
+              readonly: cx.props.readonly,
+              premoves: cx.props.premoves.clone()
+            });
+            let o_src = format!(
+              "data:image/svg+xml;base64, {}",
+              o_src.clone()
+            );
+            let x_src = format!(
+              "data:image/svg+xml;base64, {}",
+              x_src.clone()
+            );
+            let child = match &board.chesses[col][row].end {
+              EndResult::Color(color) => {
+                match Color::from_i32(*color).unwrap() {
+                  Color::White => rsx!(img { class: "won_img", src: "{x_src}" }),
+                  Color::Black => rsx!(img { class: "won_img", src: "{o_src}" })
+                }
+              },
+              EndResult::Draw(_) => rsx!(""),
+              EndResult::None(_) => rsx!(""),
+            };
+            rsx!{
+              div {
+                class: "{class}",
+                child,
+                chess_board
+              }
+            }
+          })
+        }
+      }
+    })
+  })
+}
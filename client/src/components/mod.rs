@@ -2,5 +2,7 @@ pub mod ChessBoard;
 pub mod GameButton;
 pub mod InfoPanel;
 pub mod Piece;
+pub mod ServerPicker;
 pub mod TicBoard;
+pub mod TicGrid;
 pub mod Timer;
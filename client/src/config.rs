@@ -0,0 +1,58 @@
+//! Client settings. Where they are read from and remembered depends on the platform, see
+//! [`crate::platform::load_config`].
+
+use std::{error::Error, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_SERVER: &str = "http://localhost:50051";
+
+const MAX_RECENT_SERVERS: usize = 5;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClientConfig {
+  pub server: String,
+  /// Servers connected to before, most recent first.
+  pub recent_servers: Vec<String>,
+  /// Time controls offered when making a lobby, as (initial, increment) seconds.
+  pub time_controls: Vec<(u32, u32)>,
+  /// Which events to log, like `info,client=debug`.
+  pub log: String,
+  /// File the config was read from on desktop.
+  #[serde(skip)]
+  pub path: Option<PathBuf>,
+}
+
+impl Default for ClientConfig {
+  fn default() -> Self {
+    ClientConfig {
+      server: DEFAULT_SERVER.to_owned(),
+      recent_servers: vec![],
+      time_controls: crate::TIME_CONTROLS.to_vec(),
+      log: "info".to_owned(),
+      path: None,
+    }
+  }
+}
+
+impl ClientConfig {
+  /// Parses a saved config, falling back to the defaults if it's invalid.
+  pub fn parse(saved: &str) -> Self {
+    toml::from_str(saved).unwrap_or_else(|err| {
+      tracing::warn!(%err, "Ignoring invalid client config");
+      ClientConfig::default()
+    })
+  }
+
+  /// Makes `server` the one to connect to next time, and saves the config.
+  pub fn remember_server(&mut self, server: &str) -> Result<(), Box<dyn Error>> {
+    self.server = server.to_owned();
+
+    self.recent_servers.retain(|recent| recent != server);
+    self.recent_servers.insert(0, server.to_owned());
+    self.recent_servers.truncate(MAX_RECENT_SERVERS);
+
+    crate::platform::save_config(self)
+  }
+}
@@ -2,24 +2,36 @@
 
 use std::sync::Arc;
 
+use components::ServerPicker::ServerPicker;
 use dioxus::prelude::*;
-use dioxus_desktop::{
-  tao::menu::{MenuBar, MenuItem},
-  use_window, Config, WindowBuilder,
-};
 use dioxus_router::{Route, Router};
-use helpers::chesstactoe::game_client::GameClient;
+use helpers::{
+  auth::Session,
+  chesstactoe::{auth_client::AuthClient, game_client::GameClient},
+};
 use include_dir::{include_dir, File};
-use pages::{GameScreen::GameScreen, MainScreen::MainScreen};
+use pages::{
+  GameScreen::GameScreen, LobbyBrowser::LobbyBrowser, LocalGame::LocalGame,
+  LoginScreen::LoginScreen, MainScreen::MainScreen, Replay::{Replay, ReplayList},
+};
 use tokio::sync::Mutex;
+use utils::platform;
 
 mod components;
 mod pages;
 
-const URL: &str = "http://localhost:50051";
-
+#[cfg(not(target_arch = "wasm32"))]
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+  use dioxus_desktop::{
+    tao::menu::{MenuBar, MenuItem},
+    Config, WindowBuilder,
+  };
+
+  let config = platform::load_config();
+  platform::init_logging(&config.log);
+  utils::set_config(config);
+
   // dioxus_desktop::launch(app);
   let mut menu = MenuBar::new();
 
@@ -40,10 +52,35 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
   Ok(())
 }
 
+#[cfg(target_arch = "wasm32")]
+fn main() {
+  let config = platform::load_config();
+  platform::init_logging(&config.log);
+  utils::set_config(config);
+
+  dioxus_web::launch(app);
+}
+
 fn app(cx: Scope) -> Element {
-  let client = use_future(cx, (), |_| async move { GameClient::connect(URL).await });
+  let server = use_state(cx, || utils::get_config().server);
+
+  let client = use_future(cx, (server.get(),), |(server,)| async move {
+    let channel = platform::connect(&server).await?;
 
-  use_window(cx).hide_menu();
+    utils::remember_server(&server);
+
+    Ok::<_, String>(channel)
+  });
+
+  let session = cx.use_hook(Session::default);
+
+  let logged_in = use_state(cx, || false);
+
+  // Hot-seat game started before logging in, for when there's no server to play on.
+  let offline = use_state(cx, || false);
+
+  #[cfg(not(target_arch = "wasm32"))]
+  dioxus_desktop::use_window(cx).hide_menu();
 
   let style = include_dir!("$CARGO_MANIFEST_DIR/src/components/styles/")
     .files()
@@ -51,23 +88,61 @@ fn app(cx: Scope) -> Element {
       last + next.contents_utf8().unwrap()
     });
 
+  if *offline.get() {
+    return cx.render(rsx! {
+        style { "{style}" }
+        LocalGame { on_leave: move |_| offline.set(false) }
+    });
+  }
+
+  let play_offline = rsx!(button { class: "join-lobby-button", onclick: move |_| offline.set(true), "Play offline" });
+
   match client.value() {
     Some(client) => match client {
-      Ok(client) => {
-        cx.provide_context(Arc::new(Mutex::new(client.clone())));
+      Ok(channel) => {
+        cx.provide_context(Arc::new(Mutex::new(GameClient::with_interceptor(
+          channel.clone(),
+          session.clone(),
+        ))));
+        cx.provide_context(Arc::new(Mutex::new(AuthClient::new(channel.clone()))));
+        cx.provide_context(session.clone());
+
+        if !logged_in.get() {
+          return cx.render(rsx! {
+              style { "{style}" }
+              LoginScreen { logged_in: logged_in.clone(), server: server.clone() }
+              div { class: "main-menu", play_offline }
+          });
+        }
 
         cx.render(rsx! {
             style { "{style}" }
             Router {
                 Route { to: "/", MainScreen {} }
+                Route { to: "/lobbies", LobbyBrowser {} }
                 Route { to: "/game", GameScreen {} }
                 Route { to: "/game/new", GameScreen {} }
                 Route { to: "/game/:id", GameScreen {} }
+                Route { to: "/local", LocalGame {} }
+                Route { to: "/replay", ReplayList {} }
+                Route { to: "/replay/:id", Replay {} }
             }
         })
       }
-      Err(_err) => cx.render(rsx!("Server not found (probably)")),
+      Err(err) => cx.render(rsx! {
+          style { "{style}" }
+          div { class: "main-menu",
+              ServerPicker { server: server.clone() }
+              div { class: "login-error", "Couldn't connect to {server}: {err}" }
+              play_offline
+          }
+      }),
     },
-    None => cx.render(rsx!("Still connecting")),
+    None => cx.render(rsx!(
+        div { class: "main-menu",
+            "Connecting to {server}"
+            play_offline
+        }
+    )),
   }
 }
@@ -1,23 +1,50 @@
-use std::sync::Arc;
-
 use dioxus::prelude::*;
-use dioxus_desktop::tao::clipboard;
 use dioxus_router::{use_route, use_router};
 use helpers::chesstactoe::{
-  game_client::GameClient, JoinLobbyRequest, JoinRequest, MakeLobbyRequest,
+  GameSettings, JoinLobbyRequest, JoinRequest, LeaveQueueRequest, MakeLobbyRequest, TimeControl,
 };
 
-use tokio::sync::Mutex;
-use tonic::transport::Channel;
+use utils::{platform, source::GameSource};
 
-use crate::components::TicBoard::TicBoard;
+use crate::components::{InfoPanel::InfoPanel, TicBoard::TicBoard};
 
 pub fn GameScreen(cx: Scope) -> Element {
-  let client = cx.use_hook(|| cx.consume_context::<Arc<Mutex<GameClient<Channel>>>>());
+  let client = cx.use_hook(|| cx.consume_context::<utils::Client>());
+
+  // The board components play through this.
+  cx.use_hook(|| {
+    client
+      .clone()
+      .map(|client| cx.provide_context(GameSource::Remote(client)))
+  });
+
+  let route = use_route(cx);
+
+  let lobby_code = route.parse_segment_or_404::<String>("id");
+
+  let is_new_lobby = route.last_segment() == Some("new");
+
+  let query_param = |name: &str| {
+    route
+      .query_param(name)
+      .and_then(|value| value.parse::<u32>().ok())
+      .unwrap_or(0)
+  };
+
+  let settings = GameSettings {
+    time_control: Some(TimeControl {
+      initial: query_param("initial"),
+      increment: query_param("increment"),
+    }),
+    rated: route.query_param("rated").as_deref() == Some("true"),
+    ..Default::default()
+  };
 
-  let lobby_code = use_route(cx).parse_segment_or_404::<String>("id");
+  let public = route.query_param("public").as_deref() == Some("true");
 
-  let is_new_lobby = use_route(cx).last_segment() == Some("new");
+  let chat = use_ref(cx, Vec::new);
+  let history = use_ref(cx, Vec::new);
+  let preview = use_state(cx, || None);
 
   match client.is_none() {
     true => cx.render(rsx!(
@@ -27,6 +54,9 @@ pub fn GameScreen(cx: Scope) -> Element {
     )),
     false => {
       let client = client.clone().unwrap();
+      let leave_client = client.clone();
+
+      let is_queue = lobby_code.is_none() && !is_new_lobby;
 
       let invite_code = use_state(cx, || None);
       let set_invite_code = invite_code.setter();
@@ -34,16 +64,21 @@ pub fn GameScreen(cx: Scope) -> Element {
       let future = use_future(cx, (), |_| async move {
         let mut client = client.lock().await;
 
-        println!("{lobby_code:?}");
+        tracing::debug!(?lobby_code, is_new_lobby, "Looking for a game");
 
         if is_new_lobby {
-          let cli = client.make_lobby(MakeLobbyRequest {}).await;
+          let cli = client
+            .make_lobby(MakeLobbyRequest {
+              settings: Some(settings),
+              public,
+            })
+            .await;
           drop(client);
 
           match cli {
             Ok(res) => {
               let mut res = res.into_inner();
-              while let Some(response) = res.message().await.unwrap() {
+              while let Some(response) = res.message().await? {
                 utils::set_uuid(&response.join_response.unwrap().uuid);
                 set_invite_code(Some(response.room_id))
               }
@@ -60,7 +95,13 @@ pub fn GameScreen(cx: Scope) -> Element {
                 })
                 .await
             }
-            None => client.join(JoinRequest {}).await,
+            None => {
+              client
+                .join(JoinRequest {
+                  settings: Some(settings),
+                })
+                .await
+            }
           };
 
           drop(client);
@@ -68,7 +109,7 @@ pub fn GameScreen(cx: Scope) -> Element {
           match cli {
             Ok(res) => {
               let mut res = res.into_inner();
-              while let Some(response) = res.message().await.unwrap() {
+              while let Some(response) = res.message().await? {
                 utils::set_uuid(&response.uuid)
               }
               Ok(())
@@ -83,14 +124,15 @@ pub fn GameScreen(cx: Scope) -> Element {
           match res {
             Ok(_) => cx.render(rsx!(
               div {
-                class: "main-container",
-                TicBoard {},
+                class: "main-container game-layout",
+                TicBoard { chat: chat.clone(), history: history.clone(), preview: preview.clone() },
+                InfoPanel { chat: chat.clone(), history: history.clone(), preview: preview.clone() },
                 // ChessBoard {side: helpers::chesstactoe::Color::White}
               }
             )),
-            Err(_er) => cx.render(rsx! {
+            Err(er) => cx.render(rsx! {
               div {
-                "Invalid code"
+                "{er.message()}"
                 button {
                   onclick: |_ev| { use_router(cx).navigate_to("/") },
                   "Go back to main menu"
@@ -99,13 +141,28 @@ pub fn GameScreen(cx: Scope) -> Element {
             }),
           }
         }
-        None => cx.render(rsx! {"Waiting for opponent", if invite_code.is_some() {
+        None => cx.render(rsx! {"Waiting for opponent", if is_queue {
+          rsx!(button {
+            onclick: move |_| {
+              let client = leave_client.clone();
+              cx.spawn(async move {
+                if let Some(uuid) = utils::get_uuid() {
+                  client.lock().await.leave_queue(LeaveQueueRequest { uuid }).await.ok();
+                }
+              });
+              use_router(cx).navigate_to("/");
+            },
+            "Leave queue"
+          })
+        }, if invite_code.is_some() {
           let invite_code = invite_code.as_ref().unwrap();
           rsx!(div {
             class: "lobby-code",
             "{invite_code}",
-            button {
-              onclick: move |_| { clipboard::Clipboard::new().write_text(invite_code); }, "Copy"
+            if platform::HAS_CLIPBOARD {
+              rsx!(button {
+                onclick: move |_| { platform::copy_text(invite_code) }, "Copy"
+              })
             }
           })
         },}),
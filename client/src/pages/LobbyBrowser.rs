@@ -0,0 +1,95 @@
+use dioxus::prelude::*;
+use dioxus_router::use_router;
+use helpers::chesstactoe::{GameSettings, ListGamesRequest, ListLobbiesRequest};
+
+fn rated(settings: &Option<GameSettings>) -> &'static str {
+  match settings.as_ref().map(|settings| settings.rated) {
+    Some(true) => "Rated",
+    _ => "Casual",
+  }
+}
+
+fn time_control(settings: &Option<GameSettings>) -> String {
+  match settings
+    .as_ref()
+    .and_then(|settings| settings.time_control.as_ref())
+  {
+    Some(time_control) => utils::format_time_control(time_control.initial, time_control.increment),
+    None => utils::format_time_control(0, 0),
+  }
+}
+
+pub fn LobbyBrowser(cx: Scope) -> Element {
+  let client = cx
+    .use_hook(|| cx.consume_context::<utils::Client>())
+    .clone()
+    .unwrap();
+
+  let router = use_router(cx);
+
+  let listing = use_future(cx, (), |_| async move {
+    let mut client = client.lock().await;
+
+    let lobbies = client
+      .list_lobbies(ListLobbiesRequest {})
+      .await?
+      .into_inner();
+    let games = client.list_games(ListGamesRequest {}).await?.into_inner();
+
+    Ok::<_, tonic::Status>((lobbies.lobbies, games.games))
+  });
+
+  cx.render(rsx! {
+    div { class: "lobby-browser",
+        div { class: "lobby-browser-actions",
+            button { onclick: move |_| { router.navigate_to("/") }, "Back" }
+            button { onclick: move |_| { listing.restart() }, "Refresh" }
+        }
+        match listing.value() {
+            Some(Ok((lobbies, games))) => rsx!{
+                h3 { "Open lobbies" }
+                if lobbies.is_empty() {
+                    rsx!(div { class: "lobby-browser-empty", "No open lobbies" })
+                }
+                table { class: "lobby-table",
+                    lobbies.iter().map(|lobby| {
+                        let code = lobby.code.clone();
+                        let time_control = time_control(&lobby.settings);
+                        let rated = rated(&lobby.settings);
+                        rsx!(tr {
+                            key: "{lobby.code}",
+                            td { "{lobby.host_name}" }
+                            td { "{time_control}" }
+                            td { "{rated}" }
+                            td {
+                                button {
+                                    onclick: move |_| { router.navigate_to(format!("/game/{code}").as_str()) },
+                                    "Join"
+                                }
+                            }
+                        })
+                    })
+                }
+                h3 { "Ongoing games" }
+                if games.is_empty() {
+                    rsx!(div { class: "lobby-browser-empty", "No ongoing games" })
+                }
+                table { class: "lobby-table",
+                    games.iter().map(|game| {
+                        let time_control = time_control(&game.settings);
+                        let rated = rated(&game.settings);
+                        rsx!(tr {
+                            key: "{game.id}",
+                            td { "{game.white} vs {game.black}" }
+                            td { "{time_control}" }
+                            td { "{rated}" }
+                        })
+                    })
+                }
+            },
+            Some(Err(_err)) => rsx!(div { "Couldn't load lobbies" }),
+            None => rsx!(div { "Loading lobbies" }),
+        }
+    }
+  })
+}
@@ -0,0 +1,40 @@
+use dioxus::prelude::*;
+use dioxus_router::RouterContext;
+use utils::source::GameSource;
+
+use crate::components::{InfoPanel::InfoPanel, TicBoard::TicBoard};
+
+#[derive(Props)]
+pub struct LocalGameProps<'a> {
+  /// Called by the back button. Without it the button goes back to the main menu.
+  pub on_leave: Option<EventHandler<'a>>,
+}
+
+/// Two players taking turns on one machine. The game never leaves the client, so this works
+/// without a server.
+pub fn LocalGame<'a>(cx: Scope<'a, LocalGameProps<'a>>) -> Element<'a> {
+  cx.use_hook(|| cx.provide_context(GameSource::local()));
+
+  let router = use_context::<RouterContext>(cx);
+
+  let chat = use_ref(cx, Vec::new);
+  let history = use_ref(cx, Vec::new);
+  let preview = use_state(cx, || None);
+
+  let leave = move |_| match &cx.props.on_leave {
+    Some(on_leave) => on_leave.call(()),
+    None => {
+      if let Some(router) = router {
+        router.navigate_to("/");
+      }
+    }
+  };
+
+  cx.render(rsx!(
+    div { class: "main-container game-layout",
+        TicBoard { chat: chat.clone(), history: history.clone(), preview: preview.clone() }
+        InfoPanel { chat: chat.clone(), history: history.clone(), preview: preview.clone() }
+        button { class: "local-leave", onclick: leave, "Back" }
+    }
+  ))
+}
@@ -0,0 +1,76 @@
+use dioxus::prelude::*;
+use helpers::{
+  auth::Session,
+  chesstactoe::{LoginRequest, RegisterRequest},
+};
+
+use crate::components::ServerPicker::ServerPicker;
+
+#[derive(Props, PartialEq)]
+pub struct LoginProps {
+  pub logged_in: UseState<bool>,
+  pub server: UseState<String>,
+}
+
+pub fn LoginScreen(cx: Scope<LoginProps>) -> Element {
+  let auth = &*cx.use_hook(|| cx.consume_context::<utils::Auth>().unwrap());
+
+  let session = &*cx.use_hook(|| cx.consume_context::<Session>().unwrap());
+
+  let username = use_state(cx, || "".to_owned());
+  let password = use_state(cx, || "".to_owned());
+  let error = use_state(cx, || None::<String>);
+
+  let submit = move |register: bool| {
+    let auth = auth.clone();
+    let session = session.clone();
+    let logged_in = cx.props.logged_in.clone();
+    let error = error.clone();
+    let username = username.get().clone();
+    let password = password.get().clone();
+
+    cx.spawn(async move {
+      let mut auth = auth.lock().await;
+
+      let res = if register {
+        auth.register(RegisterRequest { username, password }).await
+      } else {
+        auth.login(LoginRequest { username, password }).await
+      };
+
+      match res {
+        Ok(res) => {
+          let res = res.into_inner();
+          session.set_token(&res.token);
+          utils::set_name(&res.username);
+          logged_in.set(true);
+        }
+        Err(status) => error.set(Some(status.message().to_owned())),
+      }
+    });
+  };
+
+  cx.render(rsx! {
+    div { class: "main-menu",
+        ServerPicker { server: cx.props.server.clone() }
+        input {
+            class: "login-input",
+            oninput: move |ev| { username.set(ev.value.clone()) },
+            placeholder: "Username"
+        }
+        input {
+            class: "login-input",
+            r#type: "password",
+            oninput: move |ev| { password.set(ev.value.clone()) },
+            placeholder: "Password"
+        }
+        div { class: "join-lobby-container",
+            button { onclick: move |_| submit(false), "Log in" }
+            button { class: "join-lobby-button", onclick: move |_| submit(true), "Register" }
+        }
+        if let Some(error) = error.get() {
+            rsx!(div { class: "login-error", "{error}" })
+        }
+    }
+  })
+}
@@ -1,5 +1,6 @@
 use dioxus::prelude::*;
 use dioxus_router::use_router;
+use helpers::chesstactoe::GetPlayerRequest;
 
 pub fn MainScreen(cx: Scope) -> Element {
   let router = use_router(cx);
@@ -10,11 +11,45 @@ pub fn MainScreen(cx: Scope) -> Element {
   let lobby_code = use_state(cx, || "".to_owned());
   let set_lobby_code = lobby_code.setter();
 
+  let lobby_opened = use_state(cx, || false);
+  let public = use_state(cx, || false);
+  let rated = use_state(cx, || false);
+  let time_control = use_state(cx, || 0_usize);
+  let time_controls = &*cx.use_hook(|| utils::get_config().time_controls);
+
+  let name = utils::get_name();
+
+  let client = cx
+    .use_hook(|| cx.consume_context::<utils::Client>())
+    .clone()
+    .unwrap();
+
+  let player = use_future(cx, (), |_| async move {
+    client
+      .lock()
+      .await
+      .get_player(GetPlayerRequest {
+        username: "".to_owned(),
+      })
+      .await
+      .map(|res| res.into_inner())
+  });
+
+  let rating = match player.value() {
+    Some(Ok(player)) => format!(" ({:.0})", player.rating),
+    _ => "".to_owned(),
+  };
+
   cx.render(rsx! {
     div { class: "main-menu",
+        div { class: "player-name", "Logged in as {name}{rating}" }
         button { onclick: move |_| { router.navigate_to("/game") }, "Against random opponent" }
+        button { onclick: move |_| { router.navigate_to("/game?rated=true") }, "Rated game" }
         button { onclick: move |_| { opened.set(true) }, "Join lobby" }
-        button { onclick: move |_| { router.navigate_to("/game/new") }, "Make lobby" }
+        button { onclick: move |_| { lobby_opened.set(true) }, "Make lobby" }
+        button { onclick: move |_| { router.navigate_to("/lobbies") }, "Browse lobbies" }
+        button { onclick: move |_| { router.navigate_to("/replay") }, "Replays" }
+        button { onclick: move |_| { router.navigate_to("/local") }, "Play offline" }
         match opened.get() {
             true => rsx!{dialog { 
                 class: "join-lobby-dialog",
@@ -41,6 +76,56 @@ pub fn MainScreen(cx: Scope) -> Element {
             }},
             false => rsx!("")
           }
+        match lobby_opened.get() {
+            true => rsx!{dialog {
+                class: "join-lobby-dialog",
+                open: true,
+                div {
+                    class: "join-lobby-container",
+
+                    select {
+                        onchange: move |ev| { time_control.set(ev.value.parse().unwrap_or(0)) },
+                        time_controls.iter().enumerate().map(|(idx, (initial, increment))| {
+                            let label = utils::format_time_control(*initial, *increment);
+                            rsx!(option { value: "{idx}", selected: idx == **time_control, "{label}" })
+                        })
+                    }
+                    label {
+                        class: "public-lobby-toggle",
+                        input {
+                            r#type: "checkbox",
+                            checked: "{public}",
+                            onclick: move |_| { public.set(!public.get()) },
+                        }
+                        "Public"
+                    }
+                    label {
+                        class: "public-lobby-toggle",
+                        input {
+                            r#type: "checkbox",
+                            checked: "{rated}",
+                            onclick: move |_| { rated.set(!rated.get()) },
+                        }
+                        "Rated"
+                    }
+                    button {
+                        class: "join-lobby-button",
+                        onclick: move |_ev| {
+                            let (initial, increment) = time_controls.get(**time_control).copied().unwrap_or_default();
+                            router.navigate_to(format!("/game/new?public={public}&rated={rated}&initial={initial}&increment={increment}").as_str());
+                            lobby_opened.set(false)
+                        },
+                        "Make lobby"
+                    }
+                }
+                button {
+                    class: "close-dialog-button",
+                    onclick: move |_ev| { lobby_opened.set(false) },
+                    "Close"
+                }
+            }},
+            false => rsx!("")
+          }
     }
   })
 }
@@ -0,0 +1,377 @@
+use std::time::Duration;
+
+use dioxus::{html::input_data::keyboard_types::Key, prelude::*};
+use dioxus_free_icons::{icons::io_icons::IoArrowBack, Icon};
+use dioxus_router::{use_route, use_router};
+use helpers::{
+  chesstactoe::{
+    chess::EndResult, Color, GameOutcome, GetArchivedGameRequest, ListArchivedGamesRequest,
+  },
+  tictactoe::{PlayedMove, TicTacToe},
+};
+use serde::Deserialize;
+use utils::platform;
+
+use crate::components::{ChessBoard::ChessBoard, TicGrid::TicGrid};
+
+const AUTOPLAY_DELAY: Duration = Duration::from_millis(1200);
+
+/// The part of the CLI's `export` output the viewer needs.
+#[derive(Deserialize)]
+struct Exported {
+  #[serde(default)]
+  moves: Vec<String>,
+  #[serde(default)]
+  fen: String,
+}
+
+/// Finds the exported game in a file of JSON lines, like the output of `chesstactoe-cli export`.
+fn exported_game(contents: &str) -> Option<Exported> {
+  contents
+    .lines()
+    .filter_map(|line| serde_json::from_str::<Exported>(line).ok())
+    .find(|game| !game.moves.is_empty() || !game.fen.is_empty())
+}
+
+fn outcome(outcome: GameOutcome) -> &'static str {
+  match outcome {
+    GameOutcome::WhiteWon => "1-0",
+    GameOutcome::BlackWon => "0-1",
+    GameOutcome::Drawn => "½-½",
+    GameOutcome::Abandoned => "Abandoned",
+  }
+}
+
+/// Our finished games, and loading one from an exported file.
+pub fn ReplayList(cx: Scope) -> Element {
+  let client = cx
+    .use_hook(|| cx.consume_context::<utils::Client>())
+    .clone()
+    .unwrap();
+
+  let router = use_router(cx);
+
+  let loaded = use_state(cx, || None::<Exported>);
+  let error = use_state(cx, || None::<String>);
+
+  let listing = use_future(cx, (), |_| async move {
+    client
+      .lock()
+      .await
+      .list_archived_games(ListArchivedGamesRequest::default())
+      .await
+      .map(|res| res.into_inner().games)
+  });
+
+  if let Some(game) = loaded.get() {
+    return cx.render(rsx!(ReplayViewer {
+      title: "Loaded from a file".to_owned(),
+      moves: game.moves.clone(),
+      fen: game.fen.clone(),
+      on_back: move |_| loaded.set(None),
+    }));
+  }
+
+  cx.render(rsx! {
+    div { class: "lobby-browser",
+        div { class: "lobby-browser-actions",
+            button { onclick: move |_| { router.navigate_to("/") }, "Back" }
+            button { onclick: move |_| { listing.restart() }, "Refresh" }
+        }
+        div { class: "replay-file",
+            "Open an exported game: "
+            input {
+                r#type: "file",
+                accept: ".json,.jsonl,.txt",
+                onchange: move |ev| {
+                    let Some(files) = ev.files.clone() else {
+                        return;
+                    };
+                    let loaded = loaded.clone();
+                    let error = error.clone();
+
+                    cx.spawn(async move {
+                        let Some(name) = files.files().into_iter().next() else {
+                            return;
+                        };
+
+                        match files.read_file_to_string(&name).await.as_deref().and_then(exported_game) {
+                            Some(game) => {
+                                error.set(None);
+                                loaded.set(Some(game));
+                            }
+                            None => error.set(Some(format!("{name} doesn't contain an exported game"))),
+                        }
+                    });
+                }
+            }
+        }
+        if let Some(error) = error.get() {
+            rsx!(div { class: "login-error", "{error}" })
+        }
+        h3 { "Your finished games" }
+        match listing.value() {
+            Some(Ok(games)) if games.is_empty() => rsx!(div { class: "lobby-browser-empty", "No finished games yet" }),
+            Some(Ok(games)) => rsx!(table { class: "lobby-table",
+                games.iter().map(|game| {
+                    let id = game.id.clone();
+                    let result = outcome(game.outcome());
+                    let count = game.moves.len();
+                    rsx!(tr {
+                        key: "{game.id}",
+                        td { "{game.white}" }
+                        td { "{game.black}" }
+                        td { "{result}" }
+                        td { "{count} moves" }
+                        td {
+                            button {
+                                onclick: move |_| { router.navigate_to(format!("/replay/{id}").as_str()) },
+                                "Watch"
+                            }
+                        }
+                    })
+                })
+            }),
+            Some(Err(status)) => rsx!(div { class: "login-error", "{status.message()}" }),
+            None => rsx!(div { "Loading games" }),
+        }
+    }
+  })
+}
+
+/// An archived game from the server.
+pub fn Replay(cx: Scope) -> Element {
+  let client = cx
+    .use_hook(|| cx.consume_context::<utils::Client>())
+    .clone()
+    .unwrap();
+
+  let router = use_router(cx);
+
+  let id = use_route(cx)
+    .parse_segment_or_404::<String>("id")
+    .unwrap_or_default();
+
+  let game = use_future(cx, (), |_| async move {
+    client
+      .lock()
+      .await
+      .get_archived_game(GetArchivedGameRequest { id })
+      .await
+      .map(|res| res.into_inner())
+  });
+
+  match game.value() {
+    Some(Ok(game)) => cx.render(rsx!(ReplayViewer {
+      title: format!(
+        "{} vs {}, {}",
+        game.white,
+        game.black,
+        outcome(game.outcome())
+      ),
+      moves: game.moves.clone(),
+      fen: game.fen.clone(),
+      on_back: move |_| router.navigate_to("/replay"),
+    })),
+    Some(Err(status)) => cx.render(rsx!(div {
+      "{status.message()}"
+      button { onclick: move |_| router.navigate_to("/replay"), "Back" }
+    })),
+    None => cx.render(rsx!("Loading game")),
+  }
+}
+
+#[derive(Props)]
+pub struct ReplayViewerProps<'a> {
+  pub title: String,
+  /// In the `"<board> <alg>"` format of `lastMove`.
+  pub moves: Vec<String>,
+  /// Final position, shown on its own when the moves weren't kept.
+  #[props(default)]
+  pub fen: String,
+  pub on_back: EventHandler<'a>,
+}
+
+/// Steps through a finished game. Arrow keys move one move, Home and End jump to either end,
+/// space starts and stops autoplay and F turns the boards around.
+pub fn ReplayViewer<'a>(cx: Scope<'a, ReplayViewerProps<'a>>) -> Element<'a> {
+  let replayed = &*cx.use_hook(|| {
+    let played = TicTacToe::replay(cx.props.moves.iter().map(String::as_str))
+      .map_err(|err| format!("Couldn't replay the game: {err}"));
+
+    let start = match cx.props.moves.is_empty() {
+      true => TicTacToe::from_fen(&cx.props.fen).unwrap_or_default(),
+      false => TicTacToe::default(),
+    };
+
+    (start, played)
+  });
+
+  let index = use_state(cx, || 0_usize);
+  let autoplay = use_state(cx, || false);
+  let side = use_state(cx, || Color::White);
+  let zoomed = use_state(cx, || None::<usize>);
+
+  let (start, played) = match replayed {
+    (start, Ok(played)) => (start, played.as_slice()),
+    (_, Err(err)) => {
+      return cx.render(rsx!(div {
+        "{err}"
+        button { onclick: move |_| cx.props.on_back.call(()), "Back" }
+      }))
+    }
+  };
+
+  let count = played.len();
+
+  use_future(cx, (autoplay.get(),), |(playing,)| {
+    let index = index.clone();
+    let autoplay = autoplay.clone();
+
+    async move {
+      if playing {
+        loop {
+          platform::sleep(AUTOPLAY_DELAY).await;
+
+          let next = *index.current() + 1;
+          if next > count {
+            autoplay.set(false);
+            break;
+          }
+
+          index.set(next);
+        }
+      }
+    }
+  });
+
+  let toggle_autoplay = move || {
+    if !autoplay.get() && **index == count {
+      index.set(0);
+    }
+    autoplay.set(!autoplay.get());
+  };
+
+  let on_key = move |ev: KeyboardEvent| match ev.key() {
+    Key::ArrowRight => index.set((**index + 1).min(count)),
+    Key::ArrowLeft => index.set(index.saturating_sub(1)),
+    Key::Home => index.set(0),
+    Key::End => index.set(count),
+    Key::Character(key) if key == " " => toggle_autoplay(),
+    Key::Character(key) if key.eq_ignore_ascii_case("f") => side.set(match side.get() {
+      Color::White => Color::Black,
+      Color::Black => Color::White,
+    }),
+    _ => {}
+  };
+
+  let previous: Option<&PlayedMove> = index.checked_sub(1).and_then(|i| played.get(i));
+
+  let position = previous.map_or(start, |played| &played.position);
+
+  let (last_move, last) = match previous {
+    Some(played) => (format!("{} {}", played.board, played.alg), played.color),
+    None => (String::new(), Color::Black),
+  };
+
+  // The board the last move was on, if that move decided it.
+  let decided = previous.and_then(|last_played| {
+    let board = last_played.board as usize;
+    let end_of = |game: &TicTacToe| game.chesses[board / 3][board % 3].end.clone();
+
+    let before = match **index {
+      1 => start,
+      i => &played[i - 2].position,
+    };
+    let end = end_of(&last_played.position);
+
+    (end_of(before) == EndResult::None(true) && end != EndResult::None(true))
+      .then_some((board, end))
+  });
+
+  let decided_text = decided.as_ref().map(|(board, end)| match end {
+    EndResult::Color(color) => format!(
+      "Board {} won by {}",
+      board + 1,
+      Color::from_i32(*color).unwrap_or(Color::White)
+    ),
+    _ => format!("Board {} drawn", board + 1),
+  });
+
+  let result_text = match position.end() {
+    EndResult::Color(color) => Some(format!(
+      "{} won the game",
+      Color::from_i32(color).unwrap_or(Color::White)
+    )),
+    EndResult::Draw(_) => Some("The game is drawn".to_owned()),
+    EndResult::None(_) => None,
+  };
+
+  // Boards are numbered from 1 here, the way players see them.
+  let moves: Vec<String> = played
+    .iter()
+    .map(|played| format!("{}: {}", played.board + 1, played.san))
+    .collect();
+  let moves = &moves;
+
+  let only_final = moves.is_empty() && !cx.props.fen.is_empty();
+  let play_label = if **autoplay { "Pause" } else { "Play" };
+
+  cx.render(rsx! {
+    div { class: "main-container game-layout", tabindex: "0", onkeydown: on_key,
+        match zoomed.get() {
+            Some(board) => rsx!(div { class: "board-view",
+                div { onclick: move |_| zoomed.set(None), class: "back-icon",
+                    Icon { width: 50, height: 50, icon: IoArrowBack }
+                }
+                ChessBoard {
+                    last: last,
+                    side: *side.get(),
+                    chess: &position.chesses[board / 3][board % 3],
+                    board_num: *board as u32,
+                    last_move: last_move.clone(),
+                    readonly: true
+                }
+            }),
+            None => rsx!(div { class: "tic-container",
+                TicGrid {
+                    game: position,
+                    side: *side.get(),
+                    last: last,
+                    last_move: last_move.clone(),
+                    readonly: true,
+                    highlight: decided.as_ref().map(|(board, _)| *board),
+                    onselect: move |board| zoomed.set(Some(board))
+                }
+            }),
+        }
+        div { class: "info-panel",
+            div { class: "replay-title", "{cx.props.title}" }
+            div { class: "replay-controls",
+                button { onclick: move |_| cx.props.on_back.call(()), "Back" }
+                button { onclick: move |_| index.set(0), "Start" }
+                button { onclick: move |_| index.set(index.saturating_sub(1)), "Previous" }
+                button { onclick: move |_| toggle_autoplay(), "{play_label}" }
+                button { onclick: move |_| index.set((**index + 1).min(count)), "Next" }
+                button { onclick: move |_| index.set(count), "End" }
+            }
+            div { class: "replay-status", "Move {index} of {count}" }
+            if let Some(text) = decided_text {
+                rsx!(div { class: "replay-decided", "{text}" })
+            }
+            if let Some(text) = result_text {
+                rsx!(div { class: "replay-result", "{text}" })
+            }
+            if only_final {
+                rsx!(div { class: "replay-status", "The moves of this game weren't kept, only its final position" })
+            }
+            div { class: "move-list",
+                moves.iter().enumerate().map(|(i, label)| {
+                    let class = if **index == i + 1 { "move selected" } else { "move" };
+                    rsx!(span { class: "{class}", onclick: move |_| index.set(i + 1), "{i + 1}. {label}" })
+                })
+            }
+        }
+    }
+  })
+}
@@ -1,2 +1,6 @@
 pub mod GameScreen;
+pub mod LobbyBrowser;
+pub mod LocalGame;
+pub mod LoginScreen;
 pub mod MainScreen;
+pub mod Replay;
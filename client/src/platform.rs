@@ -0,0 +1,12 @@
+//! Everything the desktop app and the browser build do differently. The UI only goes through
+//! these functions, so it doesn't care which one it's running in.
+
+#[cfg(not(target_arch = "wasm32"))]
+mod desktop;
+#[cfg(not(target_arch = "wasm32"))]
+pub use desktop::*;
+
+#[cfg(target_arch = "wasm32")]
+mod web;
+#[cfg(target_arch = "wasm32")]
+pub use web::*;
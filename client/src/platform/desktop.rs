@@ -0,0 +1,96 @@
+use std::{error::Error, fs, path::PathBuf, time::Duration};
+
+use clap::Parser;
+use dioxus_desktop::tao::clipboard::Clipboard;
+use tonic::transport::Channel;
+use tracing_subscriber::EnvFilter;
+
+use crate::config::ClientConfig;
+
+pub type Transport = Channel;
+
+pub const HAS_CLIPBOARD: bool = true;
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Chess-Tac-Toe desktop client")]
+pub struct Args {
+  /// TOML file to read and remember the settings in
+  #[arg(short, long, env = "CHESSTACTOE_CLIENT_CONFIG")]
+  pub config: Option<PathBuf>,
+  /// Server to connect to, like http://localhost:50051
+  #[arg(short, long, env = "CHESSTACTOE_SERVER")]
+  pub server: Option<String>,
+  /// Which events to log to stderr, like `info,client=debug`
+  #[arg(long, env = "CHESSTACTOE_LOG")]
+  pub log: Option<String>,
+}
+
+pub async fn sleep(duration: Duration) {
+  tokio::time::sleep(duration).await
+}
+
+pub async fn connect(server: &str) -> Result<Transport, String> {
+  Channel::from_shared(server.to_owned())
+    .map_err(|err| err.to_string())?
+    .connect()
+    .await
+    .map_err(|err| err.to_string())
+}
+
+pub fn copy_text(text: &str) {
+  Clipboard::new().write_text(text);
+}
+
+/// Logs to stderr, `filter` uses the `RUST_LOG` syntax.
+pub fn init_logging(filter: &str) {
+  let filter = EnvFilter::try_new(filter).unwrap_or_else(|_| EnvFilter::new("info"));
+
+  tracing_subscriber::fmt()
+    .with_env_filter(filter)
+    .try_init()
+    .unwrap_or(());
+}
+
+fn default_path() -> Option<PathBuf> {
+  dirs_next::config_dir().map(|dir| dir.join("chesstactoe").join("client.toml"))
+}
+
+/// Reads `client.toml` from the user's config directory, overridden by `CHESSTACTOE_*` environment
+/// variables and command line flags.
+pub fn load_config() -> ClientConfig {
+  let args = Args::parse();
+
+  let path = args.config.or_else(default_path);
+
+  let mut config = path
+    .as_ref()
+    .and_then(|path| fs::read_to_string(path).ok())
+    .map(|file| ClientConfig::parse(&file))
+    .unwrap_or_default();
+
+  config.path = path;
+
+  if let Some(server) = args.server {
+    config.server = server;
+  }
+
+  if let Some(log) = args.log {
+    config.log = log;
+  }
+
+  config
+}
+
+pub fn save_config(config: &ClientConfig) -> Result<(), Box<dyn Error>> {
+  let Some(path) = &config.path else {
+    return Ok(());
+  };
+
+  if let Some(dir) = path.parent() {
+    fs::create_dir_all(dir)?;
+  }
+
+  fs::write(path, toml::to_string_pretty(config)?)?;
+
+  Ok(())
+}
@@ -0,0 +1,62 @@
+use std::{error::Error, time::Duration};
+
+use web_sys::Storage;
+
+use crate::config::ClientConfig;
+
+pub type Transport = tonic_web_wasm_client::Client;
+
+/// Browsers only allow clipboard writes through APIs `web-sys` keeps behind an unstable flag.
+pub const HAS_CLIPBOARD: bool = false;
+
+const CONFIG_KEY: &str = "chesstactoe-config";
+
+/// gRPC-Web requests are plain `fetch` calls, so there's no connection to set up.
+pub async fn connect(server: &str) -> Result<Transport, String> {
+  Ok(tonic_web_wasm_client::Client::new(server.to_owned()))
+}
+
+pub fn copy_text(_text: &str) {}
+
+/// Logs to the browser console. Only the level of `filter` is used, like `debug` in
+/// `debug,client=trace`.
+pub fn init_logging(filter: &str) {
+  let level = filter
+    .split(',')
+    .find(|directive| !directive.contains('='))
+    .and_then(|level| level.trim().parse().ok())
+    .unwrap_or(tracing::Level::INFO);
+
+  tracing_wasm::set_as_global_default_with_config(
+    tracing_wasm::WASMLayerConfigBuilder::new()
+      .set_max_level(level)
+      .set_report_logs_in_timings(false)
+      .build(),
+  );
+}
+
+pub async fn sleep(duration: Duration) {
+  gloo_timers::future::sleep(duration).await
+}
+
+fn local_storage() -> Option<Storage> {
+  web_sys::window()?.local_storage().ok()?
+}
+
+/// Reads the settings remembered in the browser's local storage.
+pub fn load_config() -> ClientConfig {
+  local_storage()
+    .and_then(|storage| storage.get_item(CONFIG_KEY).ok()?)
+    .map(|saved| ClientConfig::parse(&saved))
+    .unwrap_or_default()
+}
+
+pub fn save_config(config: &ClientConfig) -> Result<(), Box<dyn Error>> {
+  let storage = local_storage().ok_or("Local storage is not available")?;
+
+  storage
+    .set_item(CONFIG_KEY, &toml::to_string(config)?)
+    .map_err(|_| "Couldn't write to local storage")?;
+
+  Ok(())
+}
@@ -0,0 +1,247 @@
+//! Where the game on screen comes from. The board components only talk to a [`GameSource`], so
+//! the same UI plays online through the server or locally with two players on one machine.
+
+use std::sync::{Arc, Mutex};
+
+use helpers::{
+  auth,
+  chesstactoe::{
+    self, chess::EndResult, tic_tac_toe, Chess, MovePieceRequest, MoveResult, RematchRequest,
+    SendChatRequest, SubscribeBoardRequest, SubscribeBoardResponse,
+  },
+  tictactoe::TicTacToe,
+  Coordinates,
+};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tonic::Streaming;
+
+use crate::Client;
+
+#[derive(Clone)]
+pub enum GameSource {
+  /// The game of the seat in `get_uuid`, played through the server.
+  Remote(Client),
+  /// A hot-seat game kept in memory, no server involved.
+  Local(Arc<Mutex<LocalGame>>),
+}
+
+/// Updates of the game, in the same shape the server sends them.
+pub enum Updates {
+  Remote(Streaming<SubscribeBoardResponse>),
+  Local(UnboundedReceiver<SubscribeBoardResponse>),
+}
+
+impl Updates {
+  pub async fn next(&mut self) -> Option<SubscribeBoardResponse> {
+    match self {
+      Updates::Remote(stream) => stream.message().await.ok().flatten(),
+      Updates::Local(receiver) => receiver.recv().await,
+    }
+  }
+}
+
+#[derive(Debug, Default)]
+pub struct LocalGame {
+  /// Every move played, in the `"<board> <alg>"` format of `lastMove`.
+  moves: Vec<String>,
+  subscribers: Vec<UnboundedSender<SubscribeBoardResponse>>,
+}
+
+impl LocalGame {
+  fn position(&self) -> TicTacToe {
+    TicTacToe::replay(self.moves.iter().map(String::as_str))
+      .ok()
+      .and_then(|played| played.last().map(|played| played.position.clone()))
+      .unwrap_or_default()
+  }
+
+  /// Board state for the player whose turn it is, so the board turns around after every move.
+  fn response(&self) -> SubscribeBoardResponse {
+    let game = self.position();
+
+    let end_result = match game.end() {
+      EndResult::Color(color) => tic_tac_toe::EndResult::Color(color),
+      EndResult::Draw(draw) => tic_tac_toe::EndResult::Draw(draw),
+      EndResult::None(none) => tic_tac_toe::EndResult::None(none),
+    };
+
+    SubscribeBoardResponse {
+      color: game.next as i32,
+      game: Some(chesstactoe::TicTacToe {
+        chesses: game
+          .chesses
+          .iter()
+          .flatten()
+          .map(|chess| Chess {
+            end_result: Some(chess.end.clone()),
+            fen: chess.to_fen(game.next).unwrap_or_default(),
+          })
+          .collect(),
+        next: game.next as i32,
+        last_move: self.moves.last().cloned().unwrap_or_default(),
+        moves: self.moves.clone(),
+        end_result: Some(end_result),
+      }),
+      ..Default::default()
+    }
+  }
+
+  fn broadcast(&mut self) {
+    let response = self.response();
+
+    self
+      .subscribers
+      .retain(|subscriber| subscriber.send(response.clone()).is_ok());
+  }
+}
+
+impl GameSource {
+  pub fn local() -> Self {
+    GameSource::Local(Arc::default())
+  }
+
+  pub fn is_local(&self) -> bool {
+    matches!(self, GameSource::Local(_))
+  }
+
+  pub async fn subscribe(&self) -> Result<Updates, String> {
+    match self {
+      GameSource::Remote(client) => {
+        let uuid = crate::get_uuid().ok_or("Not in a game")?;
+
+        let stream = client
+          .lock()
+          .await
+          .subscribe_board(SubscribeBoardRequest { uuid })
+          .await
+          .map_err(|status| status.message().to_owned())?;
+
+        Ok(Updates::Remote(stream.into_inner()))
+      }
+      GameSource::Local(game) => {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let mut game = game.lock().unwrap();
+
+        sender.send(game.response()).ok();
+        game.subscribers.push(sender);
+
+        Ok(Updates::Local(receiver))
+      }
+    }
+  }
+
+  pub async fn move_piece(&self, board: u32, alg: String) -> Result<(), String> {
+    match self {
+      GameSource::Remote(client) => {
+        let uuid = crate::get_uuid().ok_or("Not in a game")?;
+
+        let mut request = tonic::Request::new(MovePieceRequest {
+          board,
+          alg: alg.clone(),
+          uuid,
+        });
+
+        // The server logs the move under the same id.
+        let request_id = auth::request_id(&mut request);
+        tracing::info!(request_id, board, alg, "Sending move");
+
+        let res = client
+          .lock()
+          .await
+          .move_piece(request)
+          .await
+          .map_err(|status| {
+            tracing::warn!(request_id, err = status.message(), "Move failed");
+            status.message().to_owned()
+          })?;
+
+        match res.get_ref().successful() {
+          MoveResult::ResultSuccessful => Ok(()),
+          _ if !res.get_ref().reason.is_empty() => {
+            tracing::info!(request_id, reason = res.get_ref().reason, "Move rejected");
+            Err(res.into_inner().reason)
+          }
+          _ => Err("The server rejected the move".to_owned()),
+        }
+      }
+      GameSource::Local(game) => {
+        let mut game = game.lock().unwrap();
+        let coords = Coordinates::new((board as usize % 3, board as usize / 3));
+
+        game
+          .position()
+          .make_move(coords, &alg)
+          .map_err(|err| err.to_string())?;
+
+        game.moves.push(format!("{board} {alg}"));
+        game.broadcast();
+
+        Ok(())
+      }
+    }
+  }
+
+  /// Online this offers or answers a rematch, locally both players are here so it starts over.
+  pub async fn rematch(&self, decline: bool) -> Result<(), String> {
+    match self {
+      GameSource::Remote(client) => {
+        let uuid = crate::get_uuid().ok_or("Not in a game")?;
+
+        client
+          .lock()
+          .await
+          .rematch(RematchRequest { uuid, decline })
+          .await
+          .map_err(|status| status.message().to_owned())?;
+
+        Ok(())
+      }
+      GameSource::Local(game) => {
+        if !decline {
+          let mut game = game.lock().unwrap();
+
+          game.moves.clear();
+          game.broadcast();
+        }
+
+        Ok(())
+      }
+    }
+  }
+
+  pub async fn send_chat(&self, text: String) -> Result<(), String> {
+    match self {
+      GameSource::Remote(client) => {
+        let uuid = crate::get_uuid().ok_or("Not in a game")?;
+
+        client
+          .lock()
+          .await
+          .send_chat(SendChatRequest { uuid, text })
+          .await
+          .map_err(|status| status.message().to_owned())?;
+
+        Ok(())
+      }
+      GameSource::Local(_) => Err("There's nobody to chat with in a local game".to_owned()),
+    }
+  }
+
+  /// Takes back the last move. Only local games can do this, online it takes a takeback request.
+  pub fn undo(&self) -> Result<(), String> {
+    match self {
+      GameSource::Remote(_) => Err("Only local games can undo moves".to_owned()),
+      GameSource::Local(game) => {
+        let mut game = game.lock().unwrap();
+
+        if game.moves.pop().is_none() {
+          return Err("There's no move to undo".to_owned());
+        }
+
+        game.broadcast();
+
+        Ok(())
+      }
+    }
+  }
+}
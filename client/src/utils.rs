@@ -1,7 +1,29 @@
-use std::sync::RwLock;
+use std::sync::{Arc, RwLock};
+
+use config::ClientConfig;
+use helpers::{
+  auth::Session,
+  chesstactoe::{auth_client::AuthClient, game_client::GameClient},
+};
+use once_cell::sync::Lazy;
+use platform::Transport;
+use tokio::sync::Mutex;
+use tonic::codegen::InterceptedService;
+
+pub mod config;
+pub mod platform;
+pub mod source;
+
+pub type Client = Arc<Mutex<GameClient<InterceptedService<Transport, Session>>>>;
+
+pub type Auth = Arc<Mutex<AuthClient<Transport>>>;
 
 static UUID: RwLock<Option<String>> = RwLock::new(None);
 
+static NAME: RwLock<String> = RwLock::new(String::new());
+
+static CONFIG: Lazy<RwLock<ClientConfig>> = Lazy::new(Default::default);
+
 pub fn set_uuid(new: &str) {
   UUID.write().unwrap().replace(new.to_owned());
 }
@@ -9,3 +31,35 @@ pub fn set_uuid(new: &str) {
 pub fn get_uuid() -> Option<String> {
   return UUID.read().unwrap().clone();
 }
+
+pub fn set_name(new: &str) {
+  *NAME.write().unwrap() = new.to_owned();
+}
+
+pub fn get_name() -> String {
+  return NAME.read().unwrap().clone();
+}
+
+pub fn set_config(new: ClientConfig) {
+  *CONFIG.write().unwrap() = new;
+}
+
+pub fn get_config() -> ClientConfig {
+  return CONFIG.read().unwrap().clone();
+}
+
+pub fn remember_server(server: &str) {
+  if let Err(err) = CONFIG.write().unwrap().remember_server(server) {
+    tracing::warn!(%err, "Couldn't save the client config");
+  }
+}
+
+pub const TIME_CONTROLS: [(u32, u32); 5] = [(0, 0), (60, 0), (180, 2), (300, 3), (600, 5)];
+
+pub fn format_time_control(initial: u32, increment: u32) -> String {
+  if initial == 0 {
+    return "Unlimited".to_owned();
+  }
+
+  format!("{}+{}", initial / 60, increment)
+}
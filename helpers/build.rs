@@ -1,4 +1,9 @@
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-  tonic_build::compile_protos("../proto/chesstactoe.proto")?;
+  // Browsers can't use tonic's transport, the web client brings its own gRPC-Web one
+  let wasm = std::env::var("CARGO_CFG_TARGET_ARCH").as_deref() == Ok("wasm32");
+
+  tonic_build::configure()
+    .build_transport(!wasm)
+    .compile(&["../proto/chesstactoe.proto"], &["../proto"])?;
   Ok(())
 }
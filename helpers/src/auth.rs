@@ -0,0 +1,70 @@
+use std::sync::{Arc, RwLock};
+
+use tonic::{metadata::MetadataValue, service::Interceptor, Request, Status};
+use uuid::Uuid;
+
+pub const AUTHORIZATION: &str = "authorization";
+
+pub const BEARER: &str = "Bearer ";
+
+/// Metadata key of the id the server logs a request under.
+pub const REQUEST_ID: &str = "x-request-id";
+
+/// Gives a request a new id, unless it already has one, and returns the id so the caller can log
+/// it next to whatever it sent.
+pub fn request_id<T>(request: &mut Request<T>) -> String {
+  if let Some(id) = request
+    .metadata()
+    .get(REQUEST_ID)
+    .and_then(|id| id.to_str().ok())
+  {
+    return id.to_owned();
+  }
+
+  let id = Uuid::new_v4().to_string();
+
+  // A UUID is always valid ASCII.
+  request
+    .metadata_mut()
+    .insert(REQUEST_ID, MetadataValue::try_from(&id).unwrap());
+
+  id
+}
+
+/// Client side interceptor that attaches the current session token and a request id to every
+/// request.
+///
+/// Clones share the token, so logging in through one clone authenticates all of them.
+#[derive(Debug, Clone, Default)]
+pub struct Session {
+  token: Arc<RwLock<Option<String>>>,
+}
+
+impl Session {
+  pub fn set_token(&self, token: &str) {
+    self.token.write().unwrap().replace(token.to_owned());
+  }
+
+  pub fn token(&self) -> Option<String> {
+    self.token.read().unwrap().clone()
+  }
+
+  pub fn log_out(&self) {
+    self.token.write().unwrap().take();
+  }
+}
+
+impl Interceptor for Session {
+  fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+    if let Some(token) = self.token() {
+      let value = MetadataValue::try_from(format!("{BEARER}{token}"))
+        .map_err(|_| Status::unauthenticated("Malformed session token"))?;
+
+      request.metadata_mut().insert(AUTHORIZATION, value);
+    }
+
+    request_id(&mut request);
+
+    Ok(request)
+  }
+}
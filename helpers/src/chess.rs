@@ -2,7 +2,7 @@ use std::{collections::HashMap, fmt::Display};
 
 use rand::seq::SliceRandom;
 
-use crate::{chesstactoe::chess::EndResult, Color, Coordinates, FenError, MoveError};
+use crate::{chesstactoe::chess::EndResult, Color, Coordinates, Error};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Piece {
@@ -20,6 +20,20 @@ pub enum PieceName {
   PAWN,
 }
 
+impl PieceName {
+  /// Letter used for the piece in move notation, empty for pawns.
+  pub fn letter(&self) -> &'static str {
+    match self {
+      PieceName::ROOK => "R",
+      PieceName::KNIGHT => "N",
+      PieceName::BISHOP => "B",
+      PieceName::QUEEN => "Q",
+      PieceName::KING => "K",
+      PieceName::PAWN => "",
+    }
+  }
+}
+
 impl Display for PieceName {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     match self {
@@ -33,20 +47,6 @@ impl Display for PieceName {
   }
 }
 
-#[derive(Debug)]
-pub enum ChessError {
-  FenError(FenError),
-  MoveError(MoveError),
-}
-
-impl Display for ChessError {
-  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    write!(f, "{self:?}")
-  }
-}
-
-impl std::error::Error for ChessError {}
-
 macro_rules! regex {
   ($re:literal $(,)?) => {{
     static RE: once_cell::sync::OnceCell<regex::Regex> = once_cell::sync::OnceCell::new();
@@ -74,7 +74,7 @@ impl Default for ChessBoard {
 }
 
 impl TryFrom<&str> for ChessBoard {
-  type Error = FenError;
+  type Error = Error;
 
   fn try_from(value: &str) -> Result<Self, Self::Error> {
     ChessBoard::parse_fen(value)
@@ -100,10 +100,10 @@ impl ChessBoard {
     Self::parse_fen(&format!("{board} w KQkq - 0 1")).unwrap()
   }
 
-  pub fn parse_fen(fen: &str) -> Result<ChessBoard, FenError> {
+  pub fn parse_fen(fen: &str) -> Result<ChessBoard, Error> {
     let mut parts = fen.split(' ');
     if parts.clone().count() != 6 {
-      return Err(FenError::InvalidFormat);
+      return Err(Error::InvalidFen);
     }
 
     let board_part = parts.next().unwrap();
@@ -111,7 +111,7 @@ impl ChessBoard {
     let board_parts = board_part.split('/');
 
     if board_parts.clone().count() != 8 {
-      return Err(FenError::InvalidFormat);
+      return Err(Error::InvalidFen);
     }
 
     let mut board: [[Option<Piece>; 8]; 8] = [[None; 8]; 8];
@@ -171,13 +171,13 @@ impl ChessBoard {
                 Color::Black
               },
             })),
-            _ => return Err(FenError::InvalidFormat),
+            _ => return Err(Error::InvalidFen),
           }
         }
       }
 
       if row.len() != 8 {
-        return Err(FenError::InvalidFormat);
+        return Err(Error::InvalidFen);
       }
 
       board[index] = row.try_into().unwrap();
@@ -186,7 +186,7 @@ impl ChessBoard {
     let _next = match parts.next().unwrap() {
       "w" => Color::White,
       "b" => Color::Black,
-      _ => return Err(FenError::InvalidFormat),
+      _ => return Err(Error::InvalidFen),
     };
 
     let castle_part = parts.next().unwrap();
@@ -244,22 +244,18 @@ impl ChessBoard {
       en_passant: if en_passant == "-" {
         None
       } else {
-        Some(ChessBoard::get_square(en_passant)?)
+        Some(ChessBoard::get_square(en_passant).map_err(|_| Error::InvalidFen)?)
       },
-      halfmove: halfmove
-        .parse::<usize>()
-        .map_err(|_| FenError::InvalidFormat)?,
-      fullmove: fullmove
-        .parse::<usize>()
-        .map_err(|_| FenError::InvalidFormat)?,
+      halfmove: halfmove.parse::<usize>().map_err(|_| Error::InvalidFen)?,
+      fullmove: fullmove.parse::<usize>().map_err(|_| Error::InvalidFen)?,
       past: vec![fen.to_owned()],
     })
   }
 
-  pub fn get_square(str: &str) -> Result<Coordinates, FenError> {
+  pub fn get_square(str: &str) -> Result<Coordinates, Error> {
     let regex = regex!("[a-h]{1}[1-8]{1}");
     if !regex.is_match(str) {
-      return Err(FenError::InvalidFormat);
+      return Err(Error::MalformedNotation(str.to_owned()));
     }
 
     let col = match &str[0..1] {
@@ -271,7 +267,7 @@ impl ChessBoard {
       "f" => 5,
       "g" => 6,
       "h" => 7,
-      _ => return Err(FenError::InvalidSquare),
+      _ => return Err(Error::MalformedNotation(str.to_owned())),
     };
 
     let row = str[1..2].parse::<usize>().unwrap() - 1;
@@ -279,11 +275,11 @@ impl ChessBoard {
     Ok(Coordinates { row, col })
   }
 
-  pub fn get_tile(square: Coordinates) -> Result<String, FenError> {
+  pub fn get_tile(square: Coordinates) -> Result<String, Error> {
     let chars = ['a', 'b', 'c', 'd', 'e', 'f', 'g', 'h'];
 
     if square.row > 7 || square.col > 7 {
-      return Err(FenError::InvalidSquare);
+      return Err(Error::MalformedNotation(format!("{square:?}")));
     }
 
     Ok(chars[square.col].to_string() + &(square.row + 1).to_string())
@@ -291,7 +287,7 @@ impl ChessBoard {
 
   pub fn get_data_from_move(
     move_string: &str,
-  ) -> Result<(Coordinates, Coordinates, PieceName), FenError> {
+  ) -> Result<(Coordinates, Coordinates, PieceName), Error> {
     let name = match &move_string[0..1] {
       "N" => PieceName::KNIGHT,
       "R" => PieceName::ROOK,
@@ -313,22 +309,52 @@ impl ChessBoard {
     Ok((starting_coords, end_coords, name))
   }
 
-  //TODO test
-  pub fn validate_move(
-    &self,
-    move_string: &str,
-    next: Color,
-  ) -> Result<bool, Box<dyn std::error::Error>> {
+  /// Whether `next` can play the move here. Moves the rules don't allow are `Ok(false)`,
+  /// [`ChessBoard::check_move`] tells why.
+  pub fn validate_move(&self, move_string: &str, next: Color) -> Result<bool, Error> {
+    match self.check_move(move_string, next) {
+      Ok(()) => Ok(true),
+      Err(err) if err.is_illegal_move() => Ok(false),
+      Err(err) => Err(err),
+    }
+  }
+
+  /// Checks that `next` can play the move here, with the reason if it can't.
+  pub fn check_move(&self, move_string: &str, next: Color) -> Result<(), Error> {
+    if !self.moves_like_that(move_string, next)? {
+      return Err(Error::IllegalMove);
+    }
+
+    // Castling already makes sure the king doesn't pass through or start in check.
+    if move_string == "O-O-O" || move_string == "O-O" {
+      return Ok(());
+    }
+
+    if self.exposes_king(move_string, next)? {
+      let (_, _, name) = Self::get_data_from_move(move_string)?;
+
+      if name == PieceName::KING || self.in_check(next) {
+        return Err(Error::KingInCheck);
+      }
+
+      return Err(Error::PiecePinned);
+    }
+
+    Ok(())
+  }
+
+  /// Whether the piece can make the move, leaving aside what happens to its king.
+  fn moves_like_that(&self, move_string: &str, next: Color) -> Result<bool, Error> {
     let regex = regex!(
       r"^((O-O-O)|(O-O)|([RNBKQ]{0,1}([a-h]{1}[1-8]{1})x{0,1}([a-h]{1}[1-8]{1}))|(([a-h]{1}[1-8]{1})x{0,1}([a-h]{1}[1-8]{1})[RNBKQ]{0,1}))$"
     );
 
     if !regex.is_match(move_string) {
-      return Err(Box::new(FenError::InvalidFormat));
+      return Err(Error::MalformedNotation(move_string.to_owned()));
     }
 
     if self.end != EndResult::None(true) {
-      return Err(Box::new(MoveError::GameOver));
+      return Err(Error::BoardDecided);
     }
 
     if move_string == "O-O-O" || move_string == "O-O" {
@@ -361,7 +387,7 @@ impl ChessBoard {
           ChessBoard::get_square("e1")?,
           vec![ChessBoard::get_square("f1")?, ChessBoard::get_square("g1")?],
         ),
-        _ => return Err(Box::new(MoveError::InvalidMove)),
+        _ => return Err(Error::IllegalMove),
       };
 
       if (self.board[rook_square.row][rook_square.col]
@@ -403,6 +429,17 @@ impl ChessBoard {
       name: piece_name,
     };
 
+    match self.board[starting_coords.row][starting_coords.col] {
+      Some(found) if found == piece => {}
+      Some(found) if found.color != next => return Err(Error::NotYourPiece),
+      _ => {
+        return Err(Error::NoPiece {
+          piece: piece_name,
+          square: Self::get_tile(starting_coords)?,
+        })
+      }
+    }
+
     if !move_string.contains('x') {
       if self.board[end_coords.row][end_coords.col].is_some() {
         return Ok(false);
@@ -415,12 +452,6 @@ impl ChessBoard {
       }
     }
 
-    if self.board[starting_coords.row][starting_coords.col].is_none()
-      || self.board[starting_coords.row][starting_coords.col].unwrap() != piece
-    {
-      return Ok(false);
-    }
-
     match piece.name {
       PieceName::ROOK => {
         return Ok(ChessBoard::validate_rook(
@@ -512,17 +543,66 @@ impl ChessBoard {
     }
   }
 
-  fn _validate_check(
-    &self,
-    _starting_coords: &Coordinates,
-    _end_coords: &Coordinates,
-    _board: &[[Option<Piece>; 8]; 8],
-    alg: &str,
-    next: Color,
-  ) -> bool {
-    let mut fake_board = self.clone();
-    fake_board.make_move(alg, next).unwrap();
-    !fake_board.is_checked(&next)
+  /// Whether `next`'s king would be attacked after the move.
+  fn exposes_king(&self, alg: &str, next: Color) -> Result<bool, Error> {
+    let (from, to, name) = Self::get_data_from_move(alg)?;
+
+    // Only the pieces matter, so skip cloning the history.
+    let mut after = ChessBoard {
+      board: self.board,
+      castling: HashMap::new(),
+      en_passant: None,
+      halfmove: 0,
+      fullmove: 0,
+      end: EndResult::None(true),
+      past: vec![],
+    };
+
+    if name == PieceName::PAWN && from.col != to.col && self.board[to.row][to.col].is_none() {
+      after.board[from.row][to.col] = None;
+    }
+
+    after.board[to.row][to.col] = after.board[from.row][from.col].take();
+
+    Ok(after.in_check(next))
+  }
+
+  /// Ends the board when the side that didn't just move has nothing left to play here:
+  /// checkmate wins it for `moved`, stalemate draws it.
+  fn end_if_stuck(&mut self, moved: Color) {
+    if self.end != EndResult::None(true) {
+      return;
+    }
+
+    let other = if moved == Color::White {
+      Color::Black
+    } else {
+      Color::White
+    };
+
+    if !self.has_legal_move(other) {
+      self.end = if self.in_check(other) {
+        EndResult::Color(moved as i32)
+      } else {
+        EndResult::Draw(true)
+      };
+    }
+  }
+
+  /// Whether `next` can move at all here. Stops at the first legal move it finds.
+  fn has_legal_move(&self, next: Color) -> bool {
+    let squares = || (0..8).flat_map(|row| (0..8).map(move |col| Coordinates { row, col }));
+
+    squares()
+      .filter(|from| matches!(self.board[from.row][from.col], Some(piece) if piece.color == next))
+      .any(|from| {
+        squares().any(|to| {
+          to != from
+            && self
+              .notation(from, to, None)
+              .is_some_and(|alg| self.validate_move(&alg, next).unwrap_or(false))
+        })
+      })
   }
 
   fn is_checked(&self, color: &Color) -> bool {
@@ -741,7 +821,7 @@ impl ChessBoard {
     false
   }
 
-  pub fn to_fen(&self, next: Color) -> Result<String, FenError> {
+  pub fn to_fen(&self, next: Color) -> Result<String, Error> {
     let mut output = "".to_owned();
 
     for row in self.board.into_iter().rev() {
@@ -860,11 +940,144 @@ impl ChessBoard {
     Ok(output)
   }
 
-  pub fn make_move(&mut self, alg: &str, next: Color) -> Result<(), Box<dyn std::error::Error>> {
-    if !self.validate_move(alg, next)? {
-      return Err(Box::new(ChessError::MoveError(MoveError::InvalidMove)));
+  /// Notation `validate_move` takes for moving the piece on `from` to `to`, or `None` if there's
+  /// no piece there. Moving the king two squares, or onto its own rook, castles. Pawns reaching
+  /// the last rank promote to `promotion`, a queen by default.
+  pub fn notation(
+    &self,
+    from: Coordinates,
+    to: Coordinates,
+    promotion: Option<PieceName>,
+  ) -> Option<String> {
+    let piece = self.board.get(from.row)?.get(from.col).copied().flatten()?;
+    let target = self.board.get(to.row)?.get(to.col)?;
+
+    let start = Self::get_tile(from).ok()?;
+    let end = Self::get_tile(to).ok()?;
+
+    if piece.name == PieceName::KING && from.row == to.row && start.starts_with('e') {
+      match &end[0..1] {
+        "g" | "h" => return Some("O-O".to_owned()),
+        "c" | "a" => return Some("O-O-O".to_owned()),
+        _ => {}
+      }
+    }
+
+    let capture = target.is_some()
+      || piece.name == PieceName::PAWN && self.en_passant == Some(to) && from.col != to.col;
+    let x = if capture { "x" } else { "" };
+
+    let promotion = if piece.name == PieceName::PAWN && (to.row == 0 || to.row == 7) {
+      promotion.unwrap_or(PieceName::QUEEN).letter()
+    } else {
+      ""
+    };
+
+    Some(format!("{}{start}{x}{end}{promotion}", piece.name.letter()))
+  }
+
+  /// Squares the piece on `from` can legally move to when it's `next`'s turn, with the move in
+  /// the notation `validate_move` takes.
+  pub fn legal_moves(&self, from: Coordinates, next: Color) -> Vec<(Coordinates, String)> {
+    let Some(Some(piece)) = self.board.get(from.row).and_then(|row| row.get(from.col)) else {
+      return vec![];
+    };
+
+    if piece.color != next {
+      return vec![];
+    }
+
+    (0..8)
+      .flat_map(|row| (0..8).map(move |col| Coordinates { row, col }))
+      .filter(|to| *to != from)
+      // Castling onto the rook is accepted but shown as the king's destination only.
+      .filter(|to| {
+        piece.name != PieceName::KING || from.col.abs_diff(to.col) <= 2 || from.row != to.row
+      })
+      .filter_map(|to| Some((to, self.notation(from, to, None)?)))
+      .filter(|(_, alg)| self.validate_move(alg, next).unwrap_or(false))
+      .collect()
+  }
+
+  /// Whether `color`'s king is attacked. Positions set up without that king are never in check.
+  pub fn in_check(&self, color: Color) -> bool {
+    let king = Some(Piece {
+      color,
+      name: PieceName::KING,
+    });
+
+    self.board.iter().flatten().any(|cell| *cell == king) && self.is_checked(&color)
+  }
+
+  /// Standard algebraic notation of a move played in this position, like `Nbd7`, `exd5` or `e8=Q+`.
+  pub fn to_san(&self, alg: &str, next: Color) -> Result<String, Error> {
+    let mut after = self.clone();
+    after.make_move(alg, next)?;
+
+    let opposite = if next == Color::White {
+      Color::Black
+    } else {
+      Color::White
+    };
+
+    let check = if after.in_check(opposite) { "+" } else { "" };
+
+    if alg == "O-O" || alg == "O-O-O" {
+      return Ok(format!("{alg}{check}"));
+    }
+
+    let (start, end, name) = Self::get_data_from_move(alg)?;
+
+    let capture = self.board[end.row][end.col].is_some()
+      || name == PieceName::PAWN && self.en_passant == Some(end);
+    let x = if capture { "x" } else { "" };
+
+    let start_tile = Self::get_tile(start)?;
+    let end_tile = Self::get_tile(end)?;
+
+    if name == PieceName::PAWN {
+      let file = if capture { &start_tile[0..1] } else { "" };
+
+      let promotion = match alg.chars().last() {
+        Some(piece @ ('Q' | 'R' | 'B' | 'N' | 'K')) => format!("={piece}"),
+        _ => String::new(),
+      };
+
+      return Ok(format!("{file}{x}{end_tile}{promotion}{check}"));
     }
 
+    let letter = &alg[0..1];
+
+    // Other pieces of the same kind that could have gone to the same square.
+    let rivals: Vec<String> = (0..8)
+      .flat_map(|row| (0..8).map(move |col| Coordinates { row, col }))
+      .filter(|&square| {
+        square != start && self.board[square.row][square.col] == Some(Piece { color: next, name })
+      })
+      .filter_map(|square| Self::get_tile(square).ok())
+      .filter(|tile| {
+        self
+          .validate_move(&format!("{letter}{tile}{x}{end_tile}"), next)
+          .unwrap_or(false)
+      })
+      .collect();
+
+    let disambiguation = if rivals.is_empty() {
+      ""
+    } else if rivals.iter().all(|tile| tile[0..1] != start_tile[0..1]) {
+      &start_tile[0..1]
+    } else if rivals.iter().all(|tile| tile[1..2] != start_tile[1..2]) {
+      &start_tile[1..2]
+    } else {
+      &start_tile
+    };
+
+    Ok(format!("{letter}{disambiguation}{x}{end_tile}{check}"))
+  }
+
+  pub fn make_move(&mut self, alg: &str, next: Color) -> Result<(), Error> {
+    self.check_move(alg, next)?;
+
     if alg == "O-O" || alg == "O-O-O" {
       let (king_old, king_new, rook_old, rook_new) = match (alg, next) {
         ("O-O", Color::White) => (
@@ -891,7 +1104,7 @@ impl ChessBoard {
           ChessBoard::get_square("a8").unwrap(),
           ChessBoard::get_square("d8").unwrap(),
         ),
-        _ => return Err(Box::new(MoveError::InvalidMove)),
+        _ => return Err(Error::IllegalMove),
       };
 
       self.board[king_old.row][king_old.col] = None;
@@ -916,11 +1129,12 @@ impl ChessBoard {
 
       self.en_passant = None;
 
+      self.end_if_stuck(next);
+
       return Ok(());
     }
 
-    let (starting_coords, end_coords, piece_name) =
-      Self::get_data_from_move(alg).map_err(ChessError::FenError)?;
+    let (starting_coords, end_coords, piece_name) = Self::get_data_from_move(alg)?;
 
     let piece = Piece {
       color: next,
@@ -929,22 +1143,6 @@ impl ChessBoard {
 
     let starting_square = Self::get_tile(starting_coords)?;
 
-    let opposite = if next == Color::White {
-      Color::Black
-    } else {
-      Color::White
-    };
-
-    if self.board[end_coords.row][end_coords.col].is_some()
-      && !self.board.iter().any(|row| {
-        row.iter().any(|cell| {
-          cell.is_some() && cell.unwrap().name == PieceName::KING && cell.unwrap().color == opposite
-        })
-      })
-    {
-      self.end = EndResult::Color(next as i32);
-    }
-
     let en_passant = match next {
       Color::Black => self.en_passant == Some(end_coords),
       Color::White => self.en_passant == Some(end_coords),
@@ -956,8 +1154,6 @@ impl ChessBoard {
         Color::White => -1,
       };
 
-      let _faszom = self.board[end_coords.row.checked_add_signed(dir).unwrap()][end_coords.col];
-
       self.board[end_coords.row.checked_add_signed(dir).unwrap()][end_coords.col] = None
     }
     self.board[starting_coords.row][starting_coords.col] = None;
@@ -1049,6 +1245,8 @@ impl ChessBoard {
 
     self.past.push(binding);
 
+    self.end_if_stuck(next);
+
     Ok(())
   }
 }
@@ -1,10 +1,13 @@
 // #![allow(unused)]
 
+pub mod auth;
 pub mod chess;
+pub mod rating;
 pub mod tictactoe;
 
 use std::fmt::Display;
 
+use chess::PieceName;
 use chesstactoe::*;
 
 pub mod chesstactoe {
@@ -20,30 +23,99 @@ impl Display for Color {
   }
 }
 
-#[derive(Debug, PartialEq, Eq)]
-pub enum FenError {
-  InvalidFormat,
-  InvalidSquare,
+/// Everything that can go wrong with a position or a move.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+  /// A move or a square that can't be read, with the text as it was given.
+  MalformedNotation(String),
+  InvalidFen,
+  /// A board number outside of the 3x3 grid.
+  WrongBoard,
+  NotYourTurn,
+  NotYourPiece,
+  /// The starting square doesn't hold the piece the move names.
+  NoPiece {
+    piece: PieceName,
+    square: String,
+  },
+  /// Moving the piece would leave its king attacked.
+  PiecePinned,
+  /// The king is attacked, or would be after the move, and the move doesn't help.
+  KingInCheck,
+  /// The piece doesn't move like that.
+  IllegalMove,
+  /// The board already has a winner or is drawn.
+  BoardDecided,
+  GameOver,
 }
 
-impl std::error::Error for FenError {}
+impl Error {
+  /// Whether the request was fine but the rules don't allow the move, as opposed to a broken
+  /// request or a game that can't take moves anymore.
+  pub fn is_illegal_move(&self) -> bool {
+    matches!(
+      self,
+      Error::NotYourTurn
+        | Error::NotYourPiece
+        | Error::NoPiece { .. }
+        | Error::PiecePinned
+        | Error::KingInCheck
+        | Error::IllegalMove
+        | Error::BoardDecided
+    )
+  }
 
-impl Display for FenError {
-  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    write!(f, "{self:?}")
+  pub fn rejection(&self) -> MoveRejection {
+    match self {
+      Error::MalformedNotation(_) => MoveRejection::RejectionMalformedNotation,
+      Error::InvalidFen => MoveRejection::RejectionInvalidFen,
+      Error::WrongBoard => MoveRejection::RejectionWrongBoard,
+      Error::NotYourTurn => MoveRejection::RejectionNotYourTurn,
+      Error::NotYourPiece => MoveRejection::RejectionNotYourPiece,
+      Error::NoPiece { .. } => MoveRejection::RejectionNoPiece,
+      Error::PiecePinned => MoveRejection::RejectionPiecePinned,
+      Error::KingInCheck => MoveRejection::RejectionKingInCheck,
+      Error::IllegalMove => MoveRejection::RejectionIllegalMove,
+      Error::BoardDecided => MoveRejection::RejectionBoardDecided,
+      Error::GameOver => MoveRejection::RejectionGameOver,
+    }
   }
 }
 
-#[derive(Debug)]
-pub enum MoveError {
-  WrongColor,
-  InvalidMove,
-  GameOver,
-}
+impl std::error::Error for Error {}
 
-impl Display for MoveError {
+impl Display for Error {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    write!(f, "{self:?}")
+    match self {
+      Error::MalformedNotation(text) => write!(f, "Can't read \"{text}\" as a move"),
+      Error::InvalidFen => write!(f, "Not a valid FEN"),
+      Error::WrongBoard => write!(f, "There's no such board"),
+      Error::NotYourTurn => write!(f, "It's not your turn"),
+      Error::NotYourPiece => write!(f, "That piece isn't yours"),
+      Error::NoPiece { piece, square } => {
+        write!(
+          f,
+          "There's no {} on {square}",
+          piece.to_string().to_lowercase()
+        )
+      }
+      Error::PiecePinned => write!(f, "That piece is pinned to your king"),
+      Error::KingInCheck => write!(f, "Your king would be in check"),
+      Error::IllegalMove => write!(f, "That piece can't move there"),
+      Error::BoardDecided => write!(f, "That board is already decided"),
+      Error::GameOver => write!(f, "The game is over"),
+    }
+  }
+}
+
+impl From<Error> for tonic::Status {
+  fn from(err: Error) -> Self {
+    match err {
+      Error::MalformedNotation(_) | Error::InvalidFen | Error::WrongBoard => {
+        tonic::Status::invalid_argument(err.to_string())
+      }
+      _ => tonic::Status::failed_precondition(err.to_string()),
+    }
   }
 }
 
@@ -61,5 +133,3 @@ impl Coordinates {
     }
   }
 }
-
-impl std::error::Error for MoveError {}
@@ -0,0 +1,145 @@
+//! Glicko-2 rating system, as described in Mark Glickman's
+//! "Example of the Glicko-2 system" (<http://www.glicko.net/glicko/glicko2.pdf>).
+
+use std::f64::consts::PI;
+
+/// Conversion factor between the Glicko and the Glicko-2 scale.
+const SCALE: f64 = 173.7178;
+
+const CONVERGENCE: f64 = 0.000001;
+
+/// System constant constraining the change in volatility over time.
+pub const DEFAULT_TAU: f64 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rating {
+  pub rating: f64,
+  pub deviation: f64,
+  pub volatility: f64,
+}
+
+impl Default for Rating {
+  fn default() -> Self {
+    Rating {
+      rating: 1500.0,
+      deviation: 350.0,
+      volatility: 0.06,
+    }
+  }
+}
+
+/// The result of one game in a rating period, `score` being 1 for a win, 0.5 for a draw and 0 for
+/// a loss.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Outcome {
+  pub opponent: Rating,
+  pub score: f64,
+}
+
+fn g(phi: f64) -> f64 {
+  1.0 / (1.0 + 3.0 * phi.powi(2) / PI.powi(2)).sqrt()
+}
+
+fn expected(mu: f64, opponent_mu: f64, opponent_phi: f64) -> f64 {
+  1.0 / (1.0 + (-g(opponent_phi) * (mu - opponent_mu)).exp())
+}
+
+impl Rating {
+  fn mu(&self) -> f64 {
+    (self.rating - 1500.0) / SCALE
+  }
+
+  fn phi(&self) -> f64 {
+    self.deviation / SCALE
+  }
+
+  /// Rates a player after a rating period containing `outcomes`.
+  ///
+  /// A period without games only increases the rating deviation.
+  pub fn update(&self, outcomes: &[Outcome], tau: f64) -> Rating {
+    let mu = self.mu();
+    let phi = self.phi();
+    let sigma = self.volatility;
+
+    if outcomes.is_empty() {
+      return Rating {
+        deviation: (phi.powi(2) + sigma.powi(2)).sqrt() * SCALE,
+        ..*self
+      };
+    }
+
+    let v = 1.0
+      / outcomes
+        .iter()
+        .map(|outcome| {
+          let e = expected(mu, outcome.opponent.mu(), outcome.opponent.phi());
+          g(outcome.opponent.phi()).powi(2) * e * (1.0 - e)
+        })
+        .sum::<f64>();
+
+    let improvement = outcomes
+      .iter()
+      .map(|outcome| {
+        let e = expected(mu, outcome.opponent.mu(), outcome.opponent.phi());
+        g(outcome.opponent.phi()) * (outcome.score - e)
+      })
+      .sum::<f64>();
+
+    let delta = v * improvement;
+
+    let volatility = Self::volatility(delta, phi, v, sigma, tau);
+
+    let phi_star = (phi.powi(2) + volatility.powi(2)).sqrt();
+
+    let new_phi = 1.0 / (1.0 / phi_star.powi(2) + 1.0 / v).sqrt();
+    let new_mu = mu + new_phi.powi(2) * improvement;
+
+    Rating {
+      rating: new_mu * SCALE + 1500.0,
+      deviation: new_phi * SCALE,
+      volatility,
+    }
+  }
+
+  /// Finds the new volatility with the Illinois algorithm (step 5 of the paper).
+  fn volatility(delta: f64, phi: f64, v: f64, sigma: f64, tau: f64) -> f64 {
+    let a = sigma.powi(2).ln();
+
+    let f = |x: f64| {
+      let ex = x.exp();
+      ex * (delta.powi(2) - phi.powi(2) - v - ex) / (2.0 * (phi.powi(2) + v + ex).powi(2))
+        - (x - a) / tau.powi(2)
+    };
+
+    let mut upper = a;
+    let mut lower = if delta.powi(2) > phi.powi(2) + v {
+      (delta.powi(2) - phi.powi(2) - v).ln()
+    } else {
+      let mut k = 1.0;
+      while f(a - k * tau) < 0.0 {
+        k += 1.0;
+      }
+      a - k * tau
+    };
+
+    let mut f_upper = f(upper);
+    let mut f_lower = f(lower);
+
+    while (lower - upper).abs() > CONVERGENCE {
+      let c = upper + (upper - lower) * f_upper / (f_lower - f_upper);
+      let f_c = f(c);
+
+      if f_c * f_lower <= 0.0 {
+        upper = lower;
+        f_upper = f_lower;
+      } else {
+        f_upper /= 2.0;
+      }
+
+      lower = c;
+      f_lower = f_c;
+    }
+
+    (upper / 2.0).exp()
+  }
+}
@@ -1,7 +1,8 @@
-use std::fmt::Display;
-
-use crate::{chesstactoe, FenError};
-use crate::{chesstactoe::Color, Coordinates, MoveError};
+use crate::chesstactoe;
+use crate::{
+  chesstactoe::{chess::EndResult, Color},
+  Coordinates, Error,
+};
 
 use crate::chess::ChessBoard;
 
@@ -11,37 +12,37 @@ pub struct TicTacToe {
   pub next: Color,
 }
 
-#[derive(Debug)]
-pub enum TicError {
-  InvalidCoords,
-  WrongColor,
+/// A move from a game's history, with the position it led to.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PlayedMove {
+  /// Board number, 0 to 8 in reading order.
+  pub board: u32,
+  pub alg: String,
+  pub san: String,
+  pub color: Color,
+  pub position: TicTacToe,
 }
 
-impl Display for TicError {
-  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    write!(f, "{self:?}")
+impl TicTacToe {
+  pub fn validate_move(&self, board: Coordinates, alg: &str) -> Result<bool, Error> {
+    match self.check_move(board, alg) {
+      Ok(()) => Ok(true),
+      Err(err) if err.is_illegal_move() => Ok(false),
+      Err(err) => Err(err),
+    }
   }
-}
 
-impl std::error::Error for TicError {}
+  /// Checks that the player whose turn it is can play the move, see [`ChessBoard::check_move`].
+  pub fn check_move(&self, board: Coordinates, alg: &str) -> Result<(), Error> {
+    if self.end() != EndResult::None(true) {
+      return Err(Error::GameOver);
+    }
 
-impl TicTacToe {
-  pub fn validate_move(
-    &self,
-    board: Coordinates,
-    alg: &str,
-  ) -> Result<bool, Box<dyn std::error::Error>> {
-    self.get_board(board)?.validate_move(alg, self.next)
+    self.get_board(board)?.check_move(alg, self.next)
   }
 
-  pub fn make_move(
-    &mut self,
-    board: Coordinates,
-    alg: &str,
-  ) -> Result<(), Box<dyn std::error::Error>> {
-    if !self.validate_move(board, alg)? {
-      return Err(Box::new(MoveError::InvalidMove));
-    }
+  pub fn make_move(&mut self, board: Coordinates, alg: &str) -> Result<(), Error> {
+    self.check_move(board, alg)?;
 
     self.chesses[board.col][board.row].make_move(alg, self.next)?;
 
@@ -54,7 +55,75 @@ impl TicTacToe {
     Ok(())
   }
 
-  pub fn to_fen(&self) -> Result<String, Box<dyn std::error::Error>> {
+  /// Standard algebraic notation of a move on one of the boards, see [`ChessBoard::to_san`].
+  pub fn to_san(&self, board: Coordinates, alg: &str) -> Result<String, Error> {
+    self.get_board(board)?.to_san(alg, self.next)
+  }
+
+  /// Plays moves in the `"<board> <alg>"` format of `lastMove` from the starting position.
+  pub fn replay<'a>(moves: impl IntoIterator<Item = &'a str>) -> Result<Vec<PlayedMove>, Error> {
+    let mut game = TicTacToe::default();
+
+    moves
+      .into_iter()
+      .map(|played| {
+        let malformed = || Error::MalformedNotation(played.to_owned());
+
+        let (board, alg) = played.split_once(' ').ok_or_else(malformed)?;
+        let board: u32 = board.parse().map_err(|_| malformed())?;
+        let coords = Coordinates::new((board as usize % 3, board as usize / 3));
+
+        let color = game.next;
+        let san = game.to_san(coords, alg)?;
+
+        game.make_move(coords, alg)?;
+
+        Ok(PlayedMove {
+          board,
+          alg: alg.to_owned(),
+          san,
+          color,
+          position: game.clone(),
+        })
+      })
+      .collect()
+  }
+
+  /// Result of the whole game: three boards in a row won by the same color, or a draw once every
+  /// board is decided without such a row.
+  pub fn end(&self) -> EndResult {
+    const LINES: [[(usize, usize); 3]; 8] = [
+      [(0, 0), (0, 1), (0, 2)],
+      [(1, 0), (1, 1), (1, 2)],
+      [(2, 0), (2, 1), (2, 2)],
+      [(0, 0), (1, 0), (2, 0)],
+      [(0, 1), (1, 1), (2, 1)],
+      [(0, 2), (1, 2), (2, 2)],
+      [(0, 0), (1, 1), (2, 2)],
+      [(0, 2), (1, 1), (2, 0)],
+    ];
+
+    for line in LINES {
+      let [first, rest @ ..] = line.map(|(col, row)| &self.chesses[col][row].end);
+
+      if matches!(first, EndResult::Color(_)) && rest.iter().all(|end| *end == first) {
+        return first.clone();
+      }
+    }
+
+    if self
+      .chesses
+      .iter()
+      .flatten()
+      .all(|chess| chess.end != EndResult::None(true))
+    {
+      return EndResult::Draw(true);
+    }
+
+    EndResult::None(true)
+  }
+
+  pub fn to_fen(&self) -> Result<String, Error> {
     let mut res = "".to_owned();
 
     for boards in &self.chesses {
@@ -71,11 +140,11 @@ impl TicTacToe {
     Ok(res)
   }
 
-  pub fn from_fen(input: &str) -> Result<Self, FenError> {
+  pub fn from_fen(input: &str) -> Result<Self, Error> {
     let mut parts = input.split('+');
 
     if parts.clone().count() != 2 {
-      return Err(FenError::InvalidFormat);
+      return Err(Error::InvalidFen);
     }
 
     let boards_part = parts.next().unwrap();
@@ -83,7 +152,7 @@ impl TicTacToe {
     let mut boards = boards_part.split('\\');
 
     if boards.clone().count() != 9 {
-      return Err(FenError::InvalidFormat);
+      return Err(Error::InvalidFen);
     }
 
     let mut chesses = vec![];
@@ -104,15 +173,15 @@ impl TicTacToe {
     let next = match parts.next().unwrap() {
       "w" => Color::White,
       "b" => Color::Black,
-      _ => return Err(FenError::InvalidFormat),
+      _ => return Err(Error::InvalidFen),
     };
 
     Ok(TicTacToe { chesses, next })
   }
 
-  pub fn get_board(&self, board: Coordinates) -> Result<&ChessBoard, TicError> {
+  pub fn get_board(&self, board: Coordinates) -> Result<&ChessBoard, Error> {
     if board.col > 2 || board.row > 2 {
-      return Err(TicError::InvalidCoords);
+      return Err(Error::WrongBoard);
     }
 
     Ok(&self.chesses[board.col][board.row])
@@ -152,7 +221,13 @@ impl From<chesstactoe::TicTacToe> for TicTacToe {
 
     for (i, row) in chesses.iter_mut().enumerate() {
       for (j, chess) in row.iter_mut().enumerate().take(3) {
-        *chess = ChessBoard::parse_fen(&value.chesses[i * 3 + j].fen).unwrap()
+        let board = &value.chesses[i * 3 + j];
+
+        *chess = ChessBoard::parse_fen(&board.fen).unwrap();
+
+        if let Some(end) = &board.end_result {
+          chess.end = end.clone();
+        }
       }
     }
 
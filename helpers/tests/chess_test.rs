@@ -5,7 +5,7 @@ pub mod tests {
     chess::{ChessBoard, PieceName},
     chesstactoe::{chess::EndResult, Color},
     tictactoe::TicTacToe,
-    Coordinates, FenError,
+    Coordinates, Error,
   };
 
   const COORDS: Coordinates = Coordinates::new((0, 0));
@@ -371,7 +371,7 @@ pub mod tests {
 
     let fen_error = ChessBoard::try_from(INVALID_FEN).err().unwrap();
 
-    assert_eq!(String::from("InvalidFormat"), format!("{fen_error}"));
+    assert_eq!(String::from("Not a valid FEN"), format!("{fen_error}"));
 
     assert_eq!(Coordinates { row: 0, col: 0 }, Coordinates::new((0, 0)));
 
@@ -388,7 +388,7 @@ pub mod tests {
     assert_eq!(String::from("Pawn"), format!("{}", PieceName::PAWN));
 
     assert_eq!(
-      String::from("MoveError(InvalidMove)"),
+      String::from("There's no queen on b1"),
       format!(
         "{}",
         ChessBoard::default()
@@ -399,21 +399,219 @@ pub mod tests {
     );
 
     assert_eq!(
-      FenError::InvalidFormat,
+      Error::InvalidFen,
       ChessBoard::try_from("0 0 0 0 0 0").err().unwrap()
     );
     assert_eq!(
-      FenError::InvalidFormat,
+      Error::InvalidFen,
       ChessBoard::try_from("f/f/f/f/f/f/f/f 0 0 0 0 0")
         .err()
         .unwrap()
     );
     assert_eq!(
-      FenError::InvalidFormat,
+      Error::InvalidFen,
       ChessBoard::try_from("ppppppp/f/f/f/f/f/f/f 0 0 0 0 0")
         .err()
         .unwrap()
     );
-    // assert_eq!(FenError::InvalidFormat, ChessBoard::try_from("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 1 1"));
+    // assert_eq!(Error::InvalidFen, ChessBoard::try_from("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 1 1"));
+  }
+
+  #[test]
+  fn tic_tac_toe_row() {
+    let mut tic = TicTacToe::default();
+
+    assert_eq!(tic.end(), EndResult::None(true));
+
+    for row in 0..3 {
+      tic.chesses[row][row].end = EndResult::Color(Color::Black as i32);
+    }
+
+    assert_eq!(tic.end(), EndResult::Color(Color::Black as i32));
+
+    assert!(tic.make_move(Coordinates::new((0, 1)), "e2e4").is_err());
+  }
+
+  #[test]
+  fn tic_tac_toe_draw() {
+    let mut tic = TicTacToe::default();
+
+    let ends = [
+      [Color::White, Color::Black, Color::White],
+      [Color::White, Color::Black, Color::Black],
+      [Color::Black, Color::White, Color::White],
+    ];
+
+    for (col, colors) in ends.iter().enumerate() {
+      for (row, color) in colors.iter().enumerate() {
+        tic.chesses[col][row].end = EndResult::Color(*color as i32);
+      }
+    }
+
+    tic.chesses[2][2].end = EndResult::Draw(true);
+
+    assert_eq!(tic.end(), EndResult::Draw(true));
+  }
+
+  #[test]
+  fn replays_moves_in_san() {
+    let moves = [
+      "4 e2e4", "4 d7d5", "4 e4xd5", "0 e7e5", "0 Ng1f3", "0 Nb8c6", "0 d2d3", "0 Ng8f6", "0 Nb1d2",
+      "2 f7f6", "2 e2e4", "2 g7g5", "2 Qd1h5",
+    ];
+
+    let played = TicTacToe::replay(moves).unwrap();
+
+    let sans: Vec<&str> = played.iter().map(|played| played.san.as_str()).collect();
+
+    assert_eq!(
+      sans,
+      ["e4", "d5", "exd5", "e5", "Nf3", "Nc6", "d3", "Nf6", "Nbd2", "f6", "e4", "g5", "Qh5+"]
+    );
+
+    assert_eq!(played[2].board, 4);
+    assert_eq!(played[2].color, Color::White);
+    assert_eq!(played[3].color, Color::Black);
+    assert_eq!(
+      played[2]
+        .position
+        .get_board(Coordinates::new((1, 1)))
+        .unwrap()
+        .to_fen(Color::Black)
+        .unwrap(),
+      "rnbqkbnr/ppp1pppp/8/3P4/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 2"
+    );
+
+    assert!(TicTacToe::replay(["4 e2e5"]).is_err());
+    assert!(TicTacToe::replay(["e2e4"]).is_err());
+  }
+
+  #[test]
+  fn lists_legal_moves() {
+    let mut chess = ChessBoard::default();
+
+    let tiles = |chess: &ChessBoard, from: &str, next: Color| {
+      let mut tiles: Vec<String> = chess
+        .legal_moves(ChessBoard::get_square(from).unwrap(), next)
+        .into_iter()
+        .map(|(to, _)| ChessBoard::get_tile(to).unwrap())
+        .collect();
+      tiles.sort();
+      tiles
+    };
+
+    assert_eq!(tiles(&chess, "b1", Color::White), ["a3", "c3"]);
+    assert_eq!(tiles(&chess, "e2", Color::White), ["e3", "e4"]);
+    assert!(tiles(&chess, "e7", Color::White).is_empty());
+    assert!(tiles(&chess, "e4", Color::White).is_empty());
+
+    for (alg, next) in [
+      ("e2e4", Color::White),
+      ("f7f6", Color::Black),
+      ("Ng1f3", Color::White),
+      ("g7g5", Color::Black),
+      ("Bf1c4", Color::White),
+      ("d7d5", Color::Black),
+    ] {
+      chess.make_move(alg, next).unwrap();
+    }
+
+    assert!(tiles(&chess, "e1", Color::White).contains(&"g1".to_owned()));
+    assert!(tiles(&chess, "e4", Color::White).contains(&"d5".to_owned()));
+    assert_eq!(
+      chess
+        .notation(
+          ChessBoard::get_square("e1").unwrap(),
+          ChessBoard::get_square("g1").unwrap(),
+          None
+        )
+        .unwrap(),
+      "O-O"
+    );
+
+    assert!(!chess.in_check(Color::Black));
+    chess.make_move("Bc4xd5", Color::White).unwrap();
+    chess.make_move("a7a6", Color::Black).unwrap();
+    chess.make_move("Nf3h4", Color::White).unwrap();
+    chess.make_move("a6a5", Color::Black).unwrap();
+    chess.make_move("Qd1h5", Color::White).unwrap();
+    assert!(chess.in_check(Color::Black));
+    assert!(!chess.in_check(Color::White));
+  }
+
+  #[test]
+  fn explains_rejected_moves() {
+    let mut tic = TicTacToe::default();
+
+    assert_eq!(tic.check_move(COORDS, "e7e5"), Err(Error::NotYourPiece));
+    assert_eq!(
+      tic.check_move(COORDS, "Nc1d3"),
+      Err(Error::NoPiece {
+        piece: PieceName::KNIGHT,
+        square: "c1".to_owned()
+      })
+    );
+    assert_eq!(tic.check_move(COORDS, "e2e5"), Err(Error::IllegalMove));
+    assert_eq!(
+      tic.check_move(COORDS, "e2-e4"),
+      Err(Error::MalformedNotation("e2-e4".to_owned()))
+    );
+    assert_eq!(
+      tic.check_move(Coordinates::new((3, 0)), "e2e4"),
+      Err(Error::WrongBoard)
+    );
+    assert_eq!(tic.validate_move(COORDS, "e2e5"), Ok(false));
+    assert!(tic.validate_move(COORDS, "e2-e4").is_err());
+
+    let pinned = ChessBoard::parse_fen("4k3/4r3/8/8/8/8/4B3/4K3 w - - 0 1").unwrap();
+    assert_eq!(
+      pinned.check_move("Be2d3", Color::White),
+      Err(Error::PiecePinned)
+    );
+    assert_eq!(
+      pinned.check_move("Ke1e2", Color::White),
+      Err(Error::IllegalMove)
+    );
+
+    let checked = ChessBoard::parse_fen("4k3/4r3/8/8/8/8/3B4/4K3 w - - 0 1").unwrap();
+    assert_eq!(
+      checked.check_move("Bd2c3", Color::White),
+      Err(Error::KingInCheck)
+    );
+    assert_eq!(checked.check_move("Bd2e3", Color::White), Ok(()));
+    assert_eq!(
+      checked.check_move("Ke1e2", Color::White),
+      Err(Error::KingInCheck)
+    );
+
+    // Fool's mate ends the board, which then takes no more moves.
+    for alg in ["f2f3", "e7e5", "g2g4", "Qd8h4"] {
+      tic.make_move(COORDS, alg).unwrap();
+    }
+
+    assert_eq!(
+      tic.get_board(COORDS).unwrap().end,
+      EndResult::Color(Color::Black as i32)
+    );
+    assert_eq!(tic.check_move(COORDS, "e2e4"), Err(Error::BoardDecided));
+
+    for row in 0..3 {
+      tic.chesses[row][row].end = EndResult::Color(Color::Black as i32);
+    }
+
+    assert_eq!(
+      tic.check_move(Coordinates::new((0, 1)), "e2e4"),
+      Err(Error::GameOver)
+    );
+  }
+
+  #[test]
+  fn stalemate_draws_the_board() {
+    let mut chess = ChessBoard::parse_fen("7k/8/5QK1/8/8/8/8/8 w - - 0 1").unwrap();
+
+    chess.make_move("Qf6f7", Color::White).unwrap();
+
+    assert!(!chess.in_check(Color::Black));
+    assert_eq!(chess.end, EndResult::Draw(true));
   }
 }
@@ -0,0 +1,99 @@
+pub mod tests {
+  use helpers::rating::{Outcome, Rating, DEFAULT_TAU};
+
+  fn assert_close(actual: f64, expected: f64, precision: f64) {
+    assert!(
+      (actual - expected).abs() < precision,
+      "{actual} is not within {precision} of {expected}"
+    );
+  }
+
+  fn glickman_player() -> Rating {
+    Rating {
+      rating: 1500.0,
+      deviation: 200.0,
+      volatility: 0.06,
+    }
+  }
+
+  // The worked example from section 3 of http://www.glicko.net/glicko/glicko2.pdf
+  #[test]
+  fn glickman_example() {
+    let outcomes = [
+      Outcome {
+        opponent: Rating {
+          rating: 1400.0,
+          deviation: 30.0,
+          volatility: 0.06,
+        },
+        score: 1.0,
+      },
+      Outcome {
+        opponent: Rating {
+          rating: 1550.0,
+          deviation: 100.0,
+          volatility: 0.06,
+        },
+        score: 0.0,
+      },
+      Outcome {
+        opponent: Rating {
+          rating: 1700.0,
+          deviation: 300.0,
+          volatility: 0.06,
+        },
+        score: 0.0,
+      },
+    ];
+
+    let rating = glickman_player().update(&outcomes, DEFAULT_TAU);
+
+    assert_close(rating.rating, 1464.06, 0.01);
+    assert_close(rating.deviation, 151.52, 0.01);
+    assert_close(rating.volatility, 0.05999, 0.00001);
+  }
+
+  #[test]
+  fn inactive_period_only_widens_deviation() {
+    let rating = glickman_player().update(&[], DEFAULT_TAU);
+
+    assert_eq!(rating.rating, 1500.0);
+    assert_eq!(rating.volatility, 0.06);
+    assert_close(rating.deviation, 200.27, 0.01);
+  }
+
+  #[test]
+  fn draw_between_equals_keeps_rating() {
+    let rating = Rating::default().update(
+      &[Outcome {
+        opponent: Rating::default(),
+        score: 0.5,
+      }],
+      DEFAULT_TAU,
+    );
+
+    assert_close(rating.rating, 1500.0, 0.000001);
+    assert!(rating.deviation < Rating::default().deviation);
+  }
+
+  #[test]
+  fn winner_gains_what_loser_loses() {
+    let winner = Rating::default().update(
+      &[Outcome {
+        opponent: Rating::default(),
+        score: 1.0,
+      }],
+      DEFAULT_TAU,
+    );
+    let loser = Rating::default().update(
+      &[Outcome {
+        opponent: Rating::default(),
+        score: 0.0,
+      }],
+      DEFAULT_TAU,
+    );
+
+    assert!(winner.rating > 1500.0);
+    assert_close(winner.rating - 1500.0, 1500.0 - loser.rating, 0.000001);
+  }
+}
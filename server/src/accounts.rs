@@ -0,0 +1,321 @@
+use std::{
+  fs,
+  path::{Path, PathBuf},
+  sync::Mutex,
+};
+
+use argon2::{
+  password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+  Argon2,
+};
+use dashmap::{mapref::entry::Entry, DashMap};
+use helpers::{
+  chesstactoe::{chess::EndResult, Color, PlayerInfo},
+  rating::{Outcome, Rating, DEFAULT_TAU},
+};
+use serde::{Deserialize, Serialize};
+use tonic::Status;
+use uuid::Uuid;
+
+const MIN_PASSWORD_LENGTH: usize = 8;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Stats {
+  pub rating: f64,
+  pub deviation: f64,
+  pub volatility: f64,
+  pub wins: u32,
+  pub draws: u32,
+  pub losses: u32,
+}
+
+impl Default for Stats {
+  fn default() -> Self {
+    let rating = Rating::default();
+
+    Stats {
+      rating: rating.rating,
+      deviation: rating.deviation,
+      volatility: rating.volatility,
+      wins: 0,
+      draws: 0,
+      losses: 0,
+    }
+  }
+}
+
+impl Stats {
+  pub fn rating(&self) -> Rating {
+    Rating {
+      rating: self.rating,
+      deviation: self.deviation,
+      volatility: self.volatility,
+    }
+  }
+
+  pub fn games_played(&self) -> u32 {
+    self.wins + self.draws + self.losses
+  }
+
+  fn record(&mut self, score: f64, opponent: Rating, rated: bool) {
+    if rated {
+      let rating = self
+        .rating()
+        .update(&[Outcome { opponent, score }], DEFAULT_TAU);
+
+      self.rating = rating.rating;
+      self.deviation = rating.deviation;
+      self.volatility = rating.volatility;
+    }
+
+    if score == 1.0 {
+      self.wins += 1;
+    } else if score == 0.0 {
+      self.losses += 1;
+    } else {
+      self.draws += 1;
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Account {
+  pub id: Uuid,
+  pub username: String,
+  password_hash: String,
+  #[serde(default)]
+  pub stats: Stats,
+}
+
+impl Account {
+  pub fn info(&self) -> PlayerInfo {
+    PlayerInfo {
+      id: self.id.to_string(),
+      username: self.username.clone(),
+      rating: self.stats.rating,
+      deviation: self.stats.deviation,
+      games_played: self.stats.games_played(),
+      wins: self.stats.wins,
+      draws: self.stats.draws,
+      losses: self.stats.losses,
+    }
+  }
+}
+
+/// Player accounts, kept in memory and written back to a JSON file on every change.
+#[derive(Debug, Default)]
+pub struct AccountStore {
+  path: Option<PathBuf>,
+  accounts: DashMap<Uuid, Account>,
+  usernames: DashMap<String, Uuid>,
+  /// Held for a whole write, so an older snapshot can't land after a newer one.
+  saving: Mutex<()>,
+}
+
+fn username_key(username: &str) -> String {
+  username.to_lowercase()
+}
+
+fn validate_username(username: &str) -> Result<(), Status> {
+  let valid = (3..=20).contains(&username.len())
+    && username
+      .chars()
+      .all(|char| char.is_ascii_alphanumeric() || char == '_' || char == '-');
+
+  if !valid {
+    return Err(Status::invalid_argument(
+      "Usernames must be 3-20 letters, digits, '_' or '-'",
+    ));
+  }
+
+  Ok(())
+}
+
+impl AccountStore {
+  pub fn in_memory() -> Self {
+    AccountStore::default()
+  }
+
+  pub fn open(path: impl AsRef<Path>) -> Result<Self, Box<dyn std::error::Error>> {
+    let path = path.as_ref().to_owned();
+
+    let accounts = DashMap::new();
+    let usernames = DashMap::new();
+
+    if path.exists() {
+      let saved: Vec<Account> = serde_json::from_str(&fs::read_to_string(&path)?)?;
+
+      for account in saved {
+        usernames.insert(username_key(&account.username), account.id);
+        accounts.insert(account.id, account);
+      }
+    }
+
+    Ok(AccountStore {
+      path: Some(path),
+      accounts,
+      usernames,
+      saving: Mutex::default(),
+    })
+  }
+
+  fn save(&self) -> Result<(), Status> {
+    let Some(path) = &self.path else {
+      return Ok(());
+    };
+
+    let _saving = self.saving.lock().unwrap();
+
+    let accounts: Vec<Account> = self
+      .accounts
+      .iter()
+      .map(|acc| acc.value().clone())
+      .collect();
+
+    let json =
+      serde_json::to_string_pretty(&accounts).map_err(|e| Status::internal(e.to_string()))?;
+
+    // Renaming is atomic, so a crash mid-write leaves the previous file intact.
+    let temp = path.with_extension("json.tmp");
+
+    fs::write(&temp, json).map_err(|e| Status::internal(e.to_string()))?;
+    fs::rename(&temp, path).map_err(|e| Status::internal(e.to_string()))
+  }
+
+  pub fn register(&self, username: &str, password: &str) -> Result<Account, Status> {
+    validate_username(username)?;
+
+    if password.len() < MIN_PASSWORD_LENGTH {
+      return Err(Status::invalid_argument(format!(
+        "Passwords must be at least {MIN_PASSWORD_LENGTH} characters long"
+      )));
+    }
+
+    let password_hash = Argon2::default()
+      .hash_password(password.as_bytes(), &SaltString::generate(&mut OsRng))
+      .map_err(|e| Status::internal(e.to_string()))?
+      .to_string();
+
+    let account = Account {
+      id: Uuid::new_v4(),
+      username: username.to_owned(),
+      password_hash,
+      stats: Stats::default(),
+    };
+
+    match self.usernames.entry(username_key(username)) {
+      Entry::Occupied(_) => return Err(Status::already_exists("Username is already taken")),
+      Entry::Vacant(entry) => {
+        entry.insert(account.id);
+      }
+    };
+
+    self.accounts.insert(account.id, account.clone());
+
+    self.save()?;
+
+    Ok(account)
+  }
+
+  pub fn login(&self, username: &str, password: &str) -> Result<Account, Status> {
+    let invalid = || Status::unauthenticated("Invalid username or password");
+
+    let id = *self
+      .usernames
+      .get(&username_key(username))
+      .ok_or_else(invalid)?;
+
+    let account = self.get(&id).ok_or_else(invalid)?;
+
+    let hash =
+      PasswordHash::new(&account.password_hash).map_err(|e| Status::internal(e.to_string()))?;
+
+    Argon2::default()
+      .verify_password(password.as_bytes(), &hash)
+      .map_err(|_| invalid())?;
+
+    Ok(account)
+  }
+
+  pub fn get(&self, id: &Uuid) -> Option<Account> {
+    self.accounts.get(id).map(|account| account.value().clone())
+  }
+
+  pub fn find(&self, username: &str) -> Option<Account> {
+    let id = *self.usernames.get(&username_key(username))?;
+
+    self.get(&id)
+  }
+
+  /// Updates win/draw/loss counts of both players after a finished game, and their ratings too if
+  /// the game was rated.
+  pub fn record_result(
+    &self,
+    white: Uuid,
+    black: Uuid,
+    end: &EndResult,
+    rated: bool,
+  ) -> Result<(), Status> {
+    if white == black {
+      return Ok(());
+    }
+
+    let white_score = match end {
+      EndResult::Color(color) if *color == Color::White as i32 => 1.0,
+      EndResult::Color(_) => 0.0,
+      EndResult::Draw(_) => 0.5,
+      EndResult::None(_) => return Ok(()),
+    };
+
+    let (Some(white_stats), Some(black_stats)) = (
+      self.get(&white).map(|acc| acc.stats),
+      self.get(&black).map(|acc| acc.stats),
+    ) else {
+      return Err(Status::not_found("Player account no longer exists"));
+    };
+
+    if let Some(mut account) = self.accounts.get_mut(&white) {
+      account
+        .stats
+        .record(white_score, black_stats.rating(), rated);
+    }
+
+    if let Some(mut account) = self.accounts.get_mut(&black) {
+      account
+        .stats
+        .record(1.0 - white_score, white_stats.rating(), rated);
+    }
+
+    self.save()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn concurrent_registrations_all_reach_the_file() {
+    let dir = std::env::temp_dir().join(format!("chesstactoe-{}", Uuid::new_v4()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("players.json");
+
+    let store = AccountStore::open(&path).unwrap();
+
+    std::thread::scope(|scope| {
+      for i in 0..4 {
+        let store = &store;
+        scope.spawn(move || store.register(&format!("player{i}"), "password1").unwrap());
+      }
+    });
+
+    let reopened = AccountStore::open(&path).unwrap();
+    for i in 0..4 {
+      reopened.login(&format!("player{i}"), "password1").unwrap();
+    }
+
+    assert!(!path.with_extension("json.tmp").exists());
+
+    fs::remove_dir_all(dir).unwrap();
+  }
+}
@@ -0,0 +1,394 @@
+use std::{
+  collections::HashMap,
+  fs::{File, OpenOptions},
+  io::{self, BufRead, BufReader, ErrorKind, Read, Seek, SeekFrom, Write},
+  path::{Path, PathBuf},
+  sync::Mutex,
+};
+
+use helpers::chesstactoe::{ArchivedGameInfo, GameOutcome, GameSettings, TimeControl};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::events::LoggedEvent;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ArchivedResult {
+  White,
+  Black,
+  Draw,
+  /// Neither player came back before the disconnect grace period ran out.
+  Abandoned,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchivedChat {
+  pub username: String,
+  pub text: String,
+  /// Seconds since the Unix epoch.
+  pub sent_at: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchivedGame {
+  pub id: Uuid,
+  pub white: Uuid,
+  pub black: Uuid,
+  pub white_name: String,
+  pub black_name: String,
+  pub initial: u32,
+  pub increment: u32,
+  pub rated: bool,
+  /// Whether anyone may look the game up, rather than only its players. Games archived before it
+  /// was kept count as private.
+  #[serde(default)]
+  pub public: bool,
+  pub result: ArchivedResult,
+  pub forfeit: bool,
+  /// Final position, in the format of `TicTacToe::to_fen`.
+  pub fen: String,
+  /// In the `"<board> <alg>"` format of `lastMove`. Missing from games archived before moves were
+  /// kept.
+  #[serde(default)]
+  pub moves: Vec<String>,
+  /// Missing from games archived before chat existed.
+  #[serde(default)]
+  pub chat: Vec<ArchivedChat>,
+  /// Seconds since the Unix epoch.
+  pub finished_at: u64,
+  /// The game's whole log. Missing from games archived before it was kept.
+  #[serde(default)]
+  pub events: Vec<LoggedEvent>,
+}
+
+impl ArchivedGame {
+  pub fn visible_to(&self, player: Uuid) -> bool {
+    self.public || self.white == player || self.black == player
+  }
+
+  pub fn info(&self) -> ArchivedGameInfo {
+    let outcome = match self.result {
+      ArchivedResult::White => GameOutcome::WhiteWon,
+      ArchivedResult::Black => GameOutcome::BlackWon,
+      ArchivedResult::Draw => GameOutcome::Drawn,
+      ArchivedResult::Abandoned => GameOutcome::Abandoned,
+    };
+
+    ArchivedGameInfo {
+      id: self.id.to_string(),
+      white: self.white_name.clone(),
+      black: self.black_name.clone(),
+      settings: Some(GameSettings {
+        time_control: Some(TimeControl {
+          initial: self.initial,
+          increment: self.increment,
+        }),
+        rated: self.rated,
+        ..Default::default()
+      }),
+      outcome: outcome as i32,
+      forfeit: self.forfeit,
+      moves: self.moves.clone(),
+      fen: self.fen.clone(),
+      finished_at: self.finished_at,
+    }
+  }
+}
+
+/// Finished games moved out of `GameService`. With a file, every game is appended to it as a JSON
+/// line and only its place in the file stays in memory, so games are read back one at a time
+/// when asked for.
+#[derive(Debug, Default)]
+pub struct GameArchive {
+  path: Option<PathBuf>,
+  /// Held while appending too, so concurrent stores can't interleave their lines.
+  index: Mutex<Index>,
+}
+
+#[derive(Debug, Default)]
+struct Index {
+  /// Oldest first.
+  entries: Vec<Entry>,
+  positions: HashMap<Uuid, usize>,
+  /// Games kept whole: all of them without a file, and any the file couldn't take.
+  unwritten: Vec<ArchivedGame>,
+}
+
+#[derive(Debug)]
+struct Entry {
+  white: Uuid,
+  black: Uuid,
+  location: Location,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Location {
+  /// Where the game's line starts in the file, and its length without the newline.
+  File { offset: u64, len: usize },
+  /// Position in `Index::unwritten`.
+  Memory(usize),
+}
+
+/// The fields of an archived line the index needs, read without the moves, chat and events.
+#[derive(Deserialize)]
+struct Header {
+  id: Uuid,
+  white: Uuid,
+  black: Uuid,
+}
+
+impl Index {
+  fn push(&mut self, header: Header, location: Location) {
+    self.positions.insert(header.id, self.entries.len());
+    self.entries.push(Entry {
+      white: header.white,
+      black: header.black,
+      location,
+    });
+  }
+
+  fn keep(&mut self, game: ArchivedGame) {
+    let header = Header {
+      id: game.id,
+      white: game.white,
+      black: game.black,
+    };
+
+    self.push(header, Location::Memory(self.unwritten.len()));
+    self.unwritten.push(game);
+  }
+}
+
+impl GameArchive {
+  pub fn in_memory() -> Self {
+    GameArchive::default()
+  }
+
+  /// Indexes the games archived so far. Lines of the file that don't parse are skipped.
+  pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+    let path = path.as_ref().to_owned();
+
+    let mut index = Index::default();
+
+    let file = match File::open(&path) {
+      Ok(file) => Some(file),
+      Err(err) if err.kind() == ErrorKind::NotFound => None,
+      Err(err) => return Err(err),
+    };
+
+    if let Some(file) = file {
+      let mut reader = BufReader::new(file);
+      let mut line = String::new();
+      let mut offset = 0;
+
+      loop {
+        line.clear();
+
+        let read = reader.read_line(&mut line)?;
+        if read == 0 {
+          break;
+        }
+
+        let trimmed = line.trim_end_matches('\n');
+        if let Ok(header) = serde_json::from_str::<Header>(trimmed) {
+          let len = trimmed.len();
+          index.push(header, Location::File { offset, len });
+        }
+
+        offset += read as u64;
+      }
+    }
+
+    Ok(GameArchive {
+      path: Some(path),
+      index: Mutex::new(index),
+    })
+  }
+
+  /// Appends the game to the file if there is one. A game that couldn't be written is kept in
+  /// memory and still served until the server stops.
+  pub fn store(&self, game: ArchivedGame) -> io::Result<()> {
+    let mut index = self.index.lock().unwrap();
+
+    let Some(path) = &self.path else {
+      index.keep(game);
+      return Ok(());
+    };
+
+    match append(path, &game) {
+      Ok(location) => {
+        let header = Header {
+          id: game.id,
+          white: game.white,
+          black: game.black,
+        };
+        index.push(header, location);
+
+        Ok(())
+      }
+      Err(err) => {
+        index.keep(game);
+        Err(err)
+      }
+    }
+  }
+
+  /// Reads every archived game, oldest first.
+  pub fn games(&self) -> Vec<ArchivedGame> {
+    let locations: Vec<Location> = {
+      let index = self.index.lock().unwrap();
+      index.entries.iter().map(|entry| entry.location).collect()
+    };
+
+    self.load(locations)
+  }
+
+  pub fn get(&self, id: Uuid) -> Option<ArchivedGame> {
+    let location = {
+      let index = self.index.lock().unwrap();
+      index.entries[*index.positions.get(&id)?].location
+    };
+
+    self.load([location]).pop()
+  }
+
+  /// The player's most recent games, newest first.
+  pub fn player_games(&self, player: Uuid, limit: usize) -> Vec<ArchivedGame> {
+    let locations: Vec<Location> = {
+      let index = self.index.lock().unwrap();
+
+      index
+        .entries
+        .iter()
+        .rev()
+        .filter(|entry| entry.white == player || entry.black == player)
+        .take(limit)
+        .map(|entry| entry.location)
+        .collect()
+    };
+
+    self.load(locations)
+  }
+
+  /// Reads the games at `locations`, leaving out the ones that can't be read back.
+  fn load(&self, locations: impl IntoIterator<Item = Location>) -> Vec<ArchivedGame> {
+    // Lines are only ever appended, so a location stays valid after the index is unlocked.
+    let mut file = None;
+    let mut games = vec![];
+
+    for location in locations {
+      let game = match (location, &self.path) {
+        (Location::Memory(position), _) => {
+          let index = self.index.lock().unwrap();
+          index.unwritten.get(position).cloned()
+        }
+        (Location::File { offset, len }, Some(path)) => read_line(&mut file, path, offset, len)
+          .map_err(|err| tracing::error!(%err, offset, "Couldn't read archived game"))
+          .ok(),
+        (Location::File { .. }, None) => None,
+      };
+
+      games.extend(game);
+    }
+
+    games
+  }
+}
+
+/// Appends the game as a line and returns where it went.
+fn append(path: &Path, game: &ArchivedGame) -> io::Result<Location> {
+  let json = serde_json::to_string(game)?;
+
+  let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+
+  // Stores hold the index lock, so nothing else writes to the file in between.
+  let offset = file.metadata()?.len();
+
+  file.write_all(format!("{json}\n").as_bytes())?;
+
+  Ok(Location::File {
+    offset,
+    len: json.len(),
+  })
+}
+
+/// Reads the line at `offset`, opening the file the first time it's needed.
+fn read_line(
+  file: &mut Option<File>,
+  path: &Path,
+  offset: u64,
+  len: usize,
+) -> io::Result<ArchivedGame> {
+  let file = match file {
+    Some(file) => file,
+    None => file.insert(File::open(path)?),
+  };
+
+  let mut line = vec![0; len];
+
+  file.seek(SeekFrom::Start(offset))?;
+  file.read_exact(&mut line)?;
+
+  Ok(serde_json::from_slice(&line)?)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn game(white: Uuid, black: Uuid) -> ArchivedGame {
+    ArchivedGame {
+      id: Uuid::new_v4(),
+      white,
+      black,
+      white_name: "alice".to_owned(),
+      black_name: "bob".to_owned(),
+      initial: 0,
+      increment: 0,
+      rated: false,
+      public: true,
+      result: ArchivedResult::Draw,
+      forfeit: false,
+      fen: String::new(),
+      moves: vec![],
+      chat: vec![],
+      finished_at: 0,
+      events: vec![],
+    }
+  }
+
+  #[test]
+  fn indexes_the_file_and_reads_games_back_from_it() {
+    let dir = std::env::temp_dir().join(format!("chesstactoe-{}", Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("games.jsonl");
+
+    let (alice, bob) = (Uuid::new_v4(), Uuid::new_v4());
+
+    let archive = GameArchive::open(&path).unwrap();
+
+    std::thread::scope(|scope| {
+      for _ in 0..8 {
+        let archive = &archive;
+        scope.spawn(move || archive.store(game(alice, bob)).unwrap());
+      }
+    });
+
+    let stored = archive.games();
+    assert_eq!(stored.len(), 8);
+    assert_eq!(archive.get(stored[3].id), Some(stored[3].clone()));
+    assert_eq!(
+      archive.player_games(bob, 2),
+      [stored[7].clone(), stored[6].clone()]
+    );
+    assert!(archive.player_games(Uuid::new_v4(), 2).is_empty());
+
+    // Every line made it to the file whole, and only their places are read back into memory.
+    let reopened = GameArchive::open(&path).unwrap();
+    assert!(reopened.index.lock().unwrap().unwritten.is_empty());
+    assert_eq!(reopened.games(), stored);
+    assert_eq!(reopened.get(stored[5].id), Some(stored[5].clone()));
+
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+}
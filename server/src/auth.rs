@@ -0,0 +1,212 @@
+use std::{
+  sync::Arc,
+  time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use helpers::chesstactoe::{auth_server::Auth, LoginRequest, LoginResponse, RegisterRequest};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use tonic::{service::Interceptor, Request, Response, Status};
+use tracing::{field, Span};
+use uuid::Uuid;
+
+use crate::accounts::{Account, AccountStore};
+
+const SESSION_LENGTH: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// The authenticated player, attached to every `Game` request by [`AuthInterceptor`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Player {
+  pub id: Uuid,
+  pub username: String,
+}
+
+impl From<Account> for Player {
+  fn from(account: Account) -> Self {
+    Player {
+      id: account.id,
+      username: account.username,
+    }
+  }
+}
+
+/// Signs and verifies session tokens of the form `<payload>.<signature>`, where the payload is
+/// `<player id>:<expiry in unix seconds>`.
+#[derive(Clone)]
+pub struct SessionKeys {
+  secret: Arc<Vec<u8>>,
+}
+
+impl SessionKeys {
+  pub fn new(secret: &[u8]) -> Self {
+    SessionKeys {
+      secret: Arc::new(secret.to_owned()),
+    }
+  }
+
+  pub fn generate() -> Self {
+    let mut secret = vec![0; 32];
+    rand::thread_rng().fill_bytes(&mut secret);
+
+    SessionKeys {
+      secret: Arc::new(secret),
+    }
+  }
+
+  fn mac(&self) -> Hmac<Sha256> {
+    Hmac::<Sha256>::new_from_slice(&self.secret).expect("HMAC accepts keys of any length")
+  }
+
+  pub fn issue(&self, player: Uuid) -> String {
+    let expiry = (SystemTime::now() + SESSION_LENGTH)
+      .duration_since(UNIX_EPOCH)
+      .unwrap()
+      .as_secs();
+
+    let payload = format!("{player}:{expiry}");
+
+    let mut mac = self.mac();
+    mac.update(payload.as_bytes());
+
+    format!(
+      "{}.{}",
+      URL_SAFE_NO_PAD.encode(payload),
+      URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes())
+    )
+  }
+
+  pub fn verify(&self, token: &str) -> Result<Uuid, Status> {
+    let invalid = || Status::unauthenticated("Invalid session token");
+
+    let (payload, signature) = token.split_once('.').ok_or_else(invalid)?;
+
+    let payload = URL_SAFE_NO_PAD.decode(payload).map_err(|_| invalid())?;
+    let signature = URL_SAFE_NO_PAD.decode(signature).map_err(|_| invalid())?;
+
+    let mut mac = self.mac();
+    mac.update(&payload);
+    mac.verify_slice(&signature).map_err(|_| invalid())?;
+
+    let payload = String::from_utf8(payload).map_err(|_| invalid())?;
+    let (player, expiry) = payload.split_once(':').ok_or_else(invalid)?;
+
+    let expiry = expiry.parse::<u64>().map_err(|_| invalid())?;
+
+    if UNIX_EPOCH + Duration::from_secs(expiry) < SystemTime::now() {
+      return Err(Status::unauthenticated("Session expired"));
+    }
+
+    Uuid::parse_str(player).map_err(|_| invalid())
+  }
+}
+
+/// Checks the `authorization: Bearer <token>` metadata and attaches the [`Player`] to the request.
+#[derive(Clone)]
+pub struct AuthInterceptor {
+  pub keys: SessionKeys,
+  pub accounts: Arc<AccountStore>,
+}
+
+impl Interceptor for AuthInterceptor {
+  fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+    let token = request
+      .metadata()
+      .get(helpers::auth::AUTHORIZATION)
+      .and_then(|value| value.to_str().ok())
+      .and_then(|value| value.strip_prefix(helpers::auth::BEARER))
+      .ok_or_else(|| Status::unauthenticated("Missing session token"))?;
+
+    let id = self.keys.verify(token)?;
+
+    let account = self
+      .accounts
+      .get(&id)
+      .ok_or_else(|| Status::unauthenticated("Account no longer exists"))?;
+
+    request.extensions_mut().insert(Player::from(account));
+
+    Ok(request)
+  }
+}
+
+/// The player the request was authenticated as, who is also added to the RPC's span.
+pub fn player<T>(request: &Request<T>) -> Result<Player, Status> {
+  let player = request
+    .extensions()
+    .get::<Player>()
+    .cloned()
+    .ok_or_else(|| Status::unauthenticated("Not logged in"))?;
+
+  Span::current().record("player", field::display(player.id));
+
+  Ok(player)
+}
+
+/// Lets requests through only with `authorization: Bearer <admin token>`.
+#[derive(Clone)]
+pub struct AdminInterceptor {
+  pub token: Arc<str>,
+}
+
+impl Interceptor for AdminInterceptor {
+  fn call(&mut self, request: Request<()>) -> Result<Request<()>, Status> {
+    let token = request
+      .metadata()
+      .get(helpers::auth::AUTHORIZATION)
+      .and_then(|value| value.to_str().ok())
+      .and_then(|value| value.strip_prefix(helpers::auth::BEARER))
+      .ok_or_else(|| Status::unauthenticated("Missing admin token"))?;
+
+    // Comparing digests keeps the time taken from depending on how much of the token matched.
+    if Sha256::digest(token) != Sha256::digest(self.token.as_bytes()) {
+      return Err(Status::permission_denied("Wrong admin token"));
+    }
+
+    Ok(request)
+  }
+}
+
+pub struct AuthService {
+  pub keys: SessionKeys,
+  pub accounts: Arc<AccountStore>,
+}
+
+impl AuthService {
+  fn session(&self, account: Account) -> Response<LoginResponse> {
+    Span::current().record("player", field::display(account.id));
+
+    Response::new(LoginResponse {
+      token: self.keys.issue(account.id),
+      player_id: account.id.to_string(),
+      username: account.username,
+    })
+  }
+}
+
+#[tonic::async_trait]
+impl Auth for AuthService {
+  async fn register(
+    &self,
+    request: Request<RegisterRequest>,
+  ) -> Result<Response<LoginResponse>, Status> {
+    let request = request.into_inner();
+
+    let account = self
+      .accounts
+      .register(&request.username, &request.password)?;
+
+    tracing::info!(username = %account.username, "Registered");
+
+    Ok(self.session(account))
+  }
+
+  async fn login(&self, request: Request<LoginRequest>) -> Result<Response<LoginResponse>, Status> {
+    let request = request.into_inner();
+
+    let account = self.accounts.login(&request.username, &request.password)?;
+
+    Ok(self.session(account))
+  }
+}
@@ -0,0 +1,429 @@
+use std::sync::{Arc, Mutex};
+
+use dashmap::DashMap;
+use helpers::chesstactoe::{Color, SubscribeBoardResponse};
+use tokio::sync::{
+  mpsc::{self, Receiver, Sender},
+  Notify,
+};
+use tonic::Status;
+use uuid::Uuid;
+
+pub type Update = Result<SubscribeBoardResponse, Status>;
+
+#[derive(Debug, Default)]
+struct Subscriber {
+  /// The seat's open `SubscribeBoard` stream, if it has one.
+  stream: Option<Stream>,
+  /// Everything the seat has been told, chat included, ready for its next stream.
+  latest: Option<SubscribeBoardResponse>,
+}
+
+/// A seat's stream. Dropping it ends the stream once what's pending has gone out.
+#[derive(Debug)]
+struct Stream {
+  sender: Sender<Update>,
+  outbox: Arc<Outbox>,
+}
+
+impl Stream {
+  fn open(&self) -> bool {
+    !self.sender.is_closed()
+  }
+}
+
+impl Drop for Stream {
+  fn drop(&mut self) {
+    self.outbox.finish(None);
+  }
+}
+
+/// What's waiting to go out on a stream. A task per stream sends it, so a reader that's slow only
+/// holds up its own task. Updates arriving meanwhile are folded into one, and the reader skips
+/// ahead to the latest state without missing any chat.
+#[derive(Debug, Default)]
+struct Outbox {
+  pending: Mutex<Pending>,
+  ready: Notify,
+}
+
+#[derive(Debug, Default)]
+struct Pending {
+  update: Option<SubscribeBoardResponse>,
+  /// The stream ends once `update` is out, with `status` if there is one.
+  closing: bool,
+  status: Option<Status>,
+}
+
+impl Outbox {
+  fn push(&self, response: SubscribeBoardResponse) {
+    let mut pending = self.pending.lock().unwrap();
+
+    match &mut pending.update {
+      Some(update) => {
+        update.color = response.color;
+        update.game = response.game;
+        update.request = response.request;
+        update.chat.extend(response.chat);
+
+        if !response.seat.is_empty() {
+          update.seat = response.seat;
+        }
+
+        if !response.notice.is_empty() {
+          update.notice = response.notice;
+        }
+      }
+      None => pending.update = Some(response),
+    }
+
+    drop(pending);
+
+    self.ready.notify_one();
+  }
+
+  fn finish(&self, status: Option<Status>) {
+    let mut pending = self.pending.lock().unwrap();
+
+    if !pending.closing {
+      pending.closing = true;
+      pending.status = status;
+    }
+
+    drop(pending);
+
+    self.ready.notify_one();
+  }
+
+  /// Sends what's pending until the stream ends or its reader goes away.
+  async fn pump(self: Arc<Self>, sender: Sender<Update>) {
+    loop {
+      let (update, closing, status) = {
+        let mut pending = self.pending.lock().unwrap();
+        (
+          pending.update.take(),
+          pending.closing,
+          pending.status.take(),
+        )
+      };
+
+      let idle = update.is_none();
+
+      if let Some(update) = update {
+        if sender.send(Ok(update)).await.is_err() {
+          return;
+        }
+      }
+
+      if closing {
+        if let Some(status) = status {
+          sender.send(Err(status)).await.unwrap_or(());
+        }
+
+        return;
+      }
+
+      if idle {
+        tokio::select! {
+          _ = self.ready.notified() => {}
+          _ = sender.closed() => return,
+        }
+      }
+    }
+  }
+}
+
+/// Board updates for every seat's stream. Every update is kept for the seat, and sending never
+/// waits on a stream, so seats that never subscribed or whose stream closed or stalled can't fail
+/// or hold up whoever changed the game. Seats without a stream pick up the latest state when they
+/// subscribe again.
+#[derive(Debug, Default)]
+pub struct Broadcaster {
+  subscribers: DashMap<Uuid, Subscriber>,
+  channel_size: usize,
+}
+
+impl Broadcaster {
+  pub fn new(channel_size: usize) -> Self {
+    Broadcaster {
+      channel_size,
+      ..Default::default()
+    }
+  }
+
+  /// Opens a new stream for `seat`, ending any earlier one. It starts with the state buffered for
+  /// the seat, or with `current` if the seat hasn't been sent anything yet.
+  pub fn subscribe(&self, seat: Uuid, current: SubscribeBoardResponse) -> Receiver<Update> {
+    let (sender, receiver) = mpsc::channel(self.channel_size.max(1));
+
+    let mut subscriber = self.subscribers.entry(seat).or_default();
+
+    let latest = subscriber.latest.get_or_insert(current).clone();
+
+    // The channel is new, so there's room for the first message.
+    sender.try_send(Ok(latest)).unwrap_or(());
+
+    let outbox = Arc::<Outbox>::default();
+    tokio::spawn(outbox.clone().pump(sender.clone()));
+
+    subscriber.stream = Some(Stream { sender, outbox });
+
+    receiver
+  }
+
+  /// Whether the seat has a stream that's still open.
+  pub fn connected(&self, seat: Uuid) -> bool {
+    self
+      .subscribers
+      .get(&seat)
+      .is_some_and(|subscriber| subscriber.stream.as_ref().is_some_and(Stream::open))
+  }
+
+  /// Keeps an update for the seat's next stream and passes it on to the open one, if any.
+  pub fn notify(&self, seat: Uuid, color: Color, mut response: SubscribeBoardResponse) {
+    response.color = color as i32;
+
+    let mut subscriber = self.subscribers.entry(seat).or_default();
+
+    match &mut subscriber.latest {
+      Some(latest) => {
+        latest.color = response.color;
+        latest.game = response.game.clone();
+        latest.request = response.request.clone();
+        latest.chat.extend(response.chat.iter().cloned());
+      }
+      None => subscriber.latest = Some(response.clone()),
+    }
+
+    match &subscriber.stream {
+      Some(stream) if stream.open() => stream.outbox.push(response),
+      Some(_) => subscriber.stream = None,
+      None => {}
+    }
+  }
+
+  /// Sends the same update to both players of a game.
+  pub fn broadcast(&self, (white, black): (Uuid, Uuid), response: SubscribeBoardResponse) {
+    self.notify(white, Color::White, response.clone());
+    self.notify(black, Color::Black, response);
+  }
+
+  /// Sends `notice` along with the latest state on every open stream, without buffering it. Returns
+  /// how many streams it went out on.
+  pub fn announce(&self, notice: &str) -> usize {
+    let mut sent = 0;
+
+    for subscriber in self.subscribers.iter() {
+      let (Some(stream), Some(latest)) = (&subscriber.stream, &subscriber.latest) else {
+        continue;
+      };
+
+      if !stream.open() {
+        continue;
+      }
+
+      stream.outbox.push(SubscribeBoardResponse {
+        color: latest.color,
+        game: latest.game.clone(),
+        request: latest.request.clone(),
+        notice: notice.to_owned(),
+        ..Default::default()
+      });
+
+      sent += 1;
+    }
+
+    sent
+  }
+
+  /// Ends the seat's stream with `status` once what's pending has gone out, for players an admin
+  /// removed from a game.
+  pub fn close(&self, seat: Uuid, status: Status) {
+    let stream = self
+      .subscribers
+      .get_mut(&seat)
+      .and_then(|mut subscriber| subscriber.stream.take());
+
+    if let Some(stream) = stream {
+      stream.outbox.finish(Some(status));
+    }
+  }
+
+  /// Hands the stream of `old` over to `new`, which starts without a buffered state.
+  pub fn move_seat(&self, old: Uuid, new: Uuid) {
+    if let Some((_, mut subscriber)) = self.subscribers.remove(&old) {
+      self.subscribers.insert(
+        new,
+        Subscriber {
+          stream: subscriber.stream.take(),
+          latest: None,
+        },
+      );
+    }
+  }
+
+  pub fn remove(&self, seat: Uuid) {
+    self.subscribers.remove(&seat);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use helpers::chesstactoe::{ChatMessage, TicTacToe};
+
+  use super::*;
+
+  fn state(last_move: &str, chat: &[&str]) -> SubscribeBoardResponse {
+    SubscribeBoardResponse {
+      game: Some(TicTacToe {
+        last_move: last_move.to_owned(),
+        ..Default::default()
+      }),
+      chat: chat
+        .iter()
+        .map(|text| ChatMessage {
+          text: text.to_string(),
+          ..Default::default()
+        })
+        .collect(),
+      ..Default::default()
+    }
+  }
+
+  fn last_move(update: Update) -> String {
+    update.unwrap().game.unwrap().last_move
+  }
+
+  #[tokio::test]
+  async fn buffers_for_seats_that_never_subscribed() {
+    let broadcaster = Broadcaster::new(4);
+    let seat = Uuid::new_v4();
+
+    broadcaster.notify(seat, Color::White, state("4 e2e4", &[]));
+
+    assert!(!broadcaster.connected(seat));
+
+    let mut rx = broadcaster.subscribe(seat, state("", &[]));
+    assert_eq!(last_move(rx.recv().await.unwrap()), "4 e2e4");
+  }
+
+  #[tokio::test]
+  async fn a_slow_stream_skips_to_the_latest_state() {
+    let broadcaster = Broadcaster::new(1);
+    let seat = Uuid::new_v4();
+
+    // Nobody reads the first message yet, so the channel is full while the moves come in.
+    let mut slow = broadcaster.subscribe(seat, state("", &[]));
+
+    broadcaster.notify(seat, Color::White, state("4 e2e4", &["hi"]));
+    broadcaster.notify(seat, Color::White, state("0 e7e5", &["gl"]));
+
+    assert!(broadcaster.connected(seat));
+    assert_eq!(last_move(slow.recv().await.unwrap()), "");
+
+    let caught_up = slow.recv().await.unwrap().unwrap();
+    assert_eq!(caught_up.game.unwrap().last_move, "0 e7e5");
+    let chat: Vec<&str> = caught_up.chat.iter().map(|msg| msg.text.as_str()).collect();
+    assert_eq!(chat, ["hi", "gl"]);
+
+    broadcaster.notify(seat, Color::White, state("1 d2d4", &[]));
+    assert_eq!(last_move(slow.recv().await.unwrap()), "1 d2d4");
+  }
+
+  #[tokio::test]
+  async fn survives_dropped_streams_and_buffers_for_the_next_one() {
+    let broadcaster = Broadcaster::new(4);
+    let seat = Uuid::new_v4();
+
+    let rx = broadcaster.subscribe(seat, state("", &["hi"]));
+    assert!(broadcaster.connected(seat));
+    drop(rx);
+    assert!(!broadcaster.connected(seat));
+
+    broadcaster.notify(seat, Color::Black, state("4 e2e4", &["gl"]));
+    broadcaster.notify(seat, Color::Black, state("0 e7e5", &[]));
+
+    let mut rx = broadcaster.subscribe(seat, state("", &[]));
+    let latest = rx.recv().await.unwrap().unwrap();
+
+    assert_eq!(latest.game.unwrap().last_move, "0 e7e5");
+    assert_eq!(latest.color, Color::Black as i32);
+    let chat: Vec<&str> = latest.chat.iter().map(|msg| msg.text.as_str()).collect();
+    assert_eq!(chat, ["hi", "gl"]);
+
+    broadcaster.notify(seat, Color::Black, state("1 d2d4", &[]));
+    assert_eq!(last_move(rx.recv().await.unwrap()), "1 d2d4");
+  }
+
+  #[tokio::test]
+  async fn a_new_stream_ends_the_one_it_replaces() {
+    let broadcaster = Broadcaster::new(1);
+    let seat = Uuid::new_v4();
+
+    let mut old = broadcaster.subscribe(seat, state("", &[]));
+    old.recv().await.unwrap().unwrap();
+
+    let mut rx = broadcaster.subscribe(seat, state("", &[]));
+    assert!(old.recv().await.is_none());
+
+    assert!(broadcaster.connected(seat));
+    rx.recv().await.unwrap().unwrap();
+
+    broadcaster.notify(seat, Color::White, state("4 e2e4", &[]));
+    assert_eq!(last_move(rx.recv().await.unwrap()), "4 e2e4");
+  }
+
+  #[tokio::test]
+  async fn closing_sends_what_is_pending_first() {
+    let broadcaster = Broadcaster::new(1);
+    let seat = Uuid::new_v4();
+
+    let mut rx = broadcaster.subscribe(seat, state("", &[]));
+
+    broadcaster.notify(seat, Color::White, state("4 e2e4", &[]));
+    broadcaster.close(seat, Status::aborted("kicked"));
+
+    rx.recv().await.unwrap().unwrap();
+    assert_eq!(last_move(rx.recv().await.unwrap()), "4 e2e4");
+    assert_eq!(rx.recv().await.unwrap().unwrap_err().message(), "kicked");
+    assert!(rx.recv().await.is_none());
+  }
+
+  #[tokio::test]
+  async fn announces_without_buffering() {
+    let broadcaster = Broadcaster::new(4);
+    let (seat, gone) = (Uuid::new_v4(), Uuid::new_v4());
+
+    let mut rx = broadcaster.subscribe(seat, state("4 e2e4", &["hi"]));
+    rx.recv().await.unwrap().unwrap();
+    drop(broadcaster.subscribe(gone, state("", &[])));
+
+    assert_eq!(broadcaster.announce("Restarting soon"), 1);
+
+    let update = rx.recv().await.unwrap().unwrap();
+    assert_eq!(update.notice, "Restarting soon");
+    assert_eq!(update.game.unwrap().last_move, "4 e2e4");
+    assert!(update.chat.is_empty());
+
+    let mut rx = broadcaster.subscribe(seat, state("", &[]));
+    assert!(rx.recv().await.unwrap().unwrap().notice.is_empty());
+  }
+
+  #[tokio::test]
+  async fn moves_the_stream_to_a_new_seat() {
+    let broadcaster = Broadcaster::new(4);
+    let (old, new) = (Uuid::new_v4(), Uuid::new_v4());
+
+    let mut rx = broadcaster.subscribe(old, state("4 e2e4", &["hi"]));
+    rx.recv().await.unwrap().unwrap();
+
+    broadcaster.move_seat(old, new);
+    assert!(!broadcaster.connected(old));
+    assert!(broadcaster.connected(new));
+
+    broadcaster.notify(new, Color::Black, state("", &[]));
+    let update = rx.recv().await.unwrap().unwrap();
+    assert_eq!(update.color, Color::Black as i32);
+    assert!(update.chat.is_empty());
+  }
+}
@@ -0,0 +1,113 @@
+use std::{
+  collections::VecDeque,
+  time::{Duration, Instant},
+};
+
+use serde::Deserialize;
+use tonic::Status;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ChatConfig {
+  /// Longest message accepted, in characters.
+  pub max_length: usize,
+  /// Messages a player can send within `window_secs` before being told to slow down.
+  pub burst: usize,
+  pub window_secs: u64,
+}
+
+impl Default for ChatConfig {
+  fn default() -> Self {
+    ChatConfig {
+      max_length: 300,
+      burst: 5,
+      window_secs: 10,
+    }
+  }
+}
+
+impl ChatConfig {
+  /// Trims a message, rejecting empty and overly long ones.
+  pub fn validate(&self, text: &str) -> Result<String, Status> {
+    let text = text.trim();
+
+    if text.is_empty() {
+      return Err(Status::invalid_argument("Message is empty"));
+    }
+
+    if text.chars().count() > self.max_length {
+      return Err(Status::invalid_argument(format!(
+        "Messages can't be longer than {} characters",
+        self.max_length
+      )));
+    }
+
+    Ok(text.to_owned())
+  }
+}
+
+/// Times of one player's recent messages in a game.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RateLimit {
+  sent: VecDeque<Instant>,
+}
+
+impl RateLimit {
+  /// Records a message sent at `now` unless the player already used up the burst.
+  pub fn allow(&mut self, config: &ChatConfig, now: Instant) -> bool {
+    let window = Duration::from_secs(config.window_secs);
+
+    while self
+      .sent
+      .front()
+      .is_some_and(|sent| now.saturating_duration_since(*sent) >= window)
+    {
+      self.sent.pop_front();
+    }
+
+    if self.sent.len() >= config.burst {
+      return false;
+    }
+
+    self.sent.push_back(now);
+
+    true
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn trims_and_limits_length() {
+    let config = ChatConfig {
+      max_length: 5,
+      ..ChatConfig::default()
+    };
+
+    assert_eq!(config.validate("  hi \n").unwrap(), "hi");
+    assert_eq!(config.validate("ééééé").unwrap(), "ééééé");
+    assert!(config.validate("   ").is_err());
+    assert!(config.validate("hello there").is_err());
+  }
+
+  #[test]
+  fn limits_bursts() {
+    let config = ChatConfig {
+      burst: 2,
+      window_secs: 10,
+      ..ChatConfig::default()
+    };
+    let mut limit = RateLimit::default();
+    let now = Instant::now();
+
+    assert!(limit.allow(&config, now));
+    assert!(limit.allow(&config, now + Duration::from_secs(1)));
+    assert!(!limit.allow(&config, now + Duration::from_secs(2)));
+
+    assert!(limit.allow(&config, now + Duration::from_secs(10)));
+    assert!(!limit.allow(&config, now + Duration::from_secs(10)));
+    assert!(limit.allow(&config, now + Duration::from_secs(11)));
+  }
+}
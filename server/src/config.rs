@@ -0,0 +1,294 @@
+//! Server settings. Values come from the defaults below, then an optional TOML file, then
+//! `CHESSTACTOE_*` environment variables and finally command line flags, each overriding the last.
+
+use std::{error::Error, fs, net::SocketAddr, path::PathBuf, time::Duration};
+
+use clap::{Parser, ValueEnum};
+use helpers::chesstactoe::TimeControl;
+use serde::Deserialize;
+
+use crate::{
+  chat::ChatConfig,
+  game::{ReaperConfig, ServiceConfig},
+  matchmaking::MatchmakingConfig,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Storage {
+  /// Nothing survives a restart.
+  Memory,
+  /// Accounts and archived games are kept in the data directory.
+  File,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+  /// One readable line per event.
+  #[default]
+  Text,
+  /// One JSON object per event, for log collectors.
+  Json,
+}
+
+/// Timeouts in seconds, see [`ReaperConfig`].
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Timeouts {
+  pub reaper_interval: u64,
+  pub lobby: u64,
+  pub disconnect_grace: u64,
+  pub finished: u64,
+}
+
+impl Default for Timeouts {
+  fn default() -> Self {
+    let reaper = ReaperConfig::default();
+
+    Timeouts {
+      reaper_interval: reaper.interval.as_secs(),
+      lobby: reaper.lobby_timeout.as_secs(),
+      disconnect_grace: reaper.disconnect_grace.as_secs(),
+      finished: reaper.finished_timeout.as_secs(),
+    }
+  }
+}
+
+/// Used for games requested without a time control, in seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(default)]
+pub struct DefaultTimeControl {
+  pub initial: u32,
+  pub increment: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Config {
+  pub bind: SocketAddr,
+  /// Address of the plain HTTP server with the Prometheus `/metrics` endpoint. Only local by
+  /// default, since it answers anyone who can reach it without a session.
+  pub metrics_bind: SocketAddr,
+  /// Origins allowed to make gRPC-Web calls from a browser. Empty allows every origin.
+  pub cors_origins: Vec<String>,
+  pub storage: Storage,
+  pub data_dir: PathBuf,
+  /// Key for signing session tokens. A random one is generated when missing.
+  pub secret: Option<String>,
+  /// Bearer token of the `Admin` service, which is only served when this is set.
+  pub admin_token: Option<String>,
+  pub max_games: Option<usize>,
+  /// Which events to log, in the `RUST_LOG` syntax like `info,server=debug`.
+  pub log: String,
+  pub log_format: LogFormat,
+  /// Buffer size of the board subscription streams.
+  pub channel_size: usize,
+  pub default_time_control: DefaultTimeControl,
+  pub timeouts: Timeouts,
+  pub matchmaking: MatchmakingConfig,
+  pub chat: ChatConfig,
+}
+
+impl Default for Config {
+  fn default() -> Self {
+    Config {
+      bind: ([0, 0, 0, 0], 50051).into(),
+      metrics_bind: ([127, 0, 0, 1], 9090).into(),
+      cors_origins: vec![],
+      storage: Storage::File,
+      data_dir: PathBuf::from("."),
+      secret: None,
+      admin_token: None,
+      max_games: None,
+      log: "info".to_owned(),
+      log_format: LogFormat::Text,
+      channel_size: 4,
+      default_time_control: DefaultTimeControl::default(),
+      timeouts: Timeouts::default(),
+      matchmaking: MatchmakingConfig::default(),
+      chat: ChatConfig::default(),
+    }
+  }
+}
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Chess-Tac-Toe game server")]
+pub struct Args {
+  /// TOML file to read the settings from
+  #[arg(short, long, env = "CHESSTACTOE_CONFIG")]
+  pub config: Option<PathBuf>,
+  /// Address to listen on
+  #[arg(short, long, env = "CHESSTACTOE_BIND")]
+  pub bind: Option<SocketAddr>,
+  /// Address to serve the Prometheus metrics on, 127.0.0.1:9090 unless set
+  #[arg(long, env = "CHESSTACTOE_METRICS_BIND")]
+  pub metrics_bind: Option<SocketAddr>,
+  /// Origin allowed to make gRPC-Web calls from a browser, can be repeated
+  #[arg(
+    long = "cors-origin",
+    env = "CHESSTACTOE_CORS_ORIGINS",
+    value_delimiter = ','
+  )]
+  pub cors_origins: Vec<String>,
+  #[arg(long, env = "CHESSTACTOE_STORAGE")]
+  pub storage: Option<Storage>,
+  /// Directory of the account and game archive files
+  #[arg(long, env = "CHESSTACTOE_DATA_DIR")]
+  pub data_dir: Option<PathBuf>,
+  /// Key for signing session tokens
+  #[arg(long, env = "CHESSTACTOE_SECRET", hide_env_values = true)]
+  pub secret: Option<String>,
+  /// Token for the admin service, which is off without one
+  #[arg(long, env = "CHESSTACTOE_ADMIN_TOKEN", hide_env_values = true)]
+  pub admin_token: Option<String>,
+  /// Maximum number of games played at the same time
+  #[arg(long, env = "CHESSTACTOE_MAX_GAMES")]
+  pub max_games: Option<usize>,
+  /// Which events to log, like `info,server=debug`
+  #[arg(long, env = "CHESSTACTOE_LOG")]
+  pub log: Option<String>,
+  #[arg(long, env = "CHESSTACTOE_LOG_FORMAT")]
+  pub log_format: Option<LogFormat>,
+  #[arg(long, env = "CHESSTACTOE_CHANNEL_SIZE")]
+  pub channel_size: Option<usize>,
+  /// Seconds before an unjoined lobby is closed
+  #[arg(long, env = "CHESSTACTOE_LOBBY_TIMEOUT")]
+  pub lobby_timeout: Option<u64>,
+  /// Seconds a disconnected player has to come back before forfeiting
+  #[arg(long, env = "CHESSTACTOE_DISCONNECT_GRACE")]
+  pub disconnect_grace: Option<u64>,
+  /// Seconds a finished game is kept in memory before being archived
+  #[arg(long, env = "CHESSTACTOE_FINISHED_TIMEOUT")]
+  pub finished_timeout: Option<u64>,
+  /// Initial time in seconds for games requested without a time control
+  #[arg(long, env = "CHESSTACTOE_DEFAULT_INITIAL")]
+  pub default_initial: Option<u32>,
+  /// Increment in seconds for games requested without a time control
+  #[arg(long, env = "CHESSTACTOE_DEFAULT_INCREMENT")]
+  pub default_increment: Option<u32>,
+}
+
+impl Config {
+  pub fn load(args: Args) -> Result<Self, Box<dyn Error>> {
+    let config = match &args.config {
+      Some(path) => toml::from_str(&fs::read_to_string(path)?)?,
+      None => Config::default(),
+    };
+
+    Ok(config.apply(args))
+  }
+
+  fn apply(mut self, args: Args) -> Self {
+    self.bind = args.bind.unwrap_or(self.bind);
+    self.metrics_bind = args.metrics_bind.unwrap_or(self.metrics_bind);
+    self.storage = args.storage.unwrap_or(self.storage);
+
+    if !args.cors_origins.is_empty() {
+      self.cors_origins = args.cors_origins;
+    }
+
+    self.data_dir = args.data_dir.unwrap_or(self.data_dir);
+    self.secret = args.secret.or(self.secret);
+    self.admin_token = args.admin_token.or(self.admin_token);
+    self.max_games = args.max_games.or(self.max_games);
+    self.log = args.log.unwrap_or(self.log);
+    self.log_format = args.log_format.unwrap_or(self.log_format);
+    self.channel_size = args.channel_size.unwrap_or(self.channel_size);
+
+    let timeouts = &mut self.timeouts;
+    timeouts.lobby = args.lobby_timeout.unwrap_or(timeouts.lobby);
+    timeouts.disconnect_grace = args.disconnect_grace.unwrap_or(timeouts.disconnect_grace);
+    timeouts.finished = args.finished_timeout.unwrap_or(timeouts.finished);
+
+    let time_control = &mut self.default_time_control;
+    time_control.initial = args.default_initial.unwrap_or(time_control.initial);
+    time_control.increment = args.default_increment.unwrap_or(time_control.increment);
+
+    self
+  }
+
+  pub fn reaper(&self) -> ReaperConfig {
+    ReaperConfig {
+      interval: Duration::from_secs(self.timeouts.reaper_interval.max(1)),
+      lobby_timeout: Duration::from_secs(self.timeouts.lobby),
+      disconnect_grace: Duration::from_secs(self.timeouts.disconnect_grace),
+      finished_timeout: Duration::from_secs(self.timeouts.finished),
+    }
+  }
+
+  pub fn service(&self) -> ServiceConfig {
+    ServiceConfig {
+      max_games: self.max_games,
+      channel_size: self.channel_size.max(1),
+      default_time_control: TimeControl {
+        initial: self.default_time_control.initial,
+        increment: self.default_time_control.increment,
+      },
+      matchmaking: self.matchmaking,
+      chat: self.chat,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn args(flags: &[&str]) -> Args {
+    Args::try_parse_from(std::iter::once("server").chain(flags.iter().copied())).unwrap()
+  }
+
+  #[test]
+  fn reads_partial_files() {
+    let config: Config = toml::from_str(
+      r#"
+        bind = "127.0.0.1:6000"
+        storage = "memory"
+        max_games = 10
+
+        [timeouts]
+        lobby = 60
+
+        [matchmaking]
+        widening = 25.0
+      "#,
+    )
+    .unwrap();
+
+    assert_eq!(config.bind, "127.0.0.1:6000".parse().unwrap());
+    assert_eq!(config.metrics_bind, "127.0.0.1:9090".parse().unwrap());
+    assert_eq!(config.storage, Storage::Memory);
+    assert_eq!(config.max_games, Some(10));
+    assert_eq!(config.timeouts.lobby, 60);
+    assert_eq!(config.timeouts.finished, Timeouts::default().finished);
+    assert_eq!(config.matchmaking.widening, 25.0);
+    assert_eq!(
+      config.matchmaking.initial_window,
+      MatchmakingConfig::default().initial_window
+    );
+  }
+
+  #[test]
+  fn flags_override_the_file() {
+    let file = Config {
+      max_games: Some(10),
+      storage: Storage::Memory,
+      ..Config::default()
+    };
+
+    let config = file.apply(args(&[
+      "--bind",
+      "127.0.0.1:7000",
+      "--max-games",
+      "20",
+      "--disconnect-grace",
+      "5",
+    ]));
+
+    assert_eq!(config.bind, "127.0.0.1:7000".parse().unwrap());
+    assert_eq!(config.max_games, Some(20));
+    assert_eq!(config.storage, Storage::Memory);
+    assert_eq!(config.reaper().disconnect_grace, Duration::from_secs(5));
+  }
+}
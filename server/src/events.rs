@@ -0,0 +1,475 @@
+use helpers::{
+  chesstactoe::{
+    chess::EndResult, game_event::Kind, Adjudicated, ChatMessage, Color, GameCreated, GameEvent,
+    GameOutcome, GameSettings, PlayedMove, TimeControl,
+  },
+  tictactoe::TicTacToe,
+  Coordinates, Error,
+};
+use serde::{Deserialize, Serialize};
+
+/// A player's color as the log stores it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Side {
+  White,
+  Black,
+}
+
+impl From<Color> for Side {
+  fn from(color: Color) -> Self {
+    match color {
+      Color::White => Side::White,
+      Color::Black => Side::Black,
+    }
+  }
+}
+
+impl From<Side> for Color {
+  fn from(side: Side) -> Self {
+    match side {
+      Side::White => Color::White,
+      Side::Black => Color::Black,
+    }
+  }
+}
+
+/// Something that happened in a game. The server keeps no clock, so the only way to run out of
+/// time is staying disconnected past the grace period, logged as [`Event::TimedOut`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Event {
+  /// First event of every game.
+  Created {
+    white: String,
+    black: String,
+    initial: u32,
+    increment: u32,
+    rated: bool,
+  },
+  /// The player opened the board for the first time.
+  Joined {
+    side: Side,
+  },
+  Move {
+    side: Side,
+    board: u32,
+    alg: String,
+  },
+  Chat {
+    side: Side,
+    username: String,
+    text: String,
+  },
+  RematchOffered {
+    side: Side,
+  },
+  RematchDeclined {
+    side: Side,
+  },
+  RematchAccepted {
+    side: Side,
+  },
+  /// Asks the other player to undo the last move.
+  TakebackRequested {
+    side: Side,
+  },
+  TakebackDeclined {
+    side: Side,
+  },
+  /// The last move is undone.
+  TakebackAccepted {
+    side: Side,
+  },
+  DrawOffered {
+    side: Side,
+  },
+  DrawDeclined {
+    side: Side,
+  },
+  /// Ends the game as a draw.
+  DrawAccepted {
+    side: Side,
+  },
+  /// The player gave the game up.
+  Resigned {
+    side: Side,
+  },
+  /// The player stayed disconnected past the grace period. A player who times out alone loses,
+  /// and a game both players left ends without a result.
+  TimedOut {
+    side: Side,
+  },
+  /// The other player stayed away for longer than the disconnect grace period. Only found in logs
+  /// from before [`Event::TimedOut`].
+  Forfeit {
+    winner: Side,
+  },
+  /// An admin ended the game, as a draw when there's no winner.
+  Adjudicated {
+    winner: Option<Side>,
+    reason: String,
+  },
+  /// An admin called the game off without a result.
+  Aborted {
+    reason: String,
+  },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LoggedEvent {
+  /// Position in the game's log, starting at 0.
+  pub seq: u64,
+  /// Seconds since the Unix epoch.
+  pub at: u64,
+  pub event: Event,
+}
+
+impl LoggedEvent {
+  pub fn info(&self) -> GameEvent {
+    let color = |side: &Side| Color::from(*side) as i32;
+
+    let kind = match &self.event {
+      Event::Created {
+        white,
+        black,
+        initial,
+        increment,
+        rated,
+      } => Kind::Created(GameCreated {
+        white: white.clone(),
+        black: black.clone(),
+        settings: Some(GameSettings {
+          time_control: Some(TimeControl {
+            initial: *initial,
+            increment: *increment,
+          }),
+          rated: *rated,
+          ..Default::default()
+        }),
+      }),
+      Event::Joined { side } => Kind::Joined(color(side)),
+      Event::Move { side, board, alg } => Kind::Move(PlayedMove {
+        color: color(side),
+        board: *board,
+        alg: alg.clone(),
+      }),
+      Event::Chat {
+        side,
+        username,
+        text,
+      } => Kind::Chat(ChatMessage {
+        color: color(side),
+        username: username.clone(),
+        text: text.clone(),
+        sent_at: self.at,
+      }),
+      Event::RematchOffered { side } => Kind::RematchOffered(color(side)),
+      Event::RematchDeclined { side } => Kind::RematchDeclined(color(side)),
+      Event::RematchAccepted { side } => Kind::RematchAccepted(color(side)),
+      Event::TakebackRequested { side } => Kind::TakebackRequested(color(side)),
+      Event::TakebackDeclined { side } => Kind::TakebackDeclined(color(side)),
+      Event::TakebackAccepted { side } => Kind::TakebackAccepted(color(side)),
+      Event::DrawOffered { side } => Kind::DrawOffered(color(side)),
+      Event::DrawDeclined { side } => Kind::DrawDeclined(color(side)),
+      Event::DrawAccepted { side } => Kind::DrawAccepted(color(side)),
+      Event::Resigned { side } => Kind::Resigned(color(side)),
+      Event::TimedOut { side } => Kind::TimedOut(color(side)),
+      Event::Forfeit { winner } => Kind::Forfeit(color(winner)),
+      Event::Adjudicated { winner, reason } => Kind::Adjudicated(Adjudicated {
+        outcome: match winner {
+          Some(Side::White) => GameOutcome::WhiteWon,
+          Some(Side::Black) => GameOutcome::BlackWon,
+          None => GameOutcome::Drawn,
+        } as i32,
+        reason: reason.clone(),
+      }),
+      Event::Aborted { reason } => Kind::Adjudicated(Adjudicated {
+        outcome: GameOutcome::Abandoned as i32,
+        reason: reason.clone(),
+      }),
+    };
+
+    GameEvent {
+      seq: self.seq,
+      at: self.at,
+      kind: Some(kind),
+    }
+  }
+}
+
+/// What a game's events add up to.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GameState {
+  pub game: TicTacToe,
+  /// Board number and move of every move played, like `"4 e2e4"`.
+  pub moves: Vec<String>,
+  pub chat: Vec<ChatMessage>,
+  /// Player waiting for the other one to agree to undo the last move.
+  pub takeback_offer: Option<Color>,
+  /// Player waiting for the other one to accept a draw.
+  pub draw_offer: Option<Color>,
+  pub drawn: bool,
+  /// Player who gave the game up.
+  pub resigned: Option<Color>,
+  /// Players who stayed disconnected past the grace period.
+  pub timed_out: Vec<Color>,
+  /// Winner of a game decided by the other player disconnecting.
+  pub forfeit: Option<Color>,
+  /// Result an admin ended the game with.
+  pub adjudicated: Option<EndResult>,
+  /// Called off by an admin, so nobody won or lost.
+  pub aborted: bool,
+  /// Player waiting for the other one to accept a rematch.
+  pub rematch_offer: Option<Color>,
+  pub rematched: bool,
+  pub joined: Vec<Color>,
+}
+
+impl GameState {
+  /// Rebuilds the state from a game's log.
+  pub fn replay<'a>(events: impl IntoIterator<Item = &'a LoggedEvent>) -> Result<Self, Error> {
+    let mut state = GameState::default();
+
+    for logged in events {
+      state.apply(logged)?;
+    }
+
+    Ok(state)
+  }
+
+  /// Moves the state on by one event. Moves the rules don't allow are refused, leaving the state
+  /// as it was.
+  pub fn apply(&mut self, logged: &LoggedEvent) -> Result<(), Error> {
+    match &logged.event {
+      Event::Created { .. } => {}
+      Event::Joined { side } => self.joined.push((*side).into()),
+      Event::Move { side, board, alg } => {
+        if Color::from(*side) != self.game.next {
+          return Err(Error::NotYourTurn);
+        }
+
+        let coords = Coordinates::new(((board % 3) as usize, (board / 3) as usize));
+
+        self.game.make_move(coords, alg)?;
+        self.moves.push(format!("{board} {alg}"));
+
+        // Moving on is the same as turning down what the other player asked for.
+        self.takeback_offer = None;
+        if self.draw_offer != Some(Color::from(*side)) {
+          self.draw_offer = None;
+        }
+      }
+      Event::Chat {
+        side,
+        username,
+        text,
+      } => self.chat.push(ChatMessage {
+        color: Color::from(*side) as i32,
+        username: username.clone(),
+        text: text.clone(),
+        sent_at: logged.at,
+      }),
+      Event::RematchOffered { side } => self.rematch_offer = Some((*side).into()),
+      Event::RematchDeclined { .. } => self.rematch_offer = None,
+      Event::RematchAccepted { .. } => {
+        self.rematch_offer = None;
+        self.rematched = true;
+      }
+      Event::TakebackRequested { side } => self.takeback_offer = Some((*side).into()),
+      Event::TakebackDeclined { .. } => self.takeback_offer = None,
+      Event::TakebackAccepted { .. } => {
+        self.takeback_offer = None;
+        self.moves.pop();
+
+        // Boards can't unplay a move, so the game is played again up to the one before.
+        let replayed = TicTacToe::replay(self.moves.iter().map(String::as_str))?;
+        self.game = replayed
+          .last()
+          .map(|played| played.position.clone())
+          .unwrap_or_default();
+      }
+      Event::DrawOffered { side } => self.draw_offer = Some((*side).into()),
+      Event::DrawDeclined { .. } => self.draw_offer = None,
+      Event::DrawAccepted { .. } => {
+        self.draw_offer = None;
+        self.drawn = true;
+      }
+      Event::Resigned { side } => self.resigned = Some((*side).into()),
+      Event::TimedOut { side } => {
+        self.timed_out.push((*side).into());
+
+        self.forfeit = match self.timed_out[..] {
+          [Color::White] => Some(Color::Black),
+          [Color::Black] => Some(Color::White),
+          _ => None,
+        };
+      }
+      Event::Forfeit { winner } => self.forfeit = Some((*winner).into()),
+      Event::Adjudicated { winner, .. } => {
+        self.adjudicated = Some(match winner {
+          Some(winner) => EndResult::Color(Color::from(*winner) as i32),
+          None => EndResult::Draw(true),
+        })
+      }
+      Event::Aborted { .. } => self.aborted = true,
+    }
+
+    Ok(())
+  }
+
+  pub fn end(&self) -> EndResult {
+    if let Some(winner) = self.forfeit {
+      return EndResult::Color(winner as i32);
+    }
+
+    if let Some(loser) = self.resigned {
+      return EndResult::Color(1 - loser as i32);
+    }
+
+    if self.drawn {
+      return EndResult::Draw(true);
+    }
+
+    self.adjudicated.clone().unwrap_or_else(|| self.game.end())
+  }
+}
+
+/// Append-only record of everything that happened in a game, with the state it leads to.
+///
+/// A live game's log is only kept in memory. It reaches disk with the rest of the game once the
+/// game is archived, so games still in progress are lost when the server restarts.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GameLog {
+  events: Vec<LoggedEvent>,
+  state: GameState,
+}
+
+impl GameLog {
+  /// Appends an event unless the state refuses it.
+  pub fn record(&mut self, event: Event, at: u64) -> Result<&LoggedEvent, Error> {
+    let logged = LoggedEvent {
+      seq: self.events.len() as u64,
+      at,
+      event,
+    };
+
+    self.state.apply(&logged)?;
+    self.events.push(logged);
+
+    Ok(&self.events[self.events.len() - 1])
+  }
+
+  pub fn events(&self) -> &[LoggedEvent] {
+    &self.events
+  }
+
+  pub fn state(&self) -> &GameState {
+    &self.state
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn played(log: &mut GameLog, side: Side, board: u32, alg: &str) -> Result<(), Error> {
+    let event = Event::Move {
+      side,
+      board,
+      alg: alg.to_owned(),
+    };
+
+    log.record(event, 0).map(|_| ())
+  }
+
+  #[test]
+  fn refused_moves_stay_out_of_the_log() {
+    let mut log = GameLog::default();
+
+    played(&mut log, Side::White, 4, "e2e4").unwrap();
+    assert_eq!(
+      played(&mut log, Side::White, 0, "d2d4"),
+      Err(Error::NotYourTurn)
+    );
+    assert_eq!(
+      played(&mut log, Side::Black, 0, "e7e4"),
+      Err(Error::IllegalMove)
+    );
+    played(&mut log, Side::Black, 0, "e7e5").unwrap();
+
+    assert_eq!(log.events().len(), 2);
+    assert_eq!(log.events()[1].seq, 1);
+    assert_eq!(log.state().moves, ["4 e2e4", "0 e7e5"]);
+  }
+
+  #[test]
+  fn replaying_the_log_rebuilds_the_state() {
+    let mut log = GameLog::default();
+
+    let created = Event::Created {
+      white: "alice".to_owned(),
+      black: "bob".to_owned(),
+      initial: 300,
+      increment: 2,
+      rated: true,
+    };
+    log.record(created, 10).unwrap();
+    log.record(Event::Joined { side: Side::White }, 11).unwrap();
+    played(&mut log, Side::White, 4, "e2e4").unwrap();
+    let chat = Event::Chat {
+      side: Side::Black,
+      username: "bob".to_owned(),
+      text: "gl".to_owned(),
+    };
+    log.record(chat, 12).unwrap();
+    log
+      .record(
+        Event::Forfeit {
+          winner: Side::White,
+        },
+        13,
+      )
+      .unwrap();
+
+    let replayed = GameState::replay(log.events()).unwrap();
+
+    assert_eq!(&replayed, log.state());
+    assert_eq!(replayed.chat[0].sent_at, 12);
+    assert_eq!(replayed.end(), EndResult::Color(Color::White as i32));
+
+    // Survives the trip through the archive's JSON.
+    let json = serde_json::to_string(log.events()).unwrap();
+    let events: Vec<LoggedEvent> = serde_json::from_str(&json).unwrap();
+    assert_eq!(events, log.events());
+
+    assert!(matches!(
+      events[0].info().kind,
+      Some(Kind::Created(GameCreated { ref white, .. })) if white == "alice"
+    ));
+  }
+
+  #[test]
+  fn accepted_takebacks_undo_the_last_move() {
+    let mut log = GameLog::default();
+
+    played(&mut log, Side::White, 4, "e2e4").unwrap();
+    played(&mut log, Side::Black, 4, "e7e5").unwrap();
+    log
+      .record(Event::TakebackRequested { side: Side::Black }, 0)
+      .unwrap();
+    assert_eq!(log.state().takeback_offer, Some(Color::Black));
+    log
+      .record(Event::TakebackAccepted { side: Side::White }, 0)
+      .unwrap();
+
+    assert_eq!(log.state().takeback_offer, None);
+    assert_eq!(log.state().moves, ["4 e2e4"]);
+    assert_eq!(log.state().game.next, Color::Black);
+    played(&mut log, Side::Black, 4, "d7d5").unwrap();
+
+    log
+      .record(Event::Resigned { side: Side::White }, 0)
+      .unwrap();
+    assert_eq!(log.state().end(), EndResult::Color(Color::Black as i32));
+    assert_eq!(&GameState::replay(log.events()).unwrap(), log.state());
+  }
+}
@@ -0,0 +1,2143 @@
+pub mod admin;
+
+use base64::Engine;
+use dashmap::{mapref::one::RefMut, DashMap};
+use helpers::chesstactoe::{
+  tic_tac_toe, ArchivedGameInfo, ChatMessage, Chess, Color, DrawRequest, DrawResponse, GameInfo,
+  GameSettings, GetArchivedGameRequest, GetGameEventsRequest, GetGameEventsResponse,
+  GetPlayerRequest, JoinLobbyRequest, JoinRequest, JoinResponse, LeaveQueueRequest,
+  LeaveQueueResponse, ListArchivedGamesRequest, ListArchivedGamesResponse, ListGamesRequest,
+  ListGamesResponse, ListLobbiesRequest, ListLobbiesResponse, LobbyInfo, MakeLobbyRequest,
+  MakeLobbyResponse, MidGameRequest, MovePieceRequest, MovePieceResponse, MoveResult, PlayerInfo,
+  RematchRequest, RematchResponse, Request as GameRequest, ResignRequest, ResignResponse,
+  SendChatRequest, SendChatResponse, SubscribeBoardRequest, SubscribeBoardResponse,
+  TakeBackRequest, TakeBackResponse, TicTacToe, TimeControl,
+};
+use helpers::{
+  chesstactoe::{chess::EndResult, game_server::Game, join_response::GameStatus},
+  Error as MoveError,
+};
+use std::{
+  sync::Arc,
+  time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::mpsc::{self, Sender};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+use tracing::Span;
+use uuid::Uuid;
+
+use crate::{
+  accounts::AccountStore,
+  archive::{ArchivedChat, ArchivedGame, ArchivedResult, GameArchive},
+  auth::{self, Player},
+  broadcast::Broadcaster,
+  chat::{ChatConfig, RateLimit},
+  events::{Event, GameLog, GameState, Side},
+  logging,
+  matchmaking::{Matchmaker, MatchmakingConfig, Pairing, Pool, Ticket},
+  metrics::{Gauges, Metrics},
+};
+
+const MATCHMAKING_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Most games `ListArchivedGames` returns.
+const ARCHIVE_PAGE: usize = 50;
+
+#[derive(Debug, Clone)]
+pub struct ServiceConfig {
+  /// New games are refused while this many are being played.
+  pub max_games: Option<usize>,
+  /// Buffer size of the board subscription streams.
+  pub channel_size: usize,
+  /// Used for games requested without a time control.
+  pub default_time_control: TimeControl,
+  pub matchmaking: MatchmakingConfig,
+  pub chat: ChatConfig,
+}
+
+impl Default for ServiceConfig {
+  fn default() -> Self {
+    ServiceConfig {
+      max_games: None,
+      channel_size: 4,
+      default_time_control: TimeControl::default(),
+      matchmaking: MatchmakingConfig::default(),
+      chat: ChatConfig::default(),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ReaperConfig {
+  /// How often the reaper runs.
+  pub interval: Duration,
+  /// Lobbies nobody joined are closed after this long.
+  pub lobby_timeout: Duration,
+  /// A player without a board subscription for this long forfeits the game.
+  pub disconnect_grace: Duration,
+  /// Finished games stay in memory this long, so both players can see the final position.
+  pub finished_timeout: Duration,
+}
+
+impl Default for ReaperConfig {
+  fn default() -> Self {
+    ReaperConfig {
+      interval: Duration::from_secs(15),
+      lobby_timeout: Duration::from_secs(30 * 60),
+      disconnect_grace: Duration::from_secs(2 * 60),
+      finished_timeout: Duration::from_secs(5 * 60),
+    }
+  }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+struct Ongoing {
+  white: Uuid,
+  black: Uuid,
+  settings: GameSettings,
+  public: bool,
+  white_player: Player,
+  black_player: Player,
+  /// Everything that happened in the game, the board and chat included.
+  log: GameLog,
+  finished: Option<Instant>,
+  white_disconnected: Option<Instant>,
+  black_disconnected: Option<Instant>,
+  white_chat: RateLimit,
+  black_chat: RateLimit,
+}
+
+impl Ongoing {
+  fn state(&self) -> &GameState {
+    self.log.state()
+  }
+
+  /// Logs an event that happened now.
+  fn record(&mut self, event: Event) -> Result<(), MoveError> {
+    self.log.record(event, unix_time()).map(|_| ())
+  }
+
+  fn end(&self) -> EndResult {
+    self.state().end()
+  }
+
+  fn color(&self, seat: Uuid) -> Color {
+    if seat == self.white {
+      Color::White
+    } else {
+      Color::Black
+    }
+  }
+
+  fn archived(&self, id: Uuid, result: ArchivedResult) -> ArchivedGame {
+    let time_control = self.settings.time_control.clone().unwrap_or_default();
+
+    ArchivedGame {
+      id,
+      white: self.white_player.id,
+      black: self.black_player.id,
+      white_name: self.white_player.username.clone(),
+      black_name: self.black_player.username.clone(),
+      initial: time_control.initial,
+      increment: time_control.increment,
+      rated: self.settings.rated,
+      public: self.public,
+      result,
+      forfeit: self.state().forfeit.is_some(),
+      fen: self.state().game.to_fen().unwrap_or_default(),
+      moves: self.state().moves.clone(),
+      chat: self
+        .state()
+        .chat
+        .iter()
+        .map(|message| ArchivedChat {
+          username: message.username.clone(),
+          text: message.text.clone(),
+          sent_at: message.sent_at,
+        })
+        .collect(),
+      finished_at: unix_time(),
+      events: self.log.events().to_vec(),
+    }
+  }
+
+  /// Offers waiting for an answer, sent along with every board update.
+  fn requests(&self) -> MidGameRequest {
+    let asked = |offer: Option<Color>| {
+      offer.map(|color| GameRequest {
+        requestee: 1 - color as i32,
+      })
+    };
+
+    // Takebacks and draws are moot once the game is over.
+    let live = |offer: Option<Color>| asked(offer.filter(|_| self.finished.is_none()));
+
+    MidGameRequest {
+      takeback: live(self.state().takeback_offer),
+      draw: live(self.state().draw_offer),
+      rematch: asked(self.state().rematch_offer),
+    }
+  }
+
+  fn owner(&self, seat: Uuid) -> Option<&Player> {
+    if seat == self.white {
+      Some(&self.white_player)
+    } else if seat == self.black {
+      Some(&self.black_player)
+    } else {
+      None
+    }
+  }
+}
+
+/// Seconds since the Unix epoch.
+fn unix_time() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .unwrap_or_default()
+    .as_secs()
+}
+
+#[derive(Debug)]
+struct LobbyData {
+  host: Uuid,
+  sender: Sender<Result<MakeLobbyResponse, Status>>,
+  host_player: Player,
+  settings: GameSettings,
+  public: bool,
+  created: Instant,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct GameService {
+  matchmaker: Arc<Matchmaker>,
+  updates: Arc<Broadcaster>,
+  game_ids: Arc<DashMap<Uuid, Uuid>>,
+  games: Arc<DashMap<Uuid, Ongoing>>,
+  lobbies: Arc<DashMap<String, LobbyData>>,
+  accounts: Arc<AccountStore>,
+  archive: Arc<GameArchive>,
+  metrics: Arc<Metrics>,
+  config: ServiceConfig,
+}
+
+fn board_state(ongoing: &Ongoing) -> TicTacToe {
+  let state = ongoing.state();
+  let game = &state.game;
+
+  let end_result = match ongoing.end() {
+    EndResult::Color(color) => tic_tac_toe::EndResult::Color(color),
+    EndResult::Draw(draw) => tic_tac_toe::EndResult::Draw(draw),
+    EndResult::None(none) => tic_tac_toe::EndResult::None(none),
+  };
+
+  TicTacToe {
+    chesses: game
+      .chesses
+      .iter()
+      .flatten()
+      .map(|chess| Chess {
+        end_result: Some(chess.end.clone()),
+        fen: chess.to_fen(game.next).unwrap(),
+      })
+      .collect(),
+    next: game.next as i32,
+    last_move: state.moves.last().cloned().unwrap_or_default(),
+    moves: state.moves.clone(),
+    end_result: Some(end_result),
+  }
+}
+
+/// Current state of a game for its players, `color` is filled in per seat by `notify`.
+fn board_response(ongoing: &Ongoing, chat: Vec<ChatMessage>) -> SubscribeBoardResponse {
+  SubscribeBoardResponse {
+    color: Color::White as i32,
+    game: Some(board_state(ongoing)),
+    request: Some(ongoing.requests()),
+    chat,
+    seat: String::new(),
+    notice: String::new(),
+  }
+}
+
+/// Answer to a move the rules don't allow, or the status of any other failure.
+fn rejected(err: MoveError) -> Result<Response<MovePieceResponse>, Status> {
+  if !err.is_illegal_move() {
+    return Err(err.into());
+  }
+
+  Ok(Response::new(MovePieceResponse {
+    successful: MoveResult::ResultIllegal as i32,
+    rejection: err.rejection() as i32,
+    reason: err.to_string(),
+  }))
+}
+
+impl GameService {
+  /// Records `event` and tells both players, along with `notice` if it's not empty. An event that
+  /// ends the game also marks it finished at `now` and records the result, so every way a game can
+  /// end goes through here. Finished games take no more events.
+  fn apply(
+    &self,
+    mut game: RefMut<'_, Uuid, Ongoing>,
+    event: Event,
+    notice: String,
+    now: Instant,
+  ) -> Result<(), MoveError> {
+    if game.finished.is_some() {
+      return Err(MoveError::GameOver);
+    }
+
+    game.record(event)?;
+
+    let end = game.end();
+    let over = end != EndResult::None(true);
+
+    if over {
+      game.finished = Some(now);
+      self.metrics.game_finished();
+
+      tracing::info!(game = %game.key(), result = ?end, "Game finished");
+    }
+
+    let mut response = board_response(&game, vec![]);
+    response.notice = notice;
+    let seats = (game.white, game.black);
+    let id = *game.key();
+    let players = (game.white_player.id, game.black_player.id);
+    let rated = game.settings.rated;
+
+    // Recording the result saves the accounts to disk, which mustn't hold up the game.
+    drop(game);
+
+    self.updates.broadcast(seats, response);
+
+    if over {
+      let (white, black) = players;
+
+      // The event stands either way, so a failed rating update mustn't keep it from the players.
+      if let Err(err) = self.accounts.record_result(white, black, &end, rated) {
+        tracing::error!(game = %id, err = err.message(), "Couldn't record result");
+      }
+    }
+
+    Ok(())
+  }
+
+  /// The game behind a seat and the seat's color, as long as the game is still being played.
+  fn playing(
+    &self,
+    player: &Player,
+    seat: &str,
+  ) -> Result<(RefMut<'_, Uuid, Ongoing>, Color), Status> {
+    let seat = Uuid::parse_str(seat).map_err(|_| Status::invalid_argument("Invalid UUID"))?;
+
+    let game_uuid = self.seat_game(player, seat)?;
+
+    let game = self
+      .games
+      .get_mut(&game_uuid)
+      .ok_or_else(|| Status::not_found("Game no longer exists"))?;
+
+    if game.finished.is_some() {
+      return Err(Status::failed_precondition("The game is already over"));
+    }
+
+    let color = game.color(seat);
+
+    Ok((game, color))
+  }
+
+  pub fn new(
+    accounts: Arc<AccountStore>,
+    archive: Arc<GameArchive>,
+    config: ServiceConfig,
+  ) -> Self {
+    GameService {
+      matchmaker: Arc::new(Matchmaker::new(config.matchmaking)),
+      updates: Arc::new(Broadcaster::new(config.channel_size)),
+      accounts,
+      archive,
+      config,
+      ..Default::default()
+    }
+  }
+
+  pub fn metrics(&self) -> Arc<Metrics> {
+    self.metrics.clone()
+  }
+
+  pub fn gauges(&self) -> Gauges {
+    Gauges {
+      active_games: self.games.len(),
+      queued_players: self.matchmaker.len(),
+      open_lobbies: self.lobbies.len(),
+    }
+  }
+
+  /// Resolves the game a seat belongs to, making sure the authenticated player owns that seat.
+  fn seat_game(&self, player: &Player, seat: Uuid) -> Result<Uuid, Status> {
+    let game_id = *self
+      .game_ids
+      .get(&seat)
+      .ok_or_else(|| Status::permission_denied("User needs to join a game first"))?;
+
+    let game = self
+      .games
+      .get(&game_id)
+      .ok_or_else(|| Status::not_found("Game no longer exists"))?;
+
+    if game.owner(seat) != Some(player) {
+      return Err(Status::permission_denied(
+        "This seat belongs to another player",
+      ));
+    }
+
+    logging::record_game(&game_id);
+
+    Ok(game_id)
+  }
+
+  fn check_capacity(&self) -> Result<(), Status> {
+    match self.config.max_games {
+      Some(max) if self.games.len() >= max => Err(Status::resource_exhausted(
+        "The server is full, try again later",
+      )),
+      _ => Ok(()),
+    }
+  }
+
+  /// Fills in the server's default time control if the client didn't ask for one.
+  fn settings(&self, settings: Option<GameSettings>) -> GameSettings {
+    let mut settings = settings.unwrap_or_default();
+
+    settings
+      .time_control
+      .get_or_insert_with(|| self.config.default_time_control.clone());
+
+    settings
+  }
+
+  /// Starts a game between two seats and returns its id.
+  fn create_game(
+    &self,
+    (white, white_player): (Uuid, Player),
+    (black, black_player): (Uuid, Player),
+    settings: GameSettings,
+    public: bool,
+  ) -> Uuid {
+    let game_uuid = Uuid::new_v4();
+
+    let time_control = settings.time_control.clone().unwrap_or_default();
+
+    let mut game = Ongoing {
+      white,
+      black,
+      settings,
+      public,
+      white_player,
+      black_player,
+      log: GameLog::default(),
+      finished: None,
+      white_disconnected: None,
+      black_disconnected: None,
+      white_chat: RateLimit::default(),
+      black_chat: RateLimit::default(),
+    };
+
+    let created = Event::Created {
+      white: game.white_player.username.clone(),
+      black: game.black_player.username.clone(),
+      initial: time_control.initial,
+      increment: time_control.increment,
+      rated: game.settings.rated,
+    };
+    game.record(created).unwrap_or(());
+
+    tracing::info!(
+      game = %game_uuid,
+      white = %game.white_player.id,
+      black = %game.black_player.id,
+      "Game started"
+    );
+
+    self.games.insert(game_uuid, game);
+    self.metrics.game_started();
+
+    self.game_ids.insert(white, game_uuid);
+    self.game_ids.insert(black, game_uuid);
+
+    game_uuid
+  }
+
+  /// Creates the game for two matched queue tickets and tells both players it's ready. Dropping the
+  /// tickets afterwards ends their `Join` streams.
+  async fn start_queued_game(&self, pairing: Pairing) {
+    let Pairing { pool, white, black } = pairing;
+
+    if let Err(status) = self.check_capacity() {
+      for ticket in [white, black] {
+        ticket.sender.send(Err(status.clone())).await.unwrap_or(());
+      }
+      return;
+    }
+
+    self.create_game(
+      (white.seat, white.player.clone()),
+      (black.seat, black.player.clone()),
+      pool.settings(),
+      true,
+    );
+
+    for ticket in [white, black] {
+      ticket
+        .sender
+        .send(Ok(JoinResponse {
+          status: GameStatus::Ready as i32,
+          uuid: ticket.seat.to_string(),
+        }))
+        .await
+        .unwrap_or(());
+    }
+  }
+
+  /// Periodically pairs queued players whose rating windows widened enough to meet.
+  pub fn spawn_matchmaking(&self) {
+    let service = self.clone();
+
+    tokio::spawn(async move {
+      let mut interval = tokio::time::interval(MATCHMAKING_INTERVAL);
+
+      loop {
+        interval.tick().await;
+
+        for pairing in service.matchmaker.sweep(Instant::now()) {
+          service.start_queued_game(pairing).await;
+        }
+      }
+    });
+  }
+
+  fn remove_game(&self, id: Uuid) -> Option<Ongoing> {
+    let (_, game) = self.games.remove(&id)?;
+
+    for seat in [game.white, game.black] {
+      self.game_ids.remove(&seat);
+      self.updates.remove(seat);
+    }
+
+    Some(game)
+  }
+
+  fn archive_game(&self, id: Uuid, game: &Ongoing, result: ArchivedResult) {
+    match self.archive.store(game.archived(id, result)) {
+      Ok(()) => tracing::debug!(game = %id, ?result, "Archived game"),
+      Err(err) => tracing::error!(game = %id, %err, "Couldn't archive game"),
+    }
+  }
+
+  /// Ends a game in favour of `winner` because the other player left, telling whoever's still
+  /// watching.
+  async fn forfeit(&self, id: Uuid, winner: Color, now: Instant) {
+    let Some(game) = self.games.get_mut(&id) else {
+      return;
+    };
+
+    let loser = match winner {
+      Color::White => Side::Black,
+      Color::Black => Side::White,
+    };
+
+    match self.apply(game, Event::TimedOut { side: loser }, String::new(), now) {
+      Ok(()) => tracing::info!(game = %id, ?winner, "Game forfeited"),
+      // The game may have ended on the boards since the reaper looked at it.
+      Err(MoveError::GameOver) => {}
+      Err(err) => tracing::error!(game = %id, %err, "Couldn't forfeit game"),
+    }
+  }
+
+  /// Expires unjoined lobbies, forfeits games whose players disconnected for longer than the grace
+  /// period and moves finished games to the archive.
+  pub async fn reap(&self, config: &ReaperConfig, now: Instant) {
+    let expired: Vec<String> = self
+      .lobbies
+      .iter()
+      .filter(|lobby| {
+        lobby.sender.is_closed()
+          || now.saturating_duration_since(lobby.created) >= config.lobby_timeout
+      })
+      .map(|lobby| lobby.key().clone())
+      .collect();
+
+    for code in expired {
+      if let Some((_, lobby)) = self.lobbies.remove(&code) {
+        lobby
+          .sender
+          .send(Err(Status::deadline_exceeded("Lobby expired")))
+          .await
+          .unwrap_or(());
+      }
+    }
+
+    let gone = |since: Option<Instant>| {
+      since.is_some_and(|since| now.saturating_duration_since(since) >= config.disconnect_grace)
+    };
+
+    let mut forfeited = vec![];
+    let mut abandoned = vec![];
+    let mut finished = vec![];
+
+    for mut game in self.games.iter_mut() {
+      if let Some(at) = game.finished {
+        if now.saturating_duration_since(at) >= config.finished_timeout {
+          finished.push(*game.key());
+        }
+        continue;
+      }
+
+      game.white_disconnected = match self.updates.connected(game.white) {
+        true => None,
+        false => game.white_disconnected.or(Some(now)),
+      };
+      game.black_disconnected = match self.updates.connected(game.black) {
+        true => None,
+        false => game.black_disconnected.or(Some(now)),
+      };
+
+      match (gone(game.white_disconnected), gone(game.black_disconnected)) {
+        (true, true) => abandoned.push(*game.key()),
+        (true, false) => forfeited.push((*game.key(), Color::Black)),
+        (false, true) => forfeited.push((*game.key(), Color::White)),
+        (false, false) => {}
+      }
+    }
+
+    for (id, winner) in forfeited {
+      self.forfeit(id, winner, now).await;
+    }
+
+    for id in abandoned {
+      if let Some(mut game) = self.remove_game(id) {
+        for side in [Side::White, Side::Black] {
+          game.record(Event::TimedOut { side }).unwrap_or(());
+        }
+
+        self.archive_game(id, &game, ArchivedResult::Abandoned);
+      }
+    }
+
+    for id in finished {
+      if let Some(game) = self.remove_game(id) {
+        let result = match game.end() {
+          EndResult::Color(color) if color == Color::White as i32 => ArchivedResult::White,
+          EndResult::Color(_) => ArchivedResult::Black,
+          _ => ArchivedResult::Draw,
+        };
+
+        self.archive_game(id, &game, result);
+      }
+    }
+  }
+
+  pub fn spawn_reaper(&self, config: ReaperConfig) {
+    let service = self.clone();
+
+    tokio::spawn(async move {
+      let mut interval = tokio::time::interval(config.interval);
+
+      loop {
+        interval.tick().await;
+
+        service.reap(&config, Instant::now()).await;
+      }
+    });
+  }
+}
+
+#[tonic::async_trait]
+impl Game for GameService {
+  async fn move_piece(
+    &self,
+    request: Request<MovePieceRequest>,
+  ) -> Result<Response<MovePieceResponse>, Status> {
+    let player = auth::player(&request)?;
+
+    let request = request.into_inner();
+
+    Span::current().record("board", request.board);
+
+    let uuid =
+      Uuid::parse_str(&request.uuid).map_err(|_| Status::invalid_argument("Invalid UUID"))?;
+
+    let game_uuid = self.seat_game(&player, uuid)?;
+
+    let game = self
+      .games
+      .get_mut(&game_uuid)
+      .ok_or_else(|| Status::not_found("Game no longer exists"))?;
+
+    let white = game.white;
+    let black = game.black;
+
+    let color = if uuid == white {
+      Color::White
+    } else if uuid == black {
+      Color::Black
+    } else {
+      return Err(Status::internal("Something went wrong"));
+    };
+
+    let played = Event::Move {
+      side: color.into(),
+      board: request.board,
+      alg: request.alg.clone(),
+    };
+
+    // Games can also be finished by forfeit or by an admin, which the boards don't know about.
+    if let Err(err) = self.apply(game, played, String::new(), Instant::now()) {
+      tracing::info!(alg = request.alg, reason = %err, "Move rejected");
+      return rejected(err);
+    }
+
+    tracing::info!(alg = request.alg, ?color, "Move played");
+    self.metrics.move_played();
+
+    Ok(Response::new(MovePieceResponse {
+      successful: MoveResult::ResultSuccessful as i32,
+      ..Default::default()
+    }))
+  }
+
+  type JoinStream = ReceiverStream<Result<JoinResponse, Status>>;
+
+  async fn join(
+    &self,
+    request: Request<JoinRequest>,
+  ) -> Result<Response<Self::JoinStream>, Status> {
+    let player = auth::player(&request)?;
+
+    self.check_capacity()?;
+
+    let settings = self.settings(request.into_inner().settings);
+
+    let rating = self
+      .accounts
+      .get(&player.id)
+      .map(|account| account.stats.rating)
+      .unwrap_or_default();
+
+    let uuid = Uuid::new_v4();
+
+    let (tx, rx) = mpsc::channel(4);
+
+    tx.send(Ok(JoinResponse {
+      status: GameStatus::NotReady as i32,
+      uuid: uuid.to_string(),
+    }))
+    .await
+    .unwrap_or(());
+
+    let ticket = Ticket::new(uuid, player, rating, tx);
+
+    if let Some(pairing) = self.matchmaker.join(Pool::from(&settings), ticket) {
+      self.start_queued_game(pairing).await;
+    }
+
+    Ok(Response::new(ReceiverStream::new(rx)))
+  }
+
+  type SubscribeBoardStream = ReceiverStream<Result<SubscribeBoardResponse, Status>>;
+
+  async fn subscribe_board(
+    &self,
+    request: Request<SubscribeBoardRequest>,
+  ) -> Result<Response<Self::SubscribeBoardStream>, Status> {
+    let player = auth::player(&request)?;
+
+    let request = request.into_inner();
+
+    let asker =
+      Uuid::parse_str(&request.uuid).map_err(|_| Status::invalid_argument("Invalid UUID"))?;
+
+    let game_uuid = self.seat_game(&player, asker)?;
+
+    let mut game = self
+      .games
+      .get_mut(&game_uuid)
+      .ok_or_else(|| Status::not_found("Game no longer exists"))?;
+
+    let color = game.color(asker);
+
+    if !game.state().joined.contains(&color) {
+      game
+        .record(Event::Joined { side: color.into() })
+        .unwrap_or(());
+    }
+
+    let mut current = board_response(&game, game.state().chat.clone());
+    current.color = color as i32;
+
+    drop(game);
+
+    let rx = self.updates.subscribe(asker, current);
+
+    Ok(Response::new(ReceiverStream::new(rx)))
+  }
+
+  async fn take_back(
+    &self,
+    request: Request<TakeBackRequest>,
+  ) -> Result<Response<TakeBackResponse>, Status> {
+    let player = auth::player(&request)?;
+
+    let request = request.into_inner();
+
+    let (mut game, color) = self.playing(&player, &request.uuid)?;
+    let side = color.into();
+
+    let event = match (request.is_response, game.state().takeback_offer) {
+      (false, Some(offer)) if offer == color => None,
+      // Asking back while the opponent is asking is agreeing with them.
+      (false, Some(_)) => Some(Event::TakebackAccepted { side }),
+      (false, None) if game.state().moves.is_empty() => {
+        return Err(Status::failed_precondition("There's no move to take back"));
+      }
+      (false, None) => Some(Event::TakebackRequested { side }),
+      (true, None) => {
+        return Err(Status::failed_precondition("There's no takeback to answer"));
+      }
+      (true, Some(offer)) if request.accepted && offer != color => {
+        Some(Event::TakebackAccepted { side })
+      }
+      (true, Some(_)) => Some(Event::TakebackDeclined { side }),
+    };
+
+    if let Some(event) = event {
+      game.record(event)?;
+    }
+
+    let response = board_response(&game, vec![]);
+    let seats = (game.white, game.black);
+
+    drop(game);
+
+    self.updates.broadcast(seats, response);
+
+    Ok(Response::new(TakeBackResponse {}))
+  }
+
+  async fn offer_draw(
+    &self,
+    request: Request<DrawRequest>,
+  ) -> Result<Response<DrawResponse>, Status> {
+    let player = auth::player(&request)?;
+
+    let request = request.into_inner();
+
+    let (game, color) = self.playing(&player, &request.uuid)?;
+    let side = color.into();
+
+    let event = match (request.decline, game.state().draw_offer) {
+      (true, Some(_)) => Some(Event::DrawDeclined { side }),
+      (true, None) => None,
+      (false, Some(offer)) if offer != color => Some(Event::DrawAccepted { side }),
+      (false, Some(_)) => None,
+      (false, None) => Some(Event::DrawOffered { side }),
+    };
+
+    if let Some(event) = event {
+      self.apply(game, event, String::new(), Instant::now())?;
+    }
+
+    Ok(Response::new(DrawResponse {}))
+  }
+
+  async fn resign(
+    &self,
+    request: Request<ResignRequest>,
+  ) -> Result<Response<ResignResponse>, Status> {
+    let player = auth::player(&request)?;
+
+    let request = request.into_inner();
+
+    let (game, color) = self.playing(&player, &request.uuid)?;
+
+    let resigned = Event::Resigned { side: color.into() };
+    self.apply(game, resigned, String::new(), Instant::now())?;
+
+    Ok(Response::new(ResignResponse {}))
+  }
+
+  type MakeLobbyStream = ReceiverStream<Result<MakeLobbyResponse, Status>>;
+
+  async fn make_lobby(
+    &self,
+    request: Request<MakeLobbyRequest>,
+  ) -> Result<Response<Self::MakeLobbyStream>, Status> {
+    let player = auth::player(&request)?;
+
+    let request = request.into_inner();
+
+    let (tx, rx) = mpsc::channel(2);
+
+    let room_id = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(Uuid::new_v4());
+
+    let user_id = Uuid::new_v4();
+
+    tx.send(Ok(MakeLobbyResponse {
+      join_response: Some(JoinResponse {
+        status: GameStatus::NotReady as i32,
+        uuid: user_id.to_string(),
+      }),
+      room_id: room_id.to_string(),
+    }))
+    .await
+    .unwrap_or(());
+
+    self.lobbies.insert(
+      room_id,
+      LobbyData {
+        host: user_id,
+        sender: tx,
+        host_player: player,
+        settings: self.settings(request.settings),
+        public: request.public,
+        created: Instant::now(),
+      },
+    );
+
+    Ok(Response::new(ReceiverStream::new(rx)))
+  }
+
+  type JoinLobbyStream = Self::JoinStream;
+
+  async fn join_lobby(
+    &self,
+    request: Request<JoinLobbyRequest>,
+  ) -> Result<Response<Self::JoinLobbyStream>, Status> {
+    let player = auth::player(&request)?;
+
+    let req = request.into_inner();
+
+    let lobby_code = req.code;
+
+    self.check_capacity()?;
+
+    if let Some((_lobby, white)) = self.lobbies.remove(&lobby_code) {
+      let join_uuid = Uuid::new_v4();
+
+      if white.sender.is_closed() {
+        return Err(Status::not_found("Lobby does not exist"));
+      }
+
+      self.create_game(
+        (white.host, white.host_player),
+        (join_uuid, player),
+        white.settings,
+        white.public,
+      );
+
+      white
+        .sender
+        .send(Ok(MakeLobbyResponse {
+          room_id: "".to_owned(),
+          join_response: Some(JoinResponse {
+            status: GameStatus::Ready as i32,
+            uuid: white.host.to_string(),
+          }),
+        }))
+        .await
+        .unwrap_or(());
+
+      let (tx, rx) = mpsc::channel(1);
+
+      tx.send(Ok(JoinResponse {
+        status: GameStatus::Ready as i32,
+        uuid: join_uuid.to_string(),
+      }))
+      .await
+      .unwrap_or(());
+
+      return Ok(Response::new(ReceiverStream::new(rx)));
+    }
+
+    Err(Status::not_found("Lobby doesn't exist"))
+  }
+
+  async fn list_lobbies(
+    &self,
+    _request: Request<ListLobbiesRequest>,
+  ) -> Result<Response<ListLobbiesResponse>, Status> {
+    let lobbies = self
+      .lobbies
+      .iter()
+      .filter(|lobby| lobby.public && !lobby.sender.is_closed())
+      .map(|lobby| LobbyInfo {
+        code: lobby.key().clone(),
+        host_name: lobby.host_player.username.clone(),
+        settings: Some(lobby.settings.clone()),
+      })
+      .collect();
+
+    Ok(Response::new(ListLobbiesResponse { lobbies }))
+  }
+
+  async fn list_games(
+    &self,
+    _request: Request<ListGamesRequest>,
+  ) -> Result<Response<ListGamesResponse>, Status> {
+    let games = self
+      .games
+      .iter()
+      .filter(|game| game.public)
+      .filter(|game| game.finished.is_none())
+      .map(|game| GameInfo {
+        id: game.key().to_string(),
+        white: game.white_player.username.clone(),
+        black: game.black_player.username.clone(),
+        settings: Some(game.settings.clone()),
+        next: game.state().game.next as i32,
+      })
+      .collect();
+
+    Ok(Response::new(ListGamesResponse { games }))
+  }
+
+  async fn get_player(
+    &self,
+    request: Request<GetPlayerRequest>,
+  ) -> Result<Response<PlayerInfo>, Status> {
+    let player = auth::player(&request)?;
+
+    let request = request.into_inner();
+
+    let account = if request.username.is_empty() {
+      self.accounts.get(&player.id)
+    } else {
+      self.accounts.find(&request.username)
+    };
+
+    account
+      .map(|account| Response::new(account.info()))
+      .ok_or_else(|| Status::not_found("Player doesn't exist"))
+  }
+
+  async fn leave_queue(
+    &self,
+    request: Request<LeaveQueueRequest>,
+  ) -> Result<Response<LeaveQueueResponse>, Status> {
+    let player = auth::player(&request)?;
+
+    let seat = Uuid::parse_str(&request.into_inner().uuid)
+      .map_err(|_| Status::invalid_argument("Invalid UUID"))?;
+
+    if !self.matchmaker.leave(seat, &player) {
+      return Err(Status::not_found("Not waiting in the queue"));
+    }
+
+    Ok(Response::new(LeaveQueueResponse {}))
+  }
+
+  async fn send_chat(
+    &self,
+    request: Request<SendChatRequest>,
+  ) -> Result<Response<SendChatResponse>, Status> {
+    let player = auth::player(&request)?;
+
+    let request = request.into_inner();
+
+    let seat =
+      Uuid::parse_str(&request.uuid).map_err(|_| Status::invalid_argument("Invalid UUID"))?;
+
+    let game_uuid = self.seat_game(&player, seat)?;
+
+    let text = self.config.chat.validate(&request.text)?;
+
+    let mut game = self
+      .games
+      .get_mut(&game_uuid)
+      .ok_or_else(|| Status::not_found("Game no longer exists"))?;
+
+    let (color, limit) = if seat == game.white {
+      (Color::White, &mut game.white_chat)
+    } else {
+      (Color::Black, &mut game.black_chat)
+    };
+
+    if !limit.allow(&self.config.chat, Instant::now()) {
+      return Err(Status::resource_exhausted(
+        "You're sending messages too quickly",
+      ));
+    }
+
+    let chat = Event::Chat {
+      side: color.into(),
+      username: player.username,
+      text,
+    };
+    game.record(chat).unwrap_or(());
+
+    let message = game.state().chat.last().cloned().into_iter().collect();
+
+    let response = board_response(&game, message);
+    let seats = (game.white, game.black);
+
+    drop(game);
+
+    self.updates.broadcast(seats, response);
+
+    Ok(Response::new(SendChatResponse {}))
+  }
+
+  async fn rematch(
+    &self,
+    request: Request<RematchRequest>,
+  ) -> Result<Response<RematchResponse>, Status> {
+    let player = auth::player(&request)?;
+
+    let request = request.into_inner();
+
+    let seat =
+      Uuid::parse_str(&request.uuid).map_err(|_| Status::invalid_argument("Invalid UUID"))?;
+
+    let game_uuid = self.seat_game(&player, seat)?;
+
+    let mut game = self
+      .games
+      .get_mut(&game_uuid)
+      .ok_or_else(|| Status::not_found("Game no longer exists"))?;
+
+    if game.finished.is_none() {
+      return Err(Status::failed_precondition("The game isn't over yet"));
+    }
+
+    if game.state().rematched {
+      return Err(Status::failed_precondition(
+        "The rematch has already started",
+      ));
+    }
+
+    let color = game.color(seat);
+    let side = color.into();
+
+    let accepted = match (request.decline, game.state().rematch_offer) {
+      (true, Some(_)) => {
+        game.record(Event::RematchDeclined { side }).unwrap_or(());
+        false
+      }
+      (true, None) => false,
+      (false, Some(offer)) if offer != color => {
+        self.check_capacity()?;
+        true
+      }
+      (false, _) => {
+        game.record(Event::RematchOffered { side }).unwrap_or(());
+        false
+      }
+    };
+
+    if !accepted {
+      let response = board_response(&game, vec![]);
+      let seats = (game.white, game.black);
+
+      drop(game);
+
+      self.updates.broadcast(seats, response);
+
+      return Ok(Response::new(RematchResponse {}));
+    }
+
+    game.record(Event::RematchAccepted { side }).unwrap_or(());
+
+    let old_seats = (game.white, game.black);
+    let (white_player, black_player) = (game.white_player.clone(), game.black_player.clone());
+    let (settings, public) = (game.settings.clone(), game.public);
+
+    drop(game);
+
+    // Colours are swapped, so the old black seat's stream follows the new white seat.
+    let (white, black) = (Uuid::new_v4(), Uuid::new_v4());
+
+    let id = self.create_game(
+      (white, black_player),
+      (black, white_player),
+      settings,
+      public,
+    );
+
+    for (old, new) in [(old_seats.1, white), (old_seats.0, black)] {
+      self.updates.move_seat(old, new);
+    }
+
+    let Some(response) = self
+      .games
+      .get(&id)
+      .map(|game| board_response(&game, vec![]))
+    else {
+      return Err(Status::not_found("Game no longer exists"));
+    };
+
+    for (seat, color) in [(white, Color::White), (black, Color::Black)] {
+      let response = SubscribeBoardResponse {
+        seat: seat.to_string(),
+        ..response.clone()
+      };
+
+      self.updates.notify(seat, color, response);
+    }
+
+    Ok(Response::new(RematchResponse {}))
+  }
+
+  async fn list_archived_games(
+    &self,
+    request: Request<ListArchivedGamesRequest>,
+  ) -> Result<Response<ListArchivedGamesResponse>, Status> {
+    let player = auth::player(&request)?;
+
+    let username = request.into_inner().username;
+
+    let id = if username.is_empty() {
+      player.id
+    } else {
+      self
+        .accounts
+        .find(&username)
+        .ok_or_else(|| Status::not_found("Player doesn't exist"))?
+        .id
+    };
+
+    let games = self
+      .archive
+      .player_games(id, ARCHIVE_PAGE)
+      .iter()
+      .filter(|game| game.visible_to(player.id))
+      .map(ArchivedGame::info)
+      .collect();
+
+    Ok(Response::new(ListArchivedGamesResponse { games }))
+  }
+
+  async fn get_archived_game(
+    &self,
+    request: Request<GetArchivedGameRequest>,
+  ) -> Result<Response<ArchivedGameInfo>, Status> {
+    let player = auth::player(&request)?;
+
+    let id = Uuid::parse_str(&request.into_inner().id)
+      .map_err(|_| Status::invalid_argument("Invalid UUID"))?;
+
+    let game = self
+      .archive
+      .get(id)
+      .ok_or_else(|| Status::not_found("No archived game with that ID"))?;
+
+    if !game.visible_to(player.id) {
+      return Err(Status::permission_denied("That game is private"));
+    }
+
+    Ok(Response::new(game.info()))
+  }
+
+  async fn get_game_events(
+    &self,
+    request: Request<GetGameEventsRequest>,
+  ) -> Result<Response<GetGameEventsResponse>, Status> {
+    let player = auth::player(&request)?;
+
+    let request = request.into_inner();
+
+    let id = Uuid::parse_str(&request.id).map_err(|_| Status::invalid_argument("Invalid UUID"))?;
+
+    // Players can look their game up by their seat, which is all a player is told.
+    let id = self.seat_game(&player, id).unwrap_or(id);
+
+    logging::record_game(&id);
+
+    let since = request.since as usize;
+
+    // Games still being played come from memory, where spectators can catch up on them.
+    let events = match self.games.get(&id) {
+      Some(game) => {
+        if !game.public && game.white_player != player && game.black_player != player {
+          return Err(Status::permission_denied("That game is private"));
+        }
+
+        game
+          .log
+          .events()
+          .iter()
+          .skip(since)
+          .map(|logged| logged.info())
+          .collect()
+      }
+      None => {
+        let game = self
+          .archive
+          .get(id)
+          .ok_or_else(|| Status::not_found("No game with that ID"))?;
+
+        if !game.visible_to(player.id) {
+          return Err(Status::permission_denied("That game is private"));
+        }
+
+        game
+          .events
+          .iter()
+          .skip(since)
+          .map(|logged| logged.info())
+          .collect()
+      }
+    };
+
+    Ok(Response::new(GetGameEventsResponse { events }))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use helpers::chesstactoe::{game_event, GameOutcome, MoveRejection};
+  use tokio_stream::StreamExt;
+
+  use super::*;
+  use crate::testing;
+
+  fn service() -> (GameService, Player, Player) {
+    let app = testing::app();
+    let (alice, bob) = testing::players(&app);
+
+    (app.game, alice, bob)
+  }
+
+  fn subscribe(
+    service: &GameService,
+    seat: Uuid,
+  ) -> mpsc::Receiver<Result<SubscribeBoardResponse, Status>> {
+    let mut rx = service
+      .updates
+      .subscribe(seat, SubscribeBoardResponse::default());
+    rx.try_recv().unwrap().unwrap();
+    rx
+  }
+
+  fn request<T>(player: &Player, message: T) -> Request<T> {
+    let mut request = Request::new(message);
+    request.extensions_mut().insert(player.clone());
+    request
+  }
+
+  #[tokio::test]
+  async fn chat_reaches_both_players_and_new_subscribers() {
+    let (service, alice, bob) = service();
+
+    let (white, black) = (Uuid::new_v4(), Uuid::new_v4());
+    service.create_game(
+      (white, alice.clone()),
+      (black, bob.clone()),
+      GameSettings::default(),
+      true,
+    );
+
+    let mut white_rx = subscribe(&service, white);
+    let mut black_rx = subscribe(&service, black);
+
+    let chat = SendChatRequest {
+      uuid: black.to_string(),
+      text: " good luck ".to_owned(),
+    };
+    service.send_chat(request(&bob, chat)).await.unwrap();
+
+    for rx in [&mut white_rx, &mut black_rx] {
+      let response = rx.recv().await.unwrap().unwrap();
+      assert!(response.game.is_some());
+      assert_eq!(response.chat.len(), 1);
+      assert_eq!(response.chat[0].text, "good luck");
+      assert_eq!(response.chat[0].username, "bob");
+      assert_eq!(response.chat[0].color, Color::Black as i32);
+    }
+
+    let chat = SendChatRequest {
+      uuid: black.to_string(),
+      text: "hi".to_owned(),
+    };
+    let status = service.send_chat(request(&alice, chat)).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::PermissionDenied);
+
+    let resubscribe = SubscribeBoardRequest {
+      uuid: white.to_string(),
+    };
+    let mut stream = service
+      .subscribe_board(request(&alice, resubscribe))
+      .await
+      .unwrap()
+      .into_inner();
+    let history = stream.next().await.unwrap().unwrap().chat;
+    assert_eq!(history.len(), 1);
+  }
+
+  #[tokio::test]
+  async fn rate_limits_chat() {
+    let (service, alice, bob) = service();
+
+    let (white, black) = (Uuid::new_v4(), Uuid::new_v4());
+    service.create_game(
+      (white, alice.clone()),
+      (black, bob),
+      GameSettings::default(),
+      true,
+    );
+
+    let chat = || {
+      request(
+        &alice,
+        SendChatRequest {
+          uuid: white.to_string(),
+          text: "hello".to_owned(),
+        },
+      )
+    };
+
+    for _ in 0..service.config.chat.burst {
+      service.send_chat(chat()).await.unwrap();
+    }
+
+    let status = service.send_chat(chat()).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+  }
+
+  #[tokio::test]
+  async fn sends_the_move_list() {
+    let (service, alice, bob) = service();
+
+    let (white, black) = (Uuid::new_v4(), Uuid::new_v4());
+    service.create_game(
+      (white, alice.clone()),
+      (black, bob.clone()),
+      GameSettings::default(),
+      true,
+    );
+
+    let mut white_rx = subscribe(&service, white);
+    let _black_rx = subscribe(&service, black);
+
+    for (player, seat, board, alg) in [(&alice, white, 4, "e2e4"), (&bob, black, 0, "e7e5")] {
+      let request = request(
+        player,
+        MovePieceRequest {
+          board,
+          alg: alg.to_owned(),
+          uuid: seat.to_string(),
+        },
+      );
+      service.move_piece(request).await.unwrap();
+    }
+
+    // Updates the stream hadn't sent yet may arrive folded into the latest one.
+    let state = loop {
+      let state = white_rx.recv().await.unwrap().unwrap().game.unwrap();
+
+      if state.last_move == "0 e7e5" {
+        break state;
+      }
+    };
+
+    assert_eq!(state.moves, ["4 e2e4", "0 e7e5"]);
+    assert_eq!(state.last_move, "0 e7e5");
+  }
+
+  #[tokio::test]
+  async fn counts_games_and_moves_for_the_metrics() {
+    let (service, alice, bob) = service();
+
+    let (white, black) = (Uuid::new_v4(), Uuid::new_v4());
+    service.create_game(
+      (white, alice.clone()),
+      (black, bob),
+      GameSettings::default(),
+      true,
+    );
+
+    let _white_rx = subscribe(&service, white);
+
+    let request = request(
+      &alice,
+      MovePieceRequest {
+        board: 4,
+        alg: "e2e4".to_owned(),
+        uuid: white.to_string(),
+      },
+    );
+    service.move_piece(request).await.unwrap();
+
+    assert_eq!(
+      service.gauges(),
+      Gauges {
+        active_games: 1,
+        queued_players: 0,
+        open_lobbies: 0,
+      }
+    );
+
+    let text = service.metrics.render(service.gauges());
+    assert!(text.contains("chesstactoe_games_started_total 1\n"));
+    assert!(text.contains("chesstactoe_moves_total 1\n"));
+    assert!(text.contains("chesstactoe_active_games 1\n"));
+  }
+
+  #[tokio::test]
+  async fn explains_rejected_moves() {
+    let (service, alice, bob) = service();
+
+    let (white, black) = (Uuid::new_v4(), Uuid::new_v4());
+    service.create_game(
+      (white, alice.clone()),
+      (black, bob.clone()),
+      GameSettings::default(),
+      true,
+    );
+
+    let _white_rx = subscribe(&service, white);
+    let _black_rx = subscribe(&service, black);
+
+    let play = |player: &Player, seat: Uuid, board: u32, alg: &str| {
+      service.move_piece(request(
+        player,
+        MovePieceRequest {
+          board,
+          alg: alg.to_owned(),
+          uuid: seat.to_string(),
+        },
+      ))
+    };
+
+    let res = play(&bob, black, 4, "e7e5").await.unwrap().into_inner();
+    assert_eq!(res.successful(), MoveResult::ResultIllegal);
+    assert_eq!(res.rejection(), MoveRejection::RejectionNotYourTurn);
+
+    let res = play(&alice, white, 4, "e2e5").await.unwrap().into_inner();
+    assert_eq!(res.successful(), MoveResult::ResultIllegal);
+    assert_eq!(res.rejection(), MoveRejection::RejectionIllegalMove);
+    assert_eq!(res.reason, "That piece can't move there");
+
+    let status = play(&alice, white, 4, "e2-e4").await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+
+    let status = play(&alice, white, 9, "e2e4").await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+
+    let res = play(&alice, white, 4, "e2e4").await.unwrap().into_inner();
+    assert_eq!(res.successful(), MoveResult::ResultSuccessful);
+    assert!(res.reason.is_empty());
+  }
+
+  #[tokio::test]
+  async fn moves_survive_missing_and_dropped_streams() {
+    let (service, alice, bob) = service();
+
+    let (white, black) = (Uuid::new_v4(), Uuid::new_v4());
+    service.create_game(
+      (white, alice.clone()),
+      (black, bob.clone()),
+      GameSettings::default(),
+      true,
+    );
+
+    // Black never subscribes and white's stream goes away before the first move.
+    drop(subscribe(&service, white));
+
+    for (player, seat, board, alg) in [(&alice, white, 4, "e2e4"), (&bob, black, 0, "e7e5")] {
+      let request = request(
+        player,
+        MovePieceRequest {
+          board,
+          alg: alg.to_owned(),
+          uuid: seat.to_string(),
+        },
+      );
+      let res = service.move_piece(request).await.unwrap().into_inner();
+      assert_eq!(res.successful(), MoveResult::ResultSuccessful);
+    }
+
+    assert!(!service.updates.connected(white));
+
+    for (player, seat) in [(&alice, white), (&bob, black)] {
+      let resubscribe = SubscribeBoardRequest {
+        uuid: seat.to_string(),
+      };
+      let mut stream = service
+        .subscribe_board(request(player, resubscribe))
+        .await
+        .unwrap()
+        .into_inner();
+
+      let state = stream.next().await.unwrap().unwrap().game.unwrap();
+      assert_eq!(state.moves, ["4 e2e4", "0 e7e5"]);
+    }
+  }
+
+  #[tokio::test]
+  async fn rematch_swaps_colours_on_the_same_streams() {
+    let (service, alice, bob) = service();
+
+    let (white, black) = (Uuid::new_v4(), Uuid::new_v4());
+    let id = service.create_game(
+      (white, alice.clone()),
+      (black, bob.clone()),
+      GameSettings {
+        rated: true,
+        ..Default::default()
+      },
+      false,
+    );
+
+    let mut white_rx = subscribe(&service, white);
+    let mut black_rx = subscribe(&service, black);
+
+    let rematch = |player: &Player, seat: Uuid| {
+      request(
+        player,
+        RematchRequest {
+          uuid: seat.to_string(),
+          decline: false,
+        },
+      )
+    };
+
+    let status = service.rematch(rematch(&alice, white)).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+
+    service.games.get_mut(&id).unwrap().finished = Some(Instant::now());
+
+    service.rematch(rematch(&alice, white)).await.unwrap();
+
+    let offer = black_rx.recv().await.unwrap().unwrap();
+    let asked = offer.request.unwrap().rematch.unwrap();
+    assert_eq!(asked.requestee, Color::Black as i32);
+    white_rx.recv().await.unwrap().unwrap();
+
+    service.rematch(rematch(&bob, black)).await.unwrap();
+
+    let bob_update = black_rx.recv().await.unwrap().unwrap();
+    assert_eq!(bob_update.color, Color::White as i32);
+    let alice_update = white_rx.recv().await.unwrap().unwrap();
+    assert_eq!(alice_update.color, Color::Black as i32);
+
+    let new_white = Uuid::parse_str(&bob_update.seat).unwrap();
+    let new_black = Uuid::parse_str(&alice_update.seat).unwrap();
+
+    let new_id = service.seat_game(&bob, new_white).unwrap();
+    assert_ne!(new_id, id);
+    assert_eq!(service.seat_game(&alice, new_black).unwrap(), new_id);
+
+    let rematch_game = service.games.get(&new_id).unwrap();
+    assert!(rematch_game.settings.rated);
+    assert!(!rematch_game.public);
+    drop(rematch_game);
+
+    let status = service.rematch(rematch(&alice, white)).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+  }
+
+  #[tokio::test]
+  async fn takebacks_and_draws_go_through_the_opponent() {
+    let (service, alice, bob) = service();
+
+    let (white, black) = (Uuid::new_v4(), Uuid::new_v4());
+    let id = service.create_game(
+      (white, alice.clone()),
+      (black, bob.clone()),
+      GameSettings::default(),
+      true,
+    );
+
+    let _white_rx = subscribe(&service, white);
+    let mut black_rx = subscribe(&service, black);
+
+    let takeback = |player: &Player, seat: Uuid, is_response: bool, accepted: bool| {
+      request(
+        player,
+        TakeBackRequest {
+          uuid: seat.to_string(),
+          is_response,
+          accepted,
+        },
+      )
+    };
+
+    let status = service
+      .take_back(takeback(&alice, white, false, false))
+      .await
+      .unwrap_err();
+    assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+
+    let played = MovePieceRequest {
+      board: 4,
+      alg: "e2e4".to_owned(),
+      uuid: white.to_string(),
+    };
+    service.move_piece(request(&alice, played)).await.unwrap();
+    black_rx.recv().await.unwrap().unwrap();
+
+    service
+      .take_back(takeback(&alice, white, false, false))
+      .await
+      .unwrap();
+    let asked = black_rx.recv().await.unwrap().unwrap().request.unwrap();
+    assert_eq!(asked.takeback.unwrap().requestee, Color::Black as i32);
+
+    service
+      .take_back(takeback(&bob, black, true, true))
+      .await
+      .unwrap();
+    let update = black_rx.recv().await.unwrap().unwrap();
+    assert_eq!(update.request.unwrap().takeback, None);
+    let state = update.game.unwrap();
+    assert!(state.moves.is_empty());
+    assert_eq!(state.next, Color::White as i32);
+
+    let draw = |player: &Player, seat: Uuid| {
+      request(
+        player,
+        DrawRequest {
+          uuid: seat.to_string(),
+          decline: false,
+        },
+      )
+    };
+
+    service.offer_draw(draw(&alice, white)).await.unwrap();
+    service.offer_draw(draw(&bob, black)).await.unwrap();
+
+    let game = service.games.get(&id).unwrap();
+    assert!(game.finished.is_some());
+    assert_eq!(game.end(), EndResult::Draw(true));
+    drop(game);
+
+    let resign = ResignRequest {
+      uuid: black.to_string(),
+    };
+    let status = service.resign(request(&bob, resign)).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+
+    let events: Vec<_> = service.games.get(&id).unwrap().log.events().to_vec();
+    assert!(matches!(
+      events.last().unwrap().event,
+      Event::DrawAccepted { .. }
+    ));
+  }
+
+  #[tokio::test]
+  async fn resigning_hands_the_opponent_the_game() {
+    let (service, alice, bob) = service();
+
+    let (white, black) = (Uuid::new_v4(), Uuid::new_v4());
+    let id = service.create_game(
+      (white, alice),
+      (black, bob.clone()),
+      GameSettings::default(),
+      true,
+    );
+
+    let resign = ResignRequest {
+      uuid: black.to_string(),
+    };
+    service.resign(request(&bob, resign)).await.unwrap();
+
+    let game = service.games.get(&id).unwrap();
+    assert!(game.finished.is_some());
+    assert_eq!(game.end(), EndResult::Color(Color::White as i32));
+    drop(game);
+
+    let text = service.metrics.render(service.gauges());
+    assert!(text.contains("chesstactoe_games_finished_total 1\n"));
+  }
+
+  #[tokio::test]
+  async fn lists_only_games_still_being_played() {
+    let (service, alice, bob) = service();
+
+    let (white, black) = (Uuid::new_v4(), Uuid::new_v4());
+    let id = service.create_game(
+      (white, alice.clone()),
+      (black, bob.clone()),
+      GameSettings::default(),
+      true,
+    );
+
+    let list = || service.list_games(request(&alice, ListGamesRequest {}));
+
+    let games = list().await.unwrap().into_inner().games;
+    assert_eq!(games.len(), 1);
+    assert_eq!(games[0].id, id.to_string());
+
+    let resign = ResignRequest {
+      uuid: black.to_string(),
+    };
+    service.resign(request(&bob, resign)).await.unwrap();
+
+    // Finished games wait in memory for rematches, but they're over as far as the list goes.
+    assert!(service.games.contains_key(&id));
+    assert!(list().await.unwrap().into_inner().games.is_empty());
+  }
+
+  #[tokio::test]
+  async fn expires_unjoined_lobbies() {
+    let (service, alice, _) = service();
+    let config = ReaperConfig::default();
+    let now = Instant::now();
+
+    let (tx, mut rx) = mpsc::channel(2);
+
+    service.lobbies.insert(
+      "code".to_owned(),
+      LobbyData {
+        host: Uuid::new_v4(),
+        sender: tx,
+        host_player: alice,
+        settings: GameSettings::default(),
+        public: true,
+        created: now,
+      },
+    );
+
+    service.reap(&config, now + Duration::from_secs(60)).await;
+    assert!(service.lobbies.contains_key("code"));
+
+    service.reap(&config, now + config.lobby_timeout).await;
+    assert!(!service.lobbies.contains_key("code"));
+
+    let status = rx.recv().await.unwrap().unwrap_err();
+    assert_eq!(status.code(), tonic::Code::DeadlineExceeded);
+  }
+
+  #[tokio::test]
+  async fn forfeits_after_disconnect_grace() {
+    let (service, alice, bob) = service();
+    let config = ReaperConfig::default();
+    let now = Instant::now();
+
+    let (white, black) = (Uuid::new_v4(), Uuid::new_v4());
+    let id = service.create_game(
+      (white, alice.clone()),
+      (black, bob.clone()),
+      GameSettings::default(),
+      true,
+    );
+
+    let mut white_rx = subscribe(&service, white);
+    let black_rx = subscribe(&service, black);
+    drop(black_rx);
+
+    service.reap(&config, now).await;
+    service.reap(&config, now + Duration::from_secs(1)).await;
+    assert!(service.games.get(&id).unwrap().state().forfeit.is_none());
+
+    service.reap(&config, now + config.disconnect_grace).await;
+    assert_eq!(
+      service.games.get(&id).unwrap().state().forfeit,
+      Some(Color::White)
+    );
+
+    let state = white_rx.recv().await.unwrap().unwrap().game.unwrap();
+    assert_eq!(
+      state.end_result,
+      Some(tic_tac_toe::EndResult::Color(Color::White as i32))
+    );
+
+    let timed_out = service.games.get(&id).unwrap().log.events().last().cloned();
+    assert!(matches!(
+      timed_out.unwrap().event,
+      Event::TimedOut { side: Side::Black }
+    ));
+
+    // Forfeiting a game that's already over changes nothing.
+    service.forfeit(id, Color::Black, now).await;
+    assert_eq!(
+      service.games.get(&id).unwrap().state().forfeit,
+      Some(Color::White)
+    );
+
+    assert_eq!(service.accounts.get(&alice.id).unwrap().stats.wins, 1);
+    assert_eq!(service.accounts.get(&bob.id).unwrap().stats.losses, 1);
+  }
+
+  #[tokio::test]
+  async fn reconnecting_resets_the_grace_period() {
+    let (service, alice, bob) = service();
+    let config = ReaperConfig::default();
+    let now = Instant::now();
+
+    let (white, black) = (Uuid::new_v4(), Uuid::new_v4());
+    let id = service.create_game((white, alice), (black, bob), GameSettings::default(), true);
+
+    let _white_rx = subscribe(&service, white);
+
+    service.reap(&config, now).await;
+
+    let _black_rx = subscribe(&service, black);
+
+    service.reap(&config, now + config.disconnect_grace).await;
+    assert!(service.games.get(&id).unwrap().state().forfeit.is_none());
+  }
+
+  #[tokio::test]
+  async fn archives_finished_and_abandoned_games() {
+    let (service, alice, bob) = service();
+    let config = ReaperConfig::default();
+    let now = Instant::now();
+
+    let finished = service.create_game(
+      (Uuid::new_v4(), alice.clone()),
+      (Uuid::new_v4(), bob.clone()),
+      GameSettings::default(),
+      true,
+    );
+    service.games.get_mut(&finished).unwrap().finished = Some(now);
+
+    let abandoned = service.create_game(
+      (Uuid::new_v4(), alice),
+      (Uuid::new_v4(), bob),
+      GameSettings::default(),
+      true,
+    );
+
+    service.reap(&config, now).await;
+    assert_eq!(service.games.len(), 2);
+
+    service
+      .reap(
+        &config,
+        now + config.disconnect_grace.max(config.finished_timeout),
+      )
+      .await;
+
+    assert!(service.games.is_empty());
+    assert!(service.game_ids.is_empty());
+
+    let archived = service.archive.games();
+    assert_eq!(archived.len(), 2);
+    let left = archived.iter().find(|game| game.id == abandoned).unwrap();
+    assert_eq!(left.result, ArchivedResult::Abandoned);
+    assert!(!left.forfeit);
+    let timed_out: Vec<_> = left.events.iter().map(|logged| &logged.event).collect();
+    assert!(matches!(
+      timed_out[..],
+      [
+        Event::Created { .. },
+        Event::TimedOut { side: Side::White },
+        Event::TimedOut { side: Side::Black }
+      ]
+    ));
+    assert!(archived.iter().any(|game| game.id == finished));
+  }
+
+  #[tokio::test]
+  async fn serves_archived_games_with_their_moves() {
+    let (service, alice, bob) = service();
+
+    let (white, black) = (Uuid::new_v4(), Uuid::new_v4());
+    let id = service.create_game(
+      (white, alice.clone()),
+      (black, bob.clone()),
+      GameSettings::default(),
+      true,
+    );
+
+    let _white_rx = subscribe(&service, white);
+    let _black_rx = subscribe(&service, black);
+
+    let moves = [(&alice, white, 4, "e2e4"), (&bob, black, 4, "e7e5")];
+    for (player, seat, board, alg) in moves {
+      let request = request(
+        player,
+        MovePieceRequest {
+          board,
+          alg: alg.to_owned(),
+          uuid: seat.to_string(),
+        },
+      );
+      service.move_piece(request).await.unwrap();
+    }
+
+    let game = service.remove_game(id).unwrap();
+    service.archive_game(id, &game, ArchivedResult::Draw);
+
+    let listed = service
+      .list_archived_games(request(&bob, ListArchivedGamesRequest::default()))
+      .await
+      .unwrap()
+      .into_inner()
+      .games;
+
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].id, id.to_string());
+    assert_eq!(listed[0].moves, ["4 e2e4", "4 e7e5"]);
+
+    let fetched = service
+      .get_archived_game(request(
+        &alice,
+        GetArchivedGameRequest { id: id.to_string() },
+      ))
+      .await
+      .unwrap()
+      .into_inner();
+
+    assert_eq!(fetched.outcome(), GameOutcome::Drawn);
+    assert_eq!(fetched.white, "alice");
+
+    let missing = service
+      .get_archived_game(request(
+        &alice,
+        GetArchivedGameRequest {
+          id: Uuid::new_v4().to_string(),
+        },
+      ))
+      .await;
+
+    assert_eq!(missing.unwrap_err().code(), tonic::Code::NotFound);
+  }
+
+  #[tokio::test]
+  async fn keeps_private_archived_games_to_their_players() {
+    let (service, alice, bob) = service();
+    let carol: Player = service
+      .accounts
+      .register("carol", "password3")
+      .unwrap()
+      .into();
+
+    let (white, black) = (Uuid::new_v4(), Uuid::new_v4());
+    let id = service.create_game(
+      (white, alice),
+      (black, bob.clone()),
+      GameSettings::default(),
+      false,
+    );
+
+    let game = service.remove_game(id).unwrap();
+    service.archive_game(id, &game, ArchivedResult::Abandoned);
+
+    let listed = |player: &Player| {
+      service.list_archived_games(request(
+        player,
+        ListArchivedGamesRequest {
+          username: "alice".to_owned(),
+        },
+      ))
+    };
+    assert_eq!(listed(&bob).await.unwrap().into_inner().games.len(), 1);
+    assert!(listed(&carol).await.unwrap().into_inner().games.is_empty());
+
+    let fetched = |player: &Player| {
+      service.get_archived_game(request(
+        player,
+        GetArchivedGameRequest { id: id.to_string() },
+      ))
+    };
+    fetched(&bob).await.unwrap();
+    let status = fetched(&carol).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::PermissionDenied);
+
+    let events = GetGameEventsRequest {
+      id: id.to_string(),
+      since: 0,
+    };
+    let status = service
+      .get_game_events(request(&carol, events))
+      .await
+      .unwrap_err();
+    assert_eq!(status.code(), tonic::Code::PermissionDenied);
+  }
+
+  #[tokio::test]
+  async fn serves_the_event_log_of_live_and_archived_games() {
+    let (service, alice, bob) = service();
+
+    let (white, black) = (Uuid::new_v4(), Uuid::new_v4());
+    let id = service.create_game(
+      (white, alice.clone()),
+      (black, bob.clone()),
+      GameSettings::default(),
+      true,
+    );
+
+    let _white_rx = subscribe(&service, white);
+    let _black_rx = subscribe(&service, black);
+
+    let moves = [(&alice, white, 4, "e2e4"), (&bob, black, 0, "e7e5")];
+    for (player, seat, board, alg) in moves {
+      let request = request(
+        player,
+        MovePieceRequest {
+          board,
+          alg: alg.to_owned(),
+          uuid: seat.to_string(),
+        },
+      );
+      service.move_piece(request).await.unwrap();
+    }
+
+    let chat = SendChatRequest {
+      uuid: black.to_string(),
+      text: "gl".to_owned(),
+    };
+    service.send_chat(request(&bob, chat)).await.unwrap();
+
+    let events = |since| {
+      service.get_game_events(request(
+        &alice,
+        GetGameEventsRequest {
+          id: id.to_string(),
+          since,
+        },
+      ))
+    };
+
+    let log = events(0).await.unwrap().into_inner().events;
+    assert_eq!(log.len(), 4);
+    assert!(matches!(log[0].kind, Some(game_event::Kind::Created(_))));
+    assert!(matches!(
+      &log[2].kind,
+      Some(game_event::Kind::Move(played)) if played.board == 0 && played.alg == "e7e5"
+    ));
+    assert!(matches!(&log[3].kind, Some(game_event::Kind::Chat(msg)) if msg.text == "gl"));
+
+    let by_seat = GetGameEventsRequest {
+      id: black.to_string(),
+      since: 0,
+    };
+    let seat_log = service
+      .get_game_events(request(&bob, by_seat))
+      .await
+      .unwrap();
+    assert_eq!(seat_log.into_inner().events, log);
+
+    let rest = events(2).await.unwrap().into_inner().events;
+    assert_eq!(
+      rest.iter().map(|event| event.seq).collect::<Vec<_>>(),
+      [2, 3]
+    );
+
+    let game = service.remove_game(id).unwrap();
+    service.archive_game(id, &game, ArchivedResult::Abandoned);
+
+    let archived = service.archive.games();
+    let state = GameState::replay(&archived[0].events).unwrap();
+    assert_eq!(state, *game.state());
+
+    assert_eq!(events(0).await.unwrap().into_inner().events, log);
+
+    let status = service
+      .get_game_events(request(
+        &alice,
+        GetGameEventsRequest {
+          id: Uuid::new_v4().to_string(),
+          since: 0,
+        },
+      ))
+      .await
+      .unwrap_err();
+    assert_eq!(status.code(), tonic::Code::NotFound);
+  }
+}
@@ -0,0 +1,508 @@
+//! The `Admin` service, for looking into live games and stepping in. It sits under [`GameService`]
+//! so it can reach the games, lobbies and queue the service keeps.
+
+use std::time::Instant;
+
+use helpers::chesstactoe::{
+  admin_server::Admin, Adjudication, AdminGameInfo, AdminListGamesRequest, AdminListGamesResponse,
+  AdminListLobbiesRequest, AdminListLobbiesResponse, AdminListQueueRequest, AdminListQueueResponse,
+  AdminLobbyInfo, DumpGameRequest, EndGameRequest, EndGameResponse, GameDump, KickPlayerRequest,
+  KickPlayerResponse, SendNoticeRequest, SendNoticeResponse,
+};
+
+use super::*;
+
+fn parse_id(id: &str) -> Result<Uuid, Status> {
+  Uuid::parse_str(id).map_err(|_| Status::invalid_argument("Invalid UUID"))
+}
+
+/// What the players are told when an admin steps in.
+fn admin_notice(what: &str, reason: &str) -> String {
+  match reason.trim() {
+    "" => format!("An admin {what}"),
+    reason => format!("An admin {what}: {reason}"),
+  }
+}
+
+impl GameService {
+  fn admin_info(&self, id: Uuid, game: &Ongoing) -> AdminGameInfo {
+    AdminGameInfo {
+      id: id.to_string(),
+      white: game.white_player.username.clone(),
+      black: game.black_player.username.clone(),
+      white_seat: game.white.to_string(),
+      black_seat: game.black.to_string(),
+      settings: Some(game.settings.clone()),
+      public: game.public,
+      next: game.state().game.next as i32,
+      moves: game.state().moves.len() as u32,
+      finished: game.finished.is_some(),
+      white_connected: self.updates.connected(game.white),
+      black_connected: self.updates.connected(game.black),
+    }
+  }
+
+  /// Ends a game with `winner`, or as a draw without one, and tells both players why.
+  async fn adjudicate(&self, id: Uuid, winner: Option<Color>, reason: &str) -> Result<(), Status> {
+    let game = self
+      .games
+      .get_mut(&id)
+      .ok_or_else(|| Status::not_found("No game with that ID"))?;
+
+    let adjudicated = Event::Adjudicated {
+      winner: winner.map(Into::into),
+      reason: reason.to_owned(),
+    };
+    let notice = admin_notice("ended the game", reason);
+    self.apply(game, adjudicated, notice, Instant::now())?;
+
+    tracing::warn!(game = %id, ?winner, reason, "Admin ended game");
+
+    Ok(())
+  }
+
+  /// Ends a game without a result, closing both players' streams, and archives it as abandoned.
+  async fn abort(&self, id: Uuid, reason: &str) -> Result<(), Status> {
+    let mut game = self
+      .games
+      .get_mut(&id)
+      .ok_or_else(|| Status::not_found("No game with that ID"))?;
+
+    // A game that already ended keeps its result and was counted when it did.
+    if game.finished.is_none() {
+      game.record(Event::Aborted {
+        reason: reason.to_owned(),
+      })?;
+      game.finished = Some(Instant::now());
+      self.metrics.game_finished();
+    }
+
+    let seats = [game.white, game.black];
+
+    drop(game);
+
+    for seat in seats {
+      let status = Status::aborted(admin_notice("aborted the game", reason));
+      self.updates.close(seat, status);
+    }
+
+    if let Some(game) = self.remove_game(id) {
+      tracing::warn!(game = %id, reason, "Admin aborted game");
+      self.archive_game(id, &game, ArchivedResult::Abandoned);
+    }
+
+    Ok(())
+  }
+
+  async fn kick(&self, username: &str, reason: &str) -> Result<KickPlayerResponse, Status> {
+    let account = self
+      .accounts
+      .find(username)
+      .ok_or_else(|| Status::not_found("No player with that username"))?;
+
+    let kicked = || Status::aborted(admin_notice("removed you", reason));
+
+    let playing: Vec<(Uuid, Uuid, Color)> = self
+      .games
+      .iter()
+      .filter(|game| game.finished.is_none())
+      .filter_map(|game| {
+        if game.white_player.id == account.id {
+          Some((*game.key(), game.white, Color::Black))
+        } else if game.black_player.id == account.id {
+          Some((*game.key(), game.black, Color::White))
+        } else {
+          None
+        }
+      })
+      .collect();
+
+    // Games can end or be cleaned up between listing them and adjudicating them.
+    let already_over = |status: &Status| {
+      matches!(
+        status.code(),
+        tonic::Code::FailedPrecondition | tonic::Code::NotFound
+      )
+    };
+
+    let mut games = 0;
+
+    for (id, seat, winner) in &playing {
+      match self.adjudicate(*id, Some(*winner), reason).await {
+        Ok(()) => games += 1,
+        Err(status) if already_over(&status) => continue,
+        Err(status) => return Err(status),
+      }
+
+      self.updates.close(*seat, kicked());
+    }
+
+    let hosted: Vec<String> = self
+      .lobbies
+      .iter()
+      .filter(|lobby| lobby.host_player.id == account.id)
+      .map(|lobby| lobby.key().clone())
+      .collect();
+
+    for code in &hosted {
+      if let Some((_, lobby)) = self.lobbies.remove(code) {
+        lobby.sender.send(Err(kicked())).await.unwrap_or(());
+      }
+    }
+
+    let queued = self.matchmaker.remove_player(account.id);
+
+    for ticket in &queued {
+      ticket.sender.send(Err(kicked())).await.unwrap_or(());
+    }
+
+    tracing::warn!(player = %account.id, reason, "Admin kicked player");
+
+    Ok(KickPlayerResponse {
+      games,
+      lobbies: hosted.len() as u32,
+      queued: queued.len() as u32,
+    })
+  }
+}
+
+/// Served with [`crate::auth::AdminInterceptor`] in front of it.
+#[derive(Debug, Clone)]
+pub struct AdminService {
+  service: GameService,
+}
+
+impl AdminService {
+  pub fn new(service: GameService) -> Self {
+    AdminService { service }
+  }
+}
+
+#[tonic::async_trait]
+impl Admin for AdminService {
+  async fn list_games(
+    &self,
+    _request: Request<AdminListGamesRequest>,
+  ) -> Result<Response<AdminListGamesResponse>, Status> {
+    let games = self
+      .service
+      .games
+      .iter()
+      .map(|game| self.service.admin_info(*game.key(), &game))
+      .collect();
+
+    Ok(Response::new(AdminListGamesResponse { games }))
+  }
+
+  async fn list_lobbies(
+    &self,
+    _request: Request<AdminListLobbiesRequest>,
+  ) -> Result<Response<AdminListLobbiesResponse>, Status> {
+    let now = Instant::now();
+
+    let lobbies = self
+      .service
+      .lobbies
+      .iter()
+      .map(|lobby| AdminLobbyInfo {
+        code: lobby.key().clone(),
+        host: lobby.host_player.username.clone(),
+        host_seat: lobby.host.to_string(),
+        settings: Some(lobby.settings.clone()),
+        public: lobby.public,
+        waiting_secs: now.saturating_duration_since(lobby.created).as_secs(),
+      })
+      .collect();
+
+    Ok(Response::new(AdminListLobbiesResponse { lobbies }))
+  }
+
+  async fn list_queue(
+    &self,
+    _request: Request<AdminListQueueRequest>,
+  ) -> Result<Response<AdminListQueueResponse>, Status> {
+    Ok(Response::new(AdminListQueueResponse {
+      entries: self.service.matchmaker.entries(Instant::now()),
+    }))
+  }
+
+  async fn dump_game(
+    &self,
+    request: Request<DumpGameRequest>,
+  ) -> Result<Response<GameDump>, Status> {
+    let id = parse_id(&request.get_ref().id)?;
+
+    logging::record_game(&id);
+
+    let game = self
+      .service
+      .games
+      .get(&id)
+      .ok_or_else(|| Status::not_found("No game with that ID"))?;
+
+    Ok(Response::new(GameDump {
+      info: Some(self.service.admin_info(id, &game)),
+      game: Some(board_state(&game)),
+      request: Some(game.requests()),
+      chat: game.state().chat.clone(),
+      events: game
+        .log
+        .events()
+        .iter()
+        .map(|logged| logged.info())
+        .collect(),
+    }))
+  }
+
+  async fn end_game(
+    &self,
+    request: Request<EndGameRequest>,
+  ) -> Result<Response<EndGameResponse>, Status> {
+    let request = request.into_inner();
+
+    let id = parse_id(&request.id)?;
+
+    logging::record_game(&id);
+
+    match request.result() {
+      Adjudication::Abort => self.service.abort(id, &request.reason).await?,
+      Adjudication::WhiteWins => {
+        self
+          .service
+          .adjudicate(id, Some(Color::White), &request.reason)
+          .await?
+      }
+      Adjudication::BlackWins => {
+        self
+          .service
+          .adjudicate(id, Some(Color::Black), &request.reason)
+          .await?
+      }
+      Adjudication::Draw => self.service.adjudicate(id, None, &request.reason).await?,
+    }
+
+    Ok(Response::new(EndGameResponse {}))
+  }
+
+  async fn kick_player(
+    &self,
+    request: Request<KickPlayerRequest>,
+  ) -> Result<Response<KickPlayerResponse>, Status> {
+    let request = request.into_inner();
+
+    let kicked = self
+      .service
+      .kick(&request.username, &request.reason)
+      .await?;
+
+    Ok(Response::new(kicked))
+  }
+
+  async fn send_notice(
+    &self,
+    request: Request<SendNoticeRequest>,
+  ) -> Result<Response<SendNoticeResponse>, Status> {
+    let text = request.into_inner().text;
+
+    if text.trim().is_empty() {
+      return Err(Status::invalid_argument("The notice is empty"));
+    }
+
+    let recipients = self.service.updates.announce(text.trim());
+
+    tracing::info!(recipients, text, "Admin sent notice");
+
+    Ok(Response::new(SendNoticeResponse {
+      recipients: recipients as u32,
+    }))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use helpers::{
+    auth::{AUTHORIZATION, BEARER},
+    chesstactoe::{game_event, GameOutcome},
+  };
+  use tonic::{metadata::MetadataValue, service::Interceptor};
+
+  use super::*;
+  use crate::{auth::AdminInterceptor, testing};
+
+  fn admin() -> (AdminService, Player, Player) {
+    let app = testing::app();
+    let (alice, bob) = testing::players(&app);
+
+    (AdminService::new(app.game), alice, bob)
+  }
+
+  /// Starts a game between alice and bob with both players watching.
+  fn start(
+    admin: &AdminService,
+    alice: &Player,
+    bob: &Player,
+  ) -> (
+    Uuid,
+    [mpsc::Receiver<Result<SubscribeBoardResponse, Status>>; 2],
+  ) {
+    let (white, black) = (Uuid::new_v4(), Uuid::new_v4());
+
+    let id = admin.service.create_game(
+      (white, alice.clone()),
+      (black, bob.clone()),
+      GameSettings::default(),
+      true,
+    );
+
+    let streams = [white, black].map(|seat| {
+      let mut rx = admin
+        .service
+        .updates
+        .subscribe(seat, SubscribeBoardResponse::default());
+      rx.try_recv().unwrap().unwrap();
+      rx
+    });
+
+    (id, streams)
+  }
+
+  fn end_game(id: Uuid, result: Adjudication) -> Request<EndGameRequest> {
+    Request::new(EndGameRequest {
+      id: id.to_string(),
+      result: result as i32,
+      reason: "engine use".to_owned(),
+    })
+  }
+
+  #[tokio::test]
+  async fn adjudicates_games_and_tells_the_players() {
+    let (admin, alice, bob) = admin();
+    let (id, [mut white_rx, _black_rx]) = start(&admin, &alice, &bob);
+
+    admin
+      .end_game(end_game(id, Adjudication::BlackWins))
+      .await
+      .unwrap();
+
+    let update = white_rx.recv().await.unwrap().unwrap();
+    assert_eq!(update.notice, "An admin ended the game: engine use");
+    assert_eq!(
+      update.game.unwrap().end_result,
+      Some(tic_tac_toe::EndResult::Color(Color::Black as i32))
+    );
+
+    let again = admin.end_game(end_game(id, Adjudication::Draw)).await;
+    assert_eq!(again.unwrap_err().code(), tonic::Code::FailedPrecondition);
+
+    let dump = admin
+      .dump_game(Request::new(DumpGameRequest { id: id.to_string() }))
+      .await
+      .unwrap()
+      .into_inner();
+
+    assert!(dump.info.unwrap().finished);
+    assert!(matches!(
+      &dump.events.last().unwrap().kind,
+      Some(game_event::Kind::Adjudicated(adjudicated))
+        if adjudicated.outcome() == GameOutcome::BlackWon && adjudicated.reason == "engine use"
+    ));
+
+    let accounts = &admin.service.accounts;
+    assert_eq!(accounts.get(&bob.id).unwrap().stats.wins, 1);
+  }
+
+  #[tokio::test]
+  async fn aborting_closes_the_streams_and_archives_the_game() {
+    let (admin, alice, bob) = admin();
+    let (id, [mut white_rx, mut black_rx]) = start(&admin, &alice, &bob);
+
+    admin
+      .end_game(end_game(id, Adjudication::Abort))
+      .await
+      .unwrap();
+
+    for rx in [&mut white_rx, &mut black_rx] {
+      let status = rx.recv().await.unwrap().unwrap_err();
+      assert_eq!(status.code(), tonic::Code::Aborted);
+      assert!(rx.recv().await.is_none());
+    }
+
+    assert!(admin.service.games.is_empty());
+
+    let archived = admin.service.archive.games();
+    assert_eq!(archived[0].result, ArchivedResult::Abandoned);
+    assert!(matches!(
+      &archived[0].events.last().unwrap().event,
+      Event::Aborted { reason } if reason == "engine use"
+    ));
+
+    let text = admin.service.metrics.render(admin.service.gauges());
+    assert!(text.contains("chesstactoe_games_finished_total 1\n"));
+  }
+
+  #[tokio::test]
+  async fn kicking_forfeits_games_and_notices_reach_everyone() {
+    let (admin, alice, bob) = admin();
+    let (id, [mut white_rx, mut black_rx]) = start(&admin, &alice, &bob);
+
+    let notice = SendNoticeRequest {
+      text: " Restarting in 5 minutes ".to_owned(),
+    };
+    let sent = admin.send_notice(Request::new(notice)).await.unwrap();
+    assert_eq!(sent.into_inner().recipients, 2);
+
+    for rx in [&mut white_rx, &mut black_rx] {
+      let update = rx.recv().await.unwrap().unwrap();
+      assert_eq!(update.notice, "Restarting in 5 minutes");
+    }
+
+    let kick = KickPlayerRequest {
+      username: "alice".to_owned(),
+      reason: String::new(),
+    };
+    let kicked = admin.kick_player(Request::new(kick)).await.unwrap();
+    assert_eq!(kicked.into_inner().games, 1);
+
+    let update = black_rx.recv().await.unwrap().unwrap();
+    assert_eq!(
+      update.game.unwrap().end_result,
+      Some(tic_tac_toe::EndResult::Color(Color::Black as i32))
+    );
+
+    // The final position first, then the stream ends.
+    white_rx.recv().await.unwrap().unwrap();
+    let status = white_rx.recv().await.unwrap().unwrap_err();
+    assert_eq!(status.message(), "An admin removed you");
+
+    assert!(admin.service.games.get(&id).unwrap().finished.is_some());
+
+    let missing = KickPlayerRequest {
+      username: "carol".to_owned(),
+      reason: String::new(),
+    };
+    let status = admin.kick_player(Request::new(missing)).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::NotFound);
+  }
+
+  #[test]
+  fn needs_the_admin_token() {
+    let mut interceptor = AdminInterceptor {
+      token: "hunter2".into(),
+    };
+
+    let with_token = |token: &str| {
+      let mut request = Request::new(());
+      let value = MetadataValue::try_from(format!("{BEARER}{token}")).unwrap();
+      request.metadata_mut().insert(AUTHORIZATION, value);
+      request
+    };
+
+    assert!(interceptor.call(with_token("hunter2")).is_ok());
+
+    let status = interceptor.call(with_token("hunter3")).unwrap_err();
+    assert_eq!(status.code(), tonic::Code::PermissionDenied);
+
+    let status = interceptor.call(Request::new(())).unwrap_err();
+    assert_eq!(status.code(), tonic::Code::Unauthenticated);
+  }
+}
@@ -0,0 +1,158 @@
+//! The Chess-Tac-Toe game server. The binary only reads the config and serves an [`App`] on the
+//! configured address, so tests can serve the same services on a listener of their own.
+
+// Helpers all over the server fail with the `Status` their RPC returns, so the error can be passed
+// straight on with `?`. Clippy finds `Status` too large for a `Result`, but boxing it in each helper
+// would only mean unboxing it again at every RPC.
+#![allow(clippy::result_large_err)]
+
+pub mod accounts;
+pub mod archive;
+pub mod auth;
+pub mod broadcast;
+pub mod chat;
+pub mod config;
+pub mod events;
+pub mod game;
+pub mod logging;
+pub mod matchmaking;
+pub mod metrics;
+#[doc(hidden)]
+pub mod testing;
+pub mod web;
+
+use std::{error::Error, future::Future, sync::Arc};
+
+use accounts::AccountStore;
+use archive::GameArchive;
+use auth::{AdminInterceptor, AuthInterceptor, AuthService, SessionKeys};
+use config::{Config, Storage};
+use game::{admin::AdminService, GameService, ReaperConfig};
+use helpers::chesstactoe::{
+  admin_server::AdminServer, auth_server::AuthServer, game_server::GameServer,
+};
+use metrics::RpcMetricsLayer;
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::transport::Server;
+use tonic_web::GrpcWebLayer;
+
+const ACCOUNTS_FILE: &str = "players.json";
+const ARCHIVE_FILE: &str = "games.jsonl";
+
+/// Every gRPC service of the server, set up from a [`Config`].
+pub struct App {
+  pub game: GameService,
+  keys: SessionKeys,
+  accounts: Arc<AccountStore>,
+  admin_token: Option<String>,
+  cors_origins: Vec<String>,
+  reaper: ReaperConfig,
+}
+
+impl App {
+  /// Opens the configured storage, creating the data directory if needed.
+  pub fn open(config: &Config) -> Result<Self, Box<dyn Error>> {
+    let (accounts, archive) = match config.storage {
+      Storage::Memory => (AccountStore::in_memory(), GameArchive::in_memory()),
+      Storage::File => {
+        std::fs::create_dir_all(&config.data_dir)?;
+
+        (
+          AccountStore::open(config.data_dir.join(ACCOUNTS_FILE))?,
+          GameArchive::open(config.data_dir.join(ARCHIVE_FILE))?,
+        )
+      }
+    };
+
+    let keys = match &config.secret {
+      Some(secret) => SessionKeys::new(secret.as_bytes()),
+      None => {
+        tracing::warn!("CHESSTACTOE_SECRET is not set, sessions won't survive a restart");
+        SessionKeys::generate()
+      }
+    };
+
+    Ok(App::new(config, accounts, archive, keys))
+  }
+
+  pub fn new(
+    config: &Config,
+    accounts: AccountStore,
+    archive: GameArchive,
+    keys: SessionKeys,
+  ) -> Self {
+    let accounts = Arc::new(accounts);
+
+    App {
+      game: GameService::new(accounts.clone(), Arc::new(archive), config.service()),
+      keys,
+      accounts,
+      admin_token: config.admin_token.clone(),
+      cors_origins: config.cors_origins.clone(),
+      reaper: config.reaper(),
+    }
+  }
+
+  /// Starts matchmaking and the reaper, then serves gRPC and gRPC-Web on `listener` until
+  /// `shutdown` completes. The background tasks keep running on the runtime afterwards.
+  pub async fn serve(
+    self,
+    listener: TcpListener,
+    shutdown: impl Future<Output = ()>,
+  ) -> Result<(), tonic::transport::Error> {
+    let App {
+      game,
+      keys,
+      accounts,
+      admin_token,
+      cors_origins,
+      reaper,
+    } = self;
+
+    game.spawn_matchmaking();
+    game.spawn_reaper(reaper);
+
+    let interceptor = AuthInterceptor {
+      keys: keys.clone(),
+      accounts: accounts.clone(),
+    };
+
+    let (mut health, health_service) = tonic_health::server::health_reporter();
+    health.set_serving::<AuthServer<AuthService>>().await;
+    health.set_serving::<GameServer<GameService>>().await;
+
+    // Left out entirely without a token, rather than served with one nobody can guess.
+    let admin = admin_token.as_deref().map(|token| {
+      AdminServer::with_interceptor(
+        AdminService::new(game.clone()),
+        AdminInterceptor {
+          token: token.into(),
+        },
+      )
+    });
+
+    if admin.is_some() {
+      health.set_serving::<AdminServer<AdminService>>().await;
+    } else {
+      tracing::info!("CHESSTACTOE_ADMIN_TOKEN is not set, the admin service is off");
+    }
+
+    let rpc_metrics = RpcMetricsLayer {
+      metrics: game.metrics(),
+    };
+
+    Server::builder()
+      .accept_http1(true)
+      .trace_fn(logging::rpc_span)
+      .layer(web::cors(&cors_origins))
+      .layer(GrpcWebLayer::new())
+      .layer(rpc_metrics)
+      .add_service(health_service)
+      .add_service(AuthServer::new(AuthService { keys, accounts }))
+      .add_service(GameServer::with_interceptor(game, interceptor))
+      .add_optional_service(admin)
+      .serve_with_incoming_shutdown(TcpListenerStream::new(listener), shutdown)
+      .await
+  }
+}
@@ -0,0 +1,106 @@
+//! Structured logging. Every RPC runs in an `rpc` span carrying the client's request id, and the
+//! handlers fill in the player, game and board as they learn them.
+
+use std::error::Error;
+
+use helpers::auth::REQUEST_ID;
+use tonic::codegen::http;
+use tracing::{field, Span};
+use tracing_subscriber::EnvFilter;
+
+use crate::config::LogFormat;
+
+/// Installs the global subscriber. `filter` uses the `RUST_LOG` syntax, like `info,server=debug`.
+pub fn init(filter: &str, format: LogFormat) -> Result<(), Box<dyn Error>> {
+  let builder = tracing_subscriber::fmt().with_env_filter(EnvFilter::try_new(filter)?);
+
+  let installed = match format {
+    LogFormat::Text => builder.try_init(),
+    LogFormat::Json => builder.json().try_init(),
+  };
+
+  // Only fails when a subscriber was already installed.
+  installed.map_err(|err| err.to_string().into())
+}
+
+/// Span for a single RPC, see [`tonic::transport::Server::trace_fn`].
+pub fn rpc_span(request: &http::Request<()>) -> Span {
+  let request_id = request
+    .headers()
+    .get(REQUEST_ID)
+    .and_then(|id| id.to_str().ok())
+    .unwrap_or_default();
+
+  tracing::info_span!(
+    "rpc",
+    method = request.uri().path(),
+    request_id,
+    player = field::Empty,
+    game = field::Empty,
+    board = field::Empty,
+  )
+}
+
+/// Adds the game being played to the current RPC's span.
+pub fn record_game(id: &impl std::fmt::Display) {
+  Span::current().record("game", field::display(id));
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{
+    io,
+    sync::{Arc, Mutex},
+  };
+
+  use super::*;
+
+  #[derive(Clone, Default)]
+  struct Captured(Arc<Mutex<Vec<u8>>>);
+
+  impl io::Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+      self.0.lock().unwrap().extend_from_slice(buf);
+      Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+      Ok(())
+    }
+  }
+
+  #[test]
+  fn events_carry_the_request_id_and_game() {
+    let captured = Captured::default();
+
+    let writer = captured.clone();
+    let subscriber = tracing_subscriber::fmt()
+      .json()
+      .with_writer(move || writer.clone())
+      .finish();
+
+    tracing::subscriber::with_default(subscriber, || {
+      let request = http::Request::post("/chesstactoe.Game/MovePiece")
+        .header(REQUEST_ID, "5c1e0f4e")
+        .body(())
+        .unwrap();
+
+      let span = rpc_span(&request);
+      let _entered = span.enter();
+
+      record_game(&"b2a7");
+      Span::current().record("board", 4);
+      tracing::info!("Move played");
+    });
+
+    let line = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+
+    assert!(line.contains(r#""request_id":"5c1e0f4e""#), "{line}");
+    assert!(
+      line.contains(r#""method":"/chesstactoe.Game/MovePiece""#),
+      "{line}"
+    );
+    assert!(line.contains(r#""game":"b2a7""#), "{line}");
+    assert!(line.contains(r#""board":4"#), "{line}");
+  }
+}
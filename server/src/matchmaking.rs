@@ -0,0 +1,522 @@
+use std::{
+  collections::HashMap,
+  sync::{Arc, Mutex},
+  time::Instant,
+};
+
+use helpers::chesstactoe::{GameSettings, JoinResponse, QueueEntry, TimeControl};
+use serde::Deserialize;
+use tokio::sync::{mpsc::Sender, oneshot};
+use tonic::Status;
+use uuid::Uuid;
+
+use crate::auth::Player;
+
+/// Games are only made between players asking for the same settings.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Pool {
+  pub initial: u32,
+  pub increment: u32,
+  pub variant: i32,
+  pub rated: bool,
+}
+
+impl From<&GameSettings> for Pool {
+  fn from(settings: &GameSettings) -> Self {
+    let time_control = settings.time_control.clone().unwrap_or_default();
+
+    Pool {
+      initial: time_control.initial,
+      increment: time_control.increment,
+      variant: settings.variant,
+      rated: settings.rated,
+    }
+  }
+}
+
+impl Pool {
+  pub fn settings(&self) -> GameSettings {
+    GameSettings {
+      time_control: Some(TimeControl {
+        initial: self.initial,
+        increment: self.increment,
+      }),
+      variant: self.variant,
+      rated: self.rated,
+    }
+  }
+}
+
+#[derive(Debug)]
+pub struct Ticket {
+  pub seat: Uuid,
+  pub player: Player,
+  pub rating: f64,
+  pub joined: Instant,
+  pub sender: Sender<Result<JoinResponse, Status>>,
+  /// Dropped together with the ticket, which stops the disconnect watcher of [`Matchmaker::join`].
+  dropped: Option<oneshot::Sender<()>>,
+}
+
+impl Ticket {
+  pub fn new(
+    seat: Uuid,
+    player: Player,
+    rating: f64,
+    sender: Sender<Result<JoinResponse, Status>>,
+  ) -> Self {
+    Ticket {
+      seat,
+      player,
+      rating,
+      joined: Instant::now(),
+      sender,
+      dropped: None,
+    }
+  }
+}
+
+#[derive(Debug)]
+pub struct Pairing {
+  pub pool: Pool,
+  pub white: Ticket,
+  pub black: Ticket,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct MatchmakingConfig {
+  /// Largest rating difference accepted for a player who just joined.
+  pub initial_window: f64,
+  /// How much the accepted difference grows for every second spent waiting.
+  pub widening: f64,
+  pub max_window: f64,
+}
+
+impl Default for MatchmakingConfig {
+  fn default() -> Self {
+    MatchmakingConfig {
+      initial_window: 100.0,
+      widening: 10.0,
+      max_window: 700.0,
+    }
+  }
+}
+
+#[derive(Debug, Default)]
+pub struct Matchmaker {
+  config: MatchmakingConfig,
+  pools: Mutex<HashMap<Pool, Vec<Ticket>>>,
+}
+
+impl Matchmaker {
+  pub fn new(config: MatchmakingConfig) -> Self {
+    Matchmaker {
+      config,
+      pools: Mutex::default(),
+    }
+  }
+
+  fn window(&self, ticket: &Ticket, now: Instant) -> f64 {
+    let waited = now.saturating_duration_since(ticket.joined).as_secs_f64();
+
+    (self.config.initial_window + waited * self.config.widening).min(self.config.max_window)
+  }
+
+  fn compatible(&self, first: &Ticket, second: &Ticket, now: Instant) -> bool {
+    first.player != second.player
+      && (first.rating - second.rating).abs()
+        <= self.window(first, now).max(self.window(second, now))
+  }
+
+  /// Pairs `ticket` with the closest rated compatible player waiting in `pool`, or queues it.
+  pub fn enqueue(&self, pool: Pool, ticket: Ticket, now: Instant) -> Option<Pairing> {
+    let mut pools = self.pools.lock().unwrap();
+
+    let waiting = pools.entry(pool.clone()).or_default();
+
+    waiting.retain(|waiting| !waiting.sender.is_closed());
+
+    let opponent = waiting
+      .iter()
+      .enumerate()
+      .filter(|(_, waiting)| self.compatible(waiting, &ticket, now))
+      .min_by(|(_, a), (_, b)| {
+        let a = (a.rating - ticket.rating).abs();
+        let b = (b.rating - ticket.rating).abs();
+        a.total_cmp(&b)
+      })
+      .map(|(idx, _)| idx);
+
+    match opponent {
+      Some(idx) => Some(Pairing {
+        pool,
+        white: waiting.remove(idx),
+        black: ticket,
+      }),
+      None => {
+        waiting.push(ticket);
+        None
+      }
+    }
+  }
+
+  /// Same as [`Matchmaker::enqueue`], but a queued ticket is also removed as soon as its `Join`
+  /// stream is dropped.
+  pub fn join(self: &Arc<Self>, pool: Pool, mut ticket: Ticket) -> Option<Pairing> {
+    let (dropped, ticket_dropped) = oneshot::channel();
+    ticket.dropped = Some(dropped);
+
+    let seat = ticket.seat;
+    let sender = ticket.sender.clone();
+
+    let pairing = self.enqueue(pool, ticket, Instant::now());
+
+    if pairing.is_none() {
+      let matchmaker = self.clone();
+
+      tokio::spawn(async move {
+        tokio::select! {
+          _ = sender.closed() => {
+            matchmaker.remove(seat);
+          }
+          _ = ticket_dropped => {}
+        }
+      });
+    }
+
+    pairing
+  }
+
+  /// Pairs players whose rating windows have grown wide enough while waiting, and forgets the ones
+  /// who disconnected.
+  pub fn sweep(&self, now: Instant) -> Vec<Pairing> {
+    let mut pools = self.pools.lock().unwrap();
+
+    let mut pairings = vec![];
+
+    for (pool, waiting) in pools.iter_mut() {
+      waiting.retain(|ticket| !ticket.sender.is_closed());
+      waiting.sort_by_key(|ticket| ticket.joined);
+
+      let mut idx = 0;
+
+      while idx < waiting.len() {
+        let opponent = (idx + 1..waiting.len())
+          .filter(|other| self.compatible(&waiting[idx], &waiting[*other], now))
+          .min_by(|a, b| {
+            let a = (waiting[*a].rating - waiting[idx].rating).abs();
+            let b = (waiting[*b].rating - waiting[idx].rating).abs();
+            a.total_cmp(&b)
+          });
+
+        match opponent {
+          Some(other) => {
+            let black = waiting.remove(other);
+            let white = waiting.remove(idx);
+
+            pairings.push(Pairing {
+              pool: pool.clone(),
+              white,
+              black,
+            });
+          }
+          None => idx += 1,
+        }
+      }
+    }
+
+    pools.retain(|_, waiting| !waiting.is_empty());
+
+    pairings
+  }
+
+  fn remove(&self, seat: Uuid) -> Option<Ticket> {
+    let mut pools = self.pools.lock().unwrap();
+
+    pools.values_mut().find_map(|waiting| {
+      let idx = waiting.iter().position(|ticket| ticket.seat == seat)?;
+      Some(waiting.remove(idx))
+    })
+  }
+
+  /// Removes the queued seat if it belongs to `player`.
+  pub fn leave(&self, seat: Uuid, player: &Player) -> bool {
+    let mut pools = self.pools.lock().unwrap();
+
+    pools.values_mut().any(|waiting| {
+      match waiting
+        .iter()
+        .position(|ticket| ticket.seat == seat && ticket.player == *player)
+      {
+        Some(idx) => {
+          waiting.remove(idx);
+          true
+        }
+        None => false,
+      }
+    })
+  }
+
+  pub fn len(&self) -> usize {
+    self.pools.lock().unwrap().values().map(Vec::len).sum()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// Everyone waiting, longest waiting first.
+  pub fn entries(&self, now: Instant) -> Vec<QueueEntry> {
+    let pools = self.pools.lock().unwrap();
+
+    let mut tickets: Vec<(&Pool, &Ticket)> = pools
+      .iter()
+      .flat_map(|(pool, waiting)| waiting.iter().map(move |ticket| (pool, ticket)))
+      .collect();
+    tickets.sort_by_key(|(_, ticket)| ticket.joined);
+
+    tickets
+      .into_iter()
+      .map(|(pool, ticket)| QueueEntry {
+        seat: ticket.seat.to_string(),
+        username: ticket.player.username.clone(),
+        rating: ticket.rating,
+        settings: Some(pool.settings()),
+        waiting_secs: now.saturating_duration_since(ticket.joined).as_secs(),
+      })
+      .collect()
+  }
+
+  /// Takes every ticket of a player out of the queue.
+  pub fn remove_player(&self, id: Uuid) -> Vec<Ticket> {
+    let mut pools = self.pools.lock().unwrap();
+
+    let mut removed = vec![];
+
+    for waiting in pools.values_mut() {
+      let (kept, gone) = waiting
+        .drain(..)
+        .partition(|ticket: &Ticket| ticket.player.id != id);
+      *waiting = kept;
+      removed.extend(gone);
+    }
+
+    removed
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::{Duration, Instant};
+
+  use tokio::sync::mpsc;
+  use uuid::Uuid;
+
+  use super::*;
+
+  fn arrival(
+    name: &str,
+    rating: f64,
+    joined: Instant,
+  ) -> (Ticket, mpsc::Receiver<Result<JoinResponse, Status>>) {
+    let (tx, rx) = mpsc::channel(4);
+
+    let mut ticket = Ticket::new(
+      Uuid::new_v4(),
+      Player {
+        id: Uuid::new_v4(),
+        username: name.to_owned(),
+      },
+      rating,
+      tx,
+    );
+    ticket.joined = joined;
+
+    (ticket, rx)
+  }
+
+  fn pool(rated: bool) -> Pool {
+    Pool {
+      initial: 300,
+      increment: 3,
+      variant: 0,
+      rated,
+    }
+  }
+
+  #[test]
+  fn pairs_close_ratings_immediately() {
+    let matchmaker = Matchmaker::default();
+    let now = Instant::now();
+
+    let (alice, _alice_rx) = arrival("alice", 1500.0, now);
+    let (bob, _bob_rx) = arrival("bob", 1550.0, now);
+
+    assert!(matchmaker.enqueue(pool(true), alice, now).is_none());
+
+    let pairing = matchmaker.enqueue(pool(true), bob, now).unwrap();
+
+    assert_eq!(pairing.white.player.username, "alice");
+    assert_eq!(pairing.black.player.username, "bob");
+    assert_eq!(matchmaker.len(), 0);
+  }
+
+  #[test]
+  fn lists_and_removes_a_players_tickets() {
+    let matchmaker = Matchmaker::default();
+    let now = Instant::now();
+
+    let (alice, _alice_rx) = arrival("alice", 1500.0, now);
+    let (bob, _bob_rx) = arrival("bob", 2500.0, now + Duration::from_secs(5));
+    let alice_id = alice.player.id;
+
+    matchmaker.enqueue(pool(true), bob, now);
+    matchmaker.enqueue(pool(false), alice, now);
+
+    let entries = matchmaker.entries(now + Duration::from_secs(10));
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].username, "alice");
+    assert_eq!(entries[0].waiting_secs, 10);
+    assert_eq!(entries[1].waiting_secs, 5);
+
+    let removed = matchmaker.remove_player(alice_id);
+    assert_eq!(removed.len(), 1);
+    assert_eq!(matchmaker.len(), 1);
+  }
+
+  #[test]
+  fn keeps_pools_apart() {
+    let matchmaker = Matchmaker::default();
+    let now = Instant::now();
+
+    let (alice, _alice_rx) = arrival("alice", 1500.0, now);
+    let (bob, _bob_rx) = arrival("bob", 1500.0, now);
+
+    assert!(matchmaker.enqueue(pool(true), alice, now).is_none());
+    assert!(matchmaker.enqueue(pool(false), bob, now).is_none());
+    assert!(matchmaker.sweep(now + Duration::from_secs(600)).is_empty());
+    assert_eq!(matchmaker.len(), 2);
+  }
+
+  #[test]
+  fn picks_the_closest_rating() {
+    let matchmaker = Matchmaker::default();
+    let now = Instant::now();
+
+    let (far, _far_rx) = arrival("far", 1600.0, now);
+    let (close, _close_rx) = arrival("close", 1490.0, now);
+    let (arriving, _arriving_rx) = arrival("arriving", 1500.0, now);
+
+    matchmaker.enqueue(pool(true), far, now);
+    matchmaker.enqueue(pool(true), close, now);
+
+    let pairing = matchmaker.enqueue(pool(true), arriving, now).unwrap();
+
+    assert_eq!(pairing.white.player.username, "close");
+  }
+
+  #[test]
+  fn window_widens_while_waiting() {
+    let matchmaker = Matchmaker::default();
+    let start = Instant::now();
+
+    let (strong, _strong_rx) = arrival("strong", 1900.0, start);
+    let (weak, _weak_rx) = arrival("weak", 1600.0, start + Duration::from_secs(5));
+
+    assert!(matchmaker.enqueue(pool(true), strong, start).is_none());
+    assert!(matchmaker
+      .enqueue(pool(true), weak, start + Duration::from_secs(5))
+      .is_none());
+
+    assert!(matchmaker.sweep(start + Duration::from_secs(10)).is_empty());
+
+    let pairings = matchmaker.sweep(start + Duration::from_secs(20));
+
+    assert_eq!(pairings.len(), 1);
+    assert_eq!(pairings[0].white.player.username, "strong");
+    assert_eq!(matchmaker.len(), 0);
+  }
+
+  #[test]
+  fn skips_disconnected_players() {
+    let matchmaker = Matchmaker::default();
+    let now = Instant::now();
+
+    let (gone, gone_rx) = arrival("gone", 1500.0, now);
+    let (waiting, _waiting_rx) = arrival("waiting", 1500.0, now);
+    let (arriving, _arriving_rx) = arrival("arriving", 1500.0, now);
+
+    matchmaker.enqueue(pool(false), gone, now);
+    drop(gone_rx);
+    matchmaker.enqueue(pool(false), waiting, now);
+
+    let pairing = matchmaker.enqueue(pool(false), arriving, now).unwrap();
+
+    assert_eq!(pairing.white.player.username, "waiting");
+    assert_eq!(matchmaker.len(), 0);
+  }
+
+  #[test]
+  fn never_pairs_a_player_with_themselves() {
+    let matchmaker = Matchmaker::default();
+    let now = Instant::now();
+
+    let (first, _first_rx) = arrival("alice", 1500.0, now);
+    let (mut second, _second_rx) = arrival("alice", 1500.0, now);
+    second.player = first.player.clone();
+
+    matchmaker.enqueue(pool(false), first, now);
+
+    assert!(matchmaker.enqueue(pool(false), second, now).is_none());
+  }
+
+  #[test]
+  fn leave_requires_the_owner() {
+    let matchmaker = Matchmaker::default();
+    let now = Instant::now();
+
+    let (alice, _alice_rx) = arrival("alice", 1500.0, now);
+    let seat = alice.seat;
+    let owner = alice.player.clone();
+
+    matchmaker.enqueue(pool(false), alice, now);
+
+    let (bob, _bob_rx) = arrival("bob", 1500.0, now);
+
+    assert!(!matchmaker.leave(seat, &bob.player));
+    assert!(matchmaker.leave(seat, &owner));
+    assert_eq!(matchmaker.len(), 0);
+  }
+
+  #[tokio::test]
+  async fn dropped_join_stream_leaves_queue() {
+    let matchmaker = Arc::new(Matchmaker::default());
+
+    let (alice, alice_rx) = arrival("alice", 1500.0, Instant::now());
+
+    assert!(matchmaker.join(pool(false), alice).is_none());
+    assert_eq!(matchmaker.len(), 1);
+
+    drop(alice_rx);
+    tokio::task::yield_now().await;
+    tokio::time::sleep(Duration::from_millis(10)).await;
+
+    assert_eq!(matchmaker.len(), 0);
+  }
+
+  #[tokio::test]
+  async fn paired_join_stream_ends() {
+    let matchmaker = Arc::new(Matchmaker::default());
+
+    let (alice, mut alice_rx) = arrival("alice", 1500.0, Instant::now());
+    let (bob, _bob_rx) = arrival("bob", 1500.0, Instant::now());
+
+    assert!(matchmaker.join(pool(false), alice).is_none());
+
+    let pairing = matchmaker.join(pool(false), bob).unwrap();
+    drop(pairing);
+
+    assert!(alice_rx.recv().await.is_none());
+  }
+}
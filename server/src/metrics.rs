@@ -0,0 +1,326 @@
+//! Counters for monitoring, served in the Prometheus text format.
+
+use std::{
+  collections::BTreeMap,
+  convert::Infallible,
+  fmt::Write,
+  net::SocketAddr,
+  sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+  },
+  task::{Context, Poll},
+  time::{Duration, Instant},
+};
+
+use hyper::{
+  service::{make_service_fn, service_fn},
+  Body, Response, StatusCode,
+};
+use tonic::codegen::{http, BoxFuture, Service};
+use tower_layer::Layer;
+
+/// Upper bounds of the RPC latency buckets, in seconds.
+const LATENCY_BUCKETS: [f64; 8] = [0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0];
+
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Current size of things the service keeps, read when the metrics are scraped.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Gauges {
+  pub active_games: usize,
+  pub queued_players: usize,
+  pub open_lobbies: usize,
+}
+
+#[derive(Debug, Default)]
+struct RpcStats {
+  /// Calls that finished within each of `LATENCY_BUCKETS`, not cumulative.
+  buckets: [u64; LATENCY_BUCKETS.len()],
+  count: u64,
+  seconds: f64,
+  /// Failed calls by gRPC status code.
+  errors: BTreeMap<i32, u64>,
+}
+
+#[derive(Debug, Default)]
+pub struct Metrics {
+  games_started: AtomicU64,
+  games_finished: AtomicU64,
+  moves: AtomicU64,
+  rpcs: Mutex<BTreeMap<String, RpcStats>>,
+}
+
+impl Metrics {
+  pub fn game_started(&self) {
+    self.games_started.fetch_add(1, Ordering::Relaxed);
+  }
+
+  pub fn game_finished(&self) {
+    self.games_finished.fetch_add(1, Ordering::Relaxed);
+  }
+
+  pub fn move_played(&self) {
+    self.moves.fetch_add(1, Ordering::Relaxed);
+  }
+
+  /// Counts a call of `method`, like `/chesstactoe.Game/MovePiece`. `code` is its gRPC status.
+  pub fn rpc(&self, method: &str, elapsed: Duration, code: i32) {
+    let mut rpcs = self.rpcs.lock().unwrap();
+    let stats = rpcs.entry(method.to_owned()).or_default();
+
+    let seconds = elapsed.as_secs_f64();
+
+    if let Some(bucket) = LATENCY_BUCKETS.iter().position(|bound| seconds <= *bound) {
+      stats.buckets[bucket] += 1;
+    }
+
+    stats.count += 1;
+    stats.seconds += seconds;
+
+    if code != 0 {
+      *stats.errors.entry(code).or_default() += 1;
+    }
+  }
+
+  /// Everything in the Prometheus text format.
+  pub fn render(&self, gauges: Gauges) -> String {
+    let mut out = String::new();
+
+    let counters = [
+      (
+        "chesstactoe_games_started_total",
+        "Games created since the server started.",
+        &self.games_started,
+      ),
+      (
+        "chesstactoe_games_finished_total",
+        "Games decided on the board or by forfeit.",
+        &self.games_finished,
+      ),
+      (
+        "chesstactoe_moves_total",
+        "Moves played, rate() gives moves per second.",
+        &self.moves,
+      ),
+    ];
+
+    for (name, help, counter) in counters {
+      metric(&mut out, name, help, "counter");
+      writeln!(out, "{name} {}", counter.load(Ordering::Relaxed)).unwrap();
+    }
+
+    let gauges = [
+      (
+        "chesstactoe_active_games",
+        "Games in memory, finished ones waiting to be archived included.",
+        gauges.active_games,
+      ),
+      (
+        "chesstactoe_queued_players",
+        "Players waiting in the matchmaking queue.",
+        gauges.queued_players,
+      ),
+      (
+        "chesstactoe_open_lobbies",
+        "Lobbies waiting for someone to join.",
+        gauges.open_lobbies,
+      ),
+    ];
+
+    for (name, help, value) in gauges {
+      metric(&mut out, name, help, "gauge");
+      writeln!(out, "{name} {value}").unwrap();
+    }
+
+    let rpcs = self.rpcs.lock().unwrap();
+
+    metric(
+      &mut out,
+      "chesstactoe_rpc_duration_seconds",
+      "Time until an RPC answered, streams count until they opened.",
+      "histogram",
+    );
+    for (method, stats) in rpcs.iter() {
+      let mut cumulative = 0;
+
+      for (bound, count) in LATENCY_BUCKETS.iter().zip(stats.buckets) {
+        cumulative += count;
+        writeln!(
+          out,
+          "chesstactoe_rpc_duration_seconds_bucket{{method=\"{method}\",le=\"{bound}\"}} {cumulative}"
+        )
+        .unwrap();
+      }
+
+      writeln!(
+        out,
+        "chesstactoe_rpc_duration_seconds_bucket{{method=\"{method}\",le=\"+Inf\"}} {}",
+        stats.count
+      )
+      .unwrap();
+      writeln!(
+        out,
+        "chesstactoe_rpc_duration_seconds_sum{{method=\"{method}\"}} {}",
+        stats.seconds
+      )
+      .unwrap();
+      writeln!(
+        out,
+        "chesstactoe_rpc_duration_seconds_count{{method=\"{method}\"}} {}",
+        stats.count
+      )
+      .unwrap();
+    }
+
+    metric(
+      &mut out,
+      "chesstactoe_rpc_errors_total",
+      "RPCs that failed, by gRPC status code.",
+      "counter",
+    );
+    for (method, stats) in rpcs.iter() {
+      for (code, count) in &stats.errors {
+        writeln!(
+          out,
+          "chesstactoe_rpc_errors_total{{method=\"{method}\",code=\"{code}\"}} {count}"
+        )
+        .unwrap();
+      }
+    }
+
+    out
+  }
+}
+
+fn metric(out: &mut String, name: &str, help: &str, kind: &str) {
+  writeln!(out, "# HELP {name} {help}").unwrap();
+  writeln!(out, "# TYPE {name} {kind}").unwrap();
+}
+
+/// Times every gRPC call and counts the failed ones. Goes inside the gRPC-Web layer, so browser
+/// calls are counted under the same methods.
+#[derive(Debug, Clone)]
+pub struct RpcMetricsLayer {
+  pub metrics: Arc<Metrics>,
+}
+
+impl<S> Layer<S> for RpcMetricsLayer {
+  type Service = RpcMetrics<S>;
+
+  fn layer(&self, inner: S) -> Self::Service {
+    RpcMetrics {
+      inner,
+      metrics: self.metrics.clone(),
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct RpcMetrics<S> {
+  inner: S,
+  metrics: Arc<Metrics>,
+}
+
+impl<S, ReqBody, ResBody> Service<http::Request<ReqBody>> for RpcMetrics<S>
+where
+  S: Service<http::Request<ReqBody>, Response = http::Response<ResBody>>,
+  S::Future: Send + 'static,
+{
+  type Response = S::Response;
+  type Error = S::Error;
+  type Future = BoxFuture<Self::Response, Self::Error>;
+
+  fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+    self.inner.poll_ready(cx)
+  }
+
+  fn call(&mut self, request: http::Request<ReqBody>) -> Self::Future {
+    let method = request.uri().path().to_owned();
+    let metrics = self.metrics.clone();
+    let started = Instant::now();
+
+    let response = self.inner.call(request);
+
+    Box::pin(async move {
+      let response = response.await?;
+
+      // Failed calls answer with the status in the headers, successful ones in the trailers.
+      let code = response
+        .headers()
+        .get("grpc-status")
+        .and_then(|code| code.to_str().ok())
+        .and_then(|code| code.parse().ok())
+        .unwrap_or(0);
+
+      metrics.rpc(&method, started.elapsed(), code);
+
+      Ok(response)
+    })
+  }
+}
+
+/// Serves `/metrics` over plain HTTP until the server stops. `render` is called on every scrape.
+pub async fn serve<F>(addr: SocketAddr, render: F) -> Result<(), hyper::Error>
+where
+  F: Fn() -> String + Clone + Send + Sync + 'static,
+{
+  let make_service = make_service_fn(move |_| {
+    let render = render.clone();
+
+    async move {
+      Ok::<_, Infallible>(service_fn(move |request: http::Request<Body>| {
+        let response = if request.uri().path() == "/metrics" {
+          Response::builder()
+            .header(http::header::CONTENT_TYPE, CONTENT_TYPE)
+            .body(Body::from(render()))
+        } else {
+          Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::empty())
+        };
+
+        async move { response }
+      }))
+    }
+  });
+
+  hyper::Server::try_bind(&addr)?.serve(make_service).await
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn renders_counters_gauges_and_rpcs() {
+    let metrics = Metrics::default();
+
+    metrics.game_started();
+    metrics.move_played();
+    metrics.move_played();
+    metrics.rpc("/chesstactoe.Game/MovePiece", Duration::from_millis(3), 0);
+    metrics.rpc("/chesstactoe.Game/MovePiece", Duration::from_millis(80), 9);
+
+    let text = metrics.render(Gauges {
+      active_games: 1,
+      queued_players: 3,
+      open_lobbies: 0,
+    });
+
+    for line in [
+      "chesstactoe_games_started_total 1",
+      "chesstactoe_games_finished_total 0",
+      "chesstactoe_moves_total 2",
+      "chesstactoe_queued_players 3",
+      "chesstactoe_rpc_duration_seconds_bucket{method=\"/chesstactoe.Game/MovePiece\",le=\"0.001\"} 0",
+      "chesstactoe_rpc_duration_seconds_bucket{method=\"/chesstactoe.Game/MovePiece\",le=\"0.005\"} 1",
+      "chesstactoe_rpc_duration_seconds_bucket{method=\"/chesstactoe.Game/MovePiece\",le=\"0.1\"} 2",
+      "chesstactoe_rpc_duration_seconds_count{method=\"/chesstactoe.Game/MovePiece\"} 2",
+      "chesstactoe_rpc_errors_total{method=\"/chesstactoe.Game/MovePiece\",code=\"9\"} 1",
+      "# TYPE chesstactoe_rpc_duration_seconds histogram",
+    ] {
+      assert!(text.lines().any(|l| l == line), "missing {line:?} in\n{text}");
+    }
+  }
+}
@@ -0,0 +1,58 @@
+//! Fixtures for the server's own tests, shared by the unit tests and the ones in `tests/`, which
+//! can't see anything behind `cfg(test)`. Not part of the server's API.
+
+use std::net::SocketAddr;
+
+use tokio::{net::TcpListener, sync::oneshot};
+
+use crate::{
+  accounts::AccountStore,
+  archive::GameArchive,
+  auth::{Player, SessionKeys},
+  config::Config,
+  game::GameService,
+  App,
+};
+
+/// An [`App`] with the default settings, keeping everything in memory.
+pub fn app() -> App {
+  App::new(
+    &Config::default(),
+    AccountStore::in_memory(),
+    GameArchive::in_memory(),
+    SessionKeys::generate(),
+  )
+}
+
+/// Registers alice and bob, for tests that call the services directly.
+pub fn players(app: &App) -> (Player, Player) {
+  let alice = app.accounts.register("alice", "password1").unwrap().into();
+  let bob = app.accounts.register("bob", "password2").unwrap().into();
+
+  (alice, bob)
+}
+
+/// An [`App`] served on an ephemeral port, until this is dropped.
+pub struct Running {
+  pub addr: SocketAddr,
+  pub game: GameService,
+  _shutdown: oneshot::Sender<()>,
+}
+
+/// Serves `app` on a listener of its own.
+pub async fn serve(app: App) -> Running {
+  let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+  let addr = listener.local_addr().unwrap();
+  let game = app.game.clone();
+
+  let (shutdown, stopped) = oneshot::channel();
+  tokio::spawn(app.serve(listener, async {
+    stopped.await.unwrap_or(());
+  }));
+
+  Running {
+    addr,
+    game,
+    _shutdown: shutdown,
+  }
+}
@@ -0,0 +1,269 @@
+//! gRPC-Web support, so browsers can call the server without a proxy in front of it.
+
+use std::time::Duration;
+
+use helpers::auth::{AUTHORIZATION, REQUEST_ID};
+use tonic::codegen::http::{header::HeaderName, HeaderValue, Method};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+const MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+const ALLOW_HEADERS: [&str; 6] = [
+  "x-grpc-web",
+  "content-type",
+  "x-user-agent",
+  "grpc-timeout",
+  AUTHORIZATION,
+  REQUEST_ID,
+];
+
+const EXPOSE_HEADERS: [&str; 3] = ["grpc-status", "grpc-message", "grpc-status-details-bin"];
+
+/// CORS rules for gRPC-Web calls. Any origin is allowed when `origins` is empty.
+pub fn cors(origins: &[String]) -> CorsLayer {
+  let allow_origin = if origins.is_empty() {
+    AllowOrigin::mirror_request()
+  } else {
+    AllowOrigin::list(
+      origins
+        .iter()
+        .filter_map(|origin| HeaderValue::from_str(origin).ok()),
+    )
+  };
+
+  CorsLayer::new()
+    .allow_origin(allow_origin)
+    .allow_methods([Method::POST])
+    .allow_headers(ALLOW_HEADERS.map(HeaderName::from_static))
+    .expose_headers(EXPOSE_HEADERS.map(HeaderName::from_static))
+    .max_age(MAX_AGE)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::net::SocketAddr;
+
+  use helpers::{
+    auth::BEARER,
+    chesstactoe::{
+      join_response::GameStatus, JoinLobbyRequest, JoinResponse, LoginResponse, MakeLobbyRequest,
+      MakeLobbyResponse, RegisterRequest,
+    },
+  };
+  use hyper::{body::HttpBody, Body, Client, Request, Response};
+  use prost::Message;
+
+  use super::*;
+  use crate::testing;
+
+  const TRAILERS: u8 = 0x80;
+
+  fn frame(message: &impl Message) -> Vec<u8> {
+    let message = message.encode_to_vec();
+
+    let mut frame = vec![0];
+    frame.extend((message.len() as u32).to_be_bytes());
+    frame.extend(message);
+    frame
+  }
+
+  async fn call(
+    addr: SocketAddr,
+    path: &str,
+    message: &impl Message,
+    token: Option<&str>,
+  ) -> Response<Body> {
+    let mut request = Request::post(format!("http://{addr}{path}"))
+      .header("content-type", "application/grpc-web+proto")
+      .header("x-grpc-web", "1");
+
+    if let Some(token) = token {
+      request = request.header(AUTHORIZATION, format!("{BEARER}{token}"));
+    }
+
+    Client::new()
+      .request(request.body(Body::from(frame(message))).unwrap())
+      .await
+      .unwrap()
+  }
+
+  /// Reads gRPC-Web frames until `count` messages or the trailers arrived.
+  async fn read_frames(body: &mut Body, count: usize) -> (Vec<Vec<u8>>, Option<String>) {
+    let mut buffer = vec![];
+    let mut messages = vec![];
+
+    loop {
+      while buffer.len() >= 5 {
+        let len = u32::from_be_bytes(buffer[1..5].try_into().unwrap()) as usize;
+
+        if buffer.len() < 5 + len {
+          break;
+        }
+
+        let flags = buffer[0];
+        let payload: Vec<u8> = buffer.drain(..5 + len).skip(5).collect();
+
+        if flags & TRAILERS != 0 {
+          return (messages, Some(String::from_utf8(payload).unwrap()));
+        }
+
+        messages.push(payload);
+
+        if messages.len() == count {
+          return (messages, None);
+        }
+      }
+
+      match body.data().await {
+        Some(chunk) => buffer.extend_from_slice(&chunk.unwrap()),
+        None => return (messages, None),
+      }
+    }
+  }
+
+  async fn register(addr: SocketAddr, username: &str) -> String {
+    let mut response = call(
+      addr,
+      "/chesstactoe.Auth/Register",
+      &RegisterRequest {
+        username: username.to_owned(),
+        password: "password".to_owned(),
+      },
+      None,
+    )
+    .await;
+
+    let (messages, trailers) = read_frames(response.body_mut(), 2).await;
+
+    assert!(trailers.unwrap().contains("grpc-status:0"));
+
+    LoginResponse::decode(messages[0].as_slice()).unwrap().token
+  }
+
+  #[tokio::test]
+  async fn unary_calls() {
+    let server = testing::serve(testing::app()).await;
+    let addr = server.addr;
+
+    let mut response = call(
+      addr,
+      "/chesstactoe.Auth/Register",
+      &RegisterRequest {
+        username: "alice".to_owned(),
+        password: "password".to_owned(),
+      },
+      None,
+    )
+    .await;
+
+    assert!(response.headers()["content-type"]
+      .to_str()
+      .unwrap()
+      .starts_with("application/grpc-web"));
+
+    let (messages, trailers) = read_frames(response.body_mut(), 2).await;
+
+    assert_eq!(messages.len(), 1);
+    assert_eq!(
+      LoginResponse::decode(messages[0].as_slice())
+        .unwrap()
+        .username,
+      "alice"
+    );
+    assert!(trailers.unwrap().contains("grpc-status:0"));
+  }
+
+  #[tokio::test]
+  async fn server_streaming_calls() {
+    let server = testing::serve(testing::app()).await;
+    let addr = server.addr;
+
+    let alice = register(addr, "alice").await;
+    let bob = register(addr, "bob").await;
+
+    let mut lobby = call(
+      addr,
+      "/chesstactoe.Game/MakeLobby",
+      &MakeLobbyRequest::default(),
+      Some(&alice),
+    )
+    .await;
+
+    let (messages, _) = read_frames(lobby.body_mut(), 1).await;
+    let code = MakeLobbyResponse::decode(messages[0].as_slice())
+      .unwrap()
+      .room_id;
+
+    let mut joined = call(
+      addr,
+      "/chesstactoe.Game/JoinLobby",
+      &JoinLobbyRequest { code },
+      Some(&bob),
+    )
+    .await;
+
+    let (messages, trailers) = read_frames(joined.body_mut(), 2).await;
+    let response = JoinResponse::decode(messages[0].as_slice()).unwrap();
+
+    assert_eq!(response.status, GameStatus::Ready as i32);
+    assert!(trailers.unwrap().contains("grpc-status:0"));
+
+    let (messages, _) = read_frames(lobby.body_mut(), 1).await;
+    let response = MakeLobbyResponse::decode(messages[0].as_slice()).unwrap();
+
+    assert_eq!(
+      response.join_response.unwrap().status,
+      GameStatus::Ready as i32
+    );
+  }
+
+  #[tokio::test]
+  async fn rejects_missing_sessions() {
+    let server = testing::serve(testing::app()).await;
+    let addr = server.addr;
+
+    let mut response = call(
+      addr,
+      "/chesstactoe.Game/MakeLobby",
+      &MakeLobbyRequest::default(),
+      None,
+    )
+    .await;
+
+    let status = match response.headers().get("grpc-status") {
+      Some(status) => status.to_str().unwrap().to_owned(),
+      None => read_frames(response.body_mut(), 1).await.1.unwrap(),
+    };
+
+    assert!(status.contains(&(tonic::Code::Unauthenticated as i32).to_string()));
+  }
+
+  #[tokio::test]
+  async fn answers_preflight_requests() {
+    let server = testing::serve(testing::app()).await;
+    let addr = server.addr;
+
+    let response = Client::new()
+      .request(
+        Request::options(format!("http://{addr}/chesstactoe.Game/Join"))
+          .header("origin", "http://example.com")
+          .header("access-control-request-method", "POST")
+          .header(
+            "access-control-request-headers",
+            "authorization,content-type,x-grpc-web",
+          )
+          .body(Body::empty())
+          .unwrap(),
+      )
+      .await
+      .unwrap();
+
+    let headers = response.headers();
+
+    assert_eq!(headers["access-control-allow-origin"], "http://example.com");
+    assert!(headers["access-control-allow-headers"]
+      .to_str()
+      .unwrap()
+      .contains(AUTHORIZATION));
+  }
+}
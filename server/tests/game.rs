@@ -0,0 +1,322 @@
+//! Drives the `Game` service through real tonic clients, against a server started on an ephemeral
+//! port of its own for every test.
+
+use std::collections::HashSet;
+
+use helpers::{
+  auth::Session,
+  chesstactoe::{
+    auth_client::AuthClient, game_client::GameClient, join_response::GameStatus, Color,
+    JoinLobbyRequest, JoinRequest, JoinResponse, ListLobbiesRequest, MakeLobbyRequest,
+    MovePieceRequest, MoveRejection, MoveResult, RegisterRequest, SubscribeBoardRequest,
+    SubscribeBoardResponse,
+  },
+};
+use server::testing;
+use tokio::task::JoinSet;
+use tonic::{
+  codegen::InterceptedService,
+  transport::{Channel, Endpoint},
+  Code, Streaming,
+};
+
+type Client = GameClient<InterceptedService<Channel, Session>>;
+
+/// A server of its own for every test, stopped when the test drops the harness.
+struct Harness(testing::Running);
+
+impl Harness {
+  async fn start() -> Self {
+    Harness(testing::serve(testing::app()).await)
+  }
+
+  async fn channel(&self) -> Channel {
+    Endpoint::from_shared(format!("http://{}", self.0.addr))
+      .unwrap()
+      .connect()
+      .await
+      .unwrap()
+  }
+
+  /// Registers `username` and returns a client logged in as them.
+  async fn player(&self, username: &str) -> Client {
+    let channel = self.channel().await;
+
+    let login = AuthClient::new(channel.clone())
+      .register(RegisterRequest {
+        username: username.to_owned(),
+        password: "password1".to_owned(),
+      })
+      .await
+      .unwrap()
+      .into_inner();
+
+    let session = Session::default();
+    session.set_token(&login.token);
+
+    GameClient::with_interceptor(channel, session)
+  }
+}
+
+/// Reads a `Join` stream until the game is ready and returns the seat.
+async fn ready(stream: &mut Streaming<JoinResponse>) -> String {
+  loop {
+    let response = stream.message().await.unwrap().expect("stream ended early");
+
+    if response.status() == GameStatus::Ready {
+      return response.uuid;
+    }
+  }
+}
+
+/// Queues up and waits for an opponent.
+async fn join(client: &mut Client) -> String {
+  let mut stream = client
+    .join(JoinRequest::default())
+    .await
+    .unwrap()
+    .into_inner();
+
+  ready(&mut stream).await
+}
+
+async fn subscribe(client: &mut Client, seat: &str) -> (Color, Streaming<SubscribeBoardResponse>) {
+  let mut stream = client
+    .subscribe_board(SubscribeBoardRequest {
+      uuid: seat.to_owned(),
+    })
+    .await
+    .unwrap()
+    .into_inner();
+
+  let current = stream.message().await.unwrap().unwrap();
+
+  (current.color(), stream)
+}
+
+async fn play(
+  client: &mut Client,
+  seat: &str,
+  board: u32,
+  alg: &str,
+) -> (MoveResult, MoveRejection) {
+  let response = client
+    .move_piece(MovePieceRequest {
+      board,
+      alg: alg.to_owned(),
+      uuid: seat.to_owned(),
+    })
+    .await
+    .unwrap()
+    .into_inner();
+
+  (response.successful(), response.rejection())
+}
+
+#[tokio::test]
+async fn queued_players_are_paired_and_play() {
+  let harness = Harness::start().await;
+
+  let mut alice = harness.player("alice").await;
+  let mut bob = harness.player("bob").await;
+
+  let mut waiting = alice
+    .join(JoinRequest::default())
+    .await
+    .unwrap()
+    .into_inner();
+
+  let first = waiting.message().await.unwrap().unwrap();
+  assert_eq!(first.status(), GameStatus::NotReady);
+
+  let bob_seat = join(&mut bob).await;
+  let alice_seat = ready(&mut waiting).await;
+  assert_eq!(alice_seat, first.uuid);
+
+  let (alice_color, mut alice_board) = subscribe(&mut alice, &alice_seat).await;
+  let (bob_color, mut bob_board) = subscribe(&mut bob, &bob_seat).await;
+  assert_ne!(alice_color, bob_color);
+
+  let (mut white, white_seat, mut black, black_seat) = if alice_color == Color::White {
+    (alice, alice_seat, bob, bob_seat)
+  } else {
+    (bob, bob_seat, alice, alice_seat)
+  };
+
+  assert_eq!(
+    play(&mut black, &black_seat, 4, "e7e5").await,
+    (
+      MoveResult::ResultIllegal,
+      MoveRejection::RejectionNotYourTurn
+    )
+  );
+
+  let (result, _) = play(&mut white, &white_seat, 4, "e2e4").await;
+  assert_eq!(result, MoveResult::ResultSuccessful);
+
+  for board in [&mut alice_board, &mut bob_board] {
+    let state = board.message().await.unwrap().unwrap().game.unwrap();
+    assert_eq!(state.moves, ["4 e2e4"]);
+  }
+
+  let (result, _) = play(&mut black, &black_seat, 4, "e7e5").await;
+  assert_eq!(result, MoveResult::ResultSuccessful);
+}
+
+#[tokio::test]
+async fn lobby_host_plays_white_against_the_guest() {
+  let harness = Harness::start().await;
+
+  let mut alice = harness.player("alice").await;
+  let mut bob = harness.player("bob").await;
+
+  let mut lobby = alice
+    .make_lobby(MakeLobbyRequest {
+      settings: None,
+      public: true,
+    })
+    .await
+    .unwrap()
+    .into_inner();
+
+  let made = lobby.message().await.unwrap().unwrap();
+  let host_seat = made.join_response.unwrap().uuid;
+
+  let listed = bob
+    .list_lobbies(ListLobbiesRequest {})
+    .await
+    .unwrap()
+    .into_inner()
+    .lobbies;
+  assert_eq!(listed.len(), 1);
+  assert_eq!(listed[0].code, made.room_id);
+  assert_eq!(listed[0].host_name, "alice");
+
+  let mut joined = bob
+    .join_lobby(JoinLobbyRequest {
+      code: made.room_id.clone(),
+    })
+    .await
+    .unwrap()
+    .into_inner();
+  let guest_seat = ready(&mut joined).await;
+
+  let started = lobby
+    .message()
+    .await
+    .unwrap()
+    .unwrap()
+    .join_response
+    .unwrap();
+  assert_eq!(started.status(), GameStatus::Ready);
+  assert_eq!(started.uuid, host_seat);
+
+  let (host_color, _host_board) = subscribe(&mut alice, &host_seat).await;
+  let (guest_color, mut guest_board) = subscribe(&mut bob, &guest_seat).await;
+  assert_eq!((host_color, guest_color), (Color::White, Color::Black));
+
+  let (result, _) = play(&mut alice, &host_seat, 0, "d2d4").await;
+  assert_eq!(result, MoveResult::ResultSuccessful);
+
+  let state = guest_board.message().await.unwrap().unwrap().game.unwrap();
+  assert_eq!(state.last_move, "0 d2d4");
+
+  // Nobody else can play from the guest's seat.
+  let status = alice
+    .move_piece(MovePieceRequest {
+      board: 0,
+      alg: "d7d5".to_owned(),
+      uuid: guest_seat,
+    })
+    .await
+    .unwrap_err();
+  assert_eq!(status.code(), Code::PermissionDenied);
+}
+
+#[tokio::test]
+async fn concurrent_joins_pair_everyone_exactly_once() {
+  let harness = Harness::start().await;
+
+  let mut players = JoinSet::new();
+
+  for i in 0..8 {
+    let mut client = harness.player(&format!("player{i}")).await;
+
+    players.spawn(async move {
+      let seat = join(&mut client).await;
+      let (color, _) = subscribe(&mut client, &seat).await;
+      (seat, color)
+    });
+  }
+
+  let mut seats = HashSet::new();
+  let mut whites = 0;
+
+  while let Some(joined) = players.join_next().await {
+    let (seat, color) = joined.unwrap();
+
+    assert!(seats.insert(seat), "a seat was handed out twice");
+    whites += usize::from(color == Color::White);
+  }
+
+  assert_eq!(seats.len(), 8);
+  assert_eq!(whites, 4);
+
+  let gauges = harness.0.game.gauges();
+  assert_eq!(gauges.active_games, 4);
+  assert_eq!(gauges.queued_players, 0);
+}
+
+#[tokio::test]
+async fn only_one_guest_gets_into_a_lobby() {
+  let harness = Harness::start().await;
+
+  let mut host = harness.player("host").await;
+
+  let mut lobby = host
+    .make_lobby(MakeLobbyRequest::default())
+    .await
+    .unwrap()
+    .into_inner();
+  let code = lobby.message().await.unwrap().unwrap().room_id;
+
+  let mut guests = JoinSet::new();
+
+  for i in 0..6 {
+    let mut client = harness.player(&format!("guest{i}")).await;
+    let code = code.clone();
+
+    guests.spawn(async move { client.join_lobby(JoinLobbyRequest { code }).await });
+  }
+
+  let mut joined = 0;
+
+  while let Some(result) = guests.join_next().await {
+    match result.unwrap() {
+      Ok(_) => joined += 1,
+      Err(status) => assert_eq!(status.code(), Code::NotFound),
+    }
+  }
+
+  assert_eq!(joined, 1);
+  assert_eq!(harness.0.game.gauges().active_games, 1);
+
+  let started = lobby
+    .message()
+    .await
+    .unwrap()
+    .unwrap()
+    .join_response
+    .unwrap();
+  assert_eq!(started.status(), GameStatus::Ready);
+}
+
+#[tokio::test]
+async fn refuses_players_without_a_session() {
+  let harness = Harness::start().await;
+
+  let mut client = GameClient::with_interceptor(harness.channel().await, Session::default());
+
+  let status = client.join(JoinRequest::default()).await.unwrap_err();
+  assert_eq!(status.code(), Code::Unauthenticated);
+}
@@ -0,0 +1,318 @@
+use crossterm::event::KeyCode;
+use helpers::{
+  chesstactoe::{
+    chess::EndResult, Color, GameSettings, SubscribeBoardResponse, TicTacToe, TimeControl,
+  },
+  tictactoe::TicTacToe as HelperToe,
+  Coordinates,
+};
+use tokio::task::JoinHandle;
+
+use crate::{
+  board,
+  network::{Network, Update},
+};
+
+/// (initial, increment) seconds, same as the desktop client offers.
+pub const TIME_CONTROLS: [(u32, u32); 5] = [(0, 0), (60, 0), (180, 2), (300, 3), (600, 5)];
+
+pub fn format_time_control((initial, increment): (u32, u32)) -> String {
+  if initial == 0 {
+    return "Unlimited".to_owned();
+  }
+
+  format!("{}+{}", initial / 60, increment)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Screen {
+  Menu,
+  Waiting,
+  Game,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Input {
+  None,
+  LobbyCode,
+  Move,
+}
+
+pub struct App {
+  pub username: String,
+  pub screen: Screen,
+  pub input_mode: Input,
+  pub input: String,
+  pub time_control: usize,
+  pub rated: bool,
+  pub public: bool,
+  pub seat: Option<String>,
+  pub lobby_code: Option<String>,
+  pub color: Color,
+  /// Local copy of the game, used to check moves before sending them.
+  pub game: HelperToe,
+  /// Last state the server sent, with the end results and the last move.
+  pub state: Option<TicTacToe>,
+  /// Board under the cursor, 0-8 in reading order.
+  pub selected: usize,
+  pub zoomed: bool,
+  /// Colour asked to accept a rematch, if someone offered one.
+  pub rematch: Option<Color>,
+  pub status: Option<String>,
+  pub quit: bool,
+  network: Network,
+  task: Option<JoinHandle<()>>,
+}
+
+impl App {
+  pub fn new(username: String, network: Network) -> Self {
+    App {
+      username,
+      screen: Screen::Menu,
+      input_mode: Input::None,
+      input: String::new(),
+      time_control: 0,
+      rated: false,
+      public: false,
+      seat: None,
+      lobby_code: None,
+      color: Color::White,
+      game: HelperToe::default(),
+      state: None,
+      selected: 4,
+      zoomed: false,
+      rematch: None,
+      status: None,
+      quit: false,
+      network,
+      task: None,
+    }
+  }
+
+  fn settings(&self) -> GameSettings {
+    let (initial, increment) = TIME_CONTROLS[self.time_control];
+
+    GameSettings {
+      time_control: Some(TimeControl { initial, increment }),
+      rated: self.rated,
+      ..Default::default()
+    }
+  }
+
+  /// Stops whatever game or queue we were in and goes back to the menu.
+  fn leave(&mut self) {
+    if let Some(task) = self.task.take() {
+      task.abort();
+    }
+
+    self.screen = Screen::Menu;
+    self.seat = None;
+    self.lobby_code = None;
+    self.state = None;
+    self.game = HelperToe::default();
+    self.zoomed = false;
+    self.rematch = None;
+  }
+
+  fn start(&mut self, task: JoinHandle<()>) {
+    self.task = Some(task);
+    self.screen = Screen::Waiting;
+    self.status = None;
+  }
+
+  pub fn result(&self) -> Option<String> {
+    let end = self.state.as_ref()?.end_result.as_ref()?;
+
+    board::game_result(end)
+  }
+
+  pub fn board_end(&self, board: usize) -> EndResult {
+    self.game.chesses[board / 3][board % 3].end.clone()
+  }
+
+  pub fn on_key(&mut self, key: KeyCode) {
+    match self.input_mode {
+      Input::None => self.on_command(key),
+      _ => self.on_input(key),
+    }
+  }
+
+  fn on_input(&mut self, key: KeyCode) {
+    match key {
+      KeyCode::Esc => {
+        self.input_mode = Input::None;
+        self.input.clear();
+      }
+      KeyCode::Backspace => {
+        self.input.pop();
+      }
+      KeyCode::Enter => {
+        let input = self.input.trim().to_owned();
+        let mode = self.input_mode;
+
+        self.input_mode = Input::None;
+        self.input.clear();
+
+        if input.is_empty() {
+          return;
+        }
+
+        match mode {
+          Input::LobbyCode => {
+            let task = self.network.join_lobby(input);
+            self.start(task);
+          }
+          Input::Move => self.submit_move(&input),
+          Input::None => {}
+        }
+      }
+      KeyCode::Char(char) => self.input.push(char),
+      _ => {}
+    }
+  }
+
+  fn on_command(&mut self, key: KeyCode) {
+    match (self.screen, key) {
+      (Screen::Menu, KeyCode::Esc) => self.quit = true,
+      (Screen::Menu, KeyCode::Char('q')) => {
+        let task = self.network.queue(self.settings());
+        self.start(task);
+      }
+      (Screen::Menu, KeyCode::Char('m')) => {
+        let task = self.network.make_lobby(self.settings(), self.public);
+        self.start(task);
+      }
+      (Screen::Menu, KeyCode::Char('j')) => self.input_mode = Input::LobbyCode,
+      (Screen::Menu, KeyCode::Char('t')) => {
+        self.time_control = (self.time_control + 1) % TIME_CONTROLS.len()
+      }
+      (Screen::Menu, KeyCode::Char('r')) => self.rated = !self.rated,
+      (Screen::Menu, KeyCode::Char('p')) => self.public = !self.public,
+
+      (Screen::Waiting, KeyCode::Esc) => self.leave(),
+
+      (Screen::Game, KeyCode::Esc) if self.zoomed => self.zoomed = false,
+      (Screen::Game, KeyCode::Esc) | (Screen::Game, KeyCode::Char('q')) => self.leave(),
+      (Screen::Game, KeyCode::Enter) | (Screen::Game, KeyCode::Char('z')) => {
+        self.zoomed = !self.zoomed
+      }
+      (Screen::Game, KeyCode::Char('r')) => self.send_rematch(false),
+      (Screen::Game, KeyCode::Char('n')) if self.rematch.is_some() => self.send_rematch(true),
+      (Screen::Game, KeyCode::Char('m')) | (Screen::Game, KeyCode::Char(':')) => {
+        self.input_mode = Input::Move
+      }
+      (Screen::Game, KeyCode::Char(digit @ '1'..='9')) => {
+        self.selected = digit as usize - '1' as usize
+      }
+      (Screen::Game, KeyCode::Left) | (Screen::Game, KeyCode::Char('h')) => {
+        self.selected -= usize::from(!self.selected.is_multiple_of(3))
+      }
+      (Screen::Game, KeyCode::Right) | (Screen::Game, KeyCode::Char('l')) => {
+        self.selected += usize::from(self.selected % 3 < 2)
+      }
+      (Screen::Game, KeyCode::Up) | (Screen::Game, KeyCode::Char('k')) => {
+        self.selected -= if self.selected >= 3 { 3 } else { 0 }
+      }
+      (Screen::Game, KeyCode::Down) | (Screen::Game, KeyCode::Char('j')) => {
+        self.selected += if self.selected < 6 { 3 } else { 0 }
+      }
+      _ => {}
+    }
+  }
+
+  fn send_rematch(&mut self, decline: bool) {
+    let Some(seat) = self.seat.clone() else {
+      return;
+    };
+
+    if self.result().is_none() {
+      self.status = Some("The game isn't over yet".to_owned());
+      return;
+    }
+
+    self.network.rematch(seat, decline);
+  }
+
+  /// Checks the move against the local copy of the game before sending it to the server.
+  fn submit_move(&mut self, alg: &str) {
+    let Some(seat) = self.seat.clone() else {
+      return;
+    };
+
+    if self.result().is_some() {
+      self.status = Some("The game is over".to_owned());
+      return;
+    }
+
+    if self.game.next != self.color {
+      self.status = Some("It's not your turn".to_owned());
+      return;
+    }
+
+    let board = self.selected;
+    let coords = Coordinates::new((board % 3, board / 3));
+
+    match self.game.check_move(coords, alg) {
+      Ok(()) => {
+        self.status = None;
+        self.network.make_move(seat, board as u32, alg.to_owned());
+      }
+      Err(err) => self.status = Some(format!("{alg} on board {}: {err}", board + 1)),
+    }
+  }
+
+  pub fn on_update(&mut self, update: Update) {
+    match update {
+      Update::Waiting { seat, code } => {
+        self.seat = Some(seat);
+        if code.is_some() {
+          self.lobby_code = code;
+        }
+      }
+      Update::Started { seat } => {
+        self.seat = Some(seat);
+        self.screen = Screen::Game;
+      }
+      Update::Board(SubscribeBoardResponse {
+        color,
+        game,
+        request,
+        seat,
+        notice,
+        ..
+      }) => {
+        self.color = Color::from_i32(color).unwrap_or(Color::White);
+
+        // The server moved this stream on to a rematch.
+        if !seat.is_empty() {
+          self.seat = Some(seat);
+          self.status = None;
+        }
+
+        self.rematch = request
+          .and_then(|request| request.rematch)
+          .and_then(|rematch| Color::from_i32(rematch.requestee));
+
+        if let Some(game) = game {
+          self.game = HelperToe::from(game.clone());
+          self.state = Some(game);
+        }
+
+        if let Some(result) = self.result() {
+          self.status = Some(result);
+        }
+
+        if !notice.is_empty() {
+          self.status = Some(format!("Server notice: {notice}"));
+        }
+      }
+      Update::Error(message) => {
+        self.status = Some(message);
+
+        if self.screen == Screen::Waiting {
+          self.leave();
+        }
+      }
+    }
+  }
+}
@@ -0,0 +1,63 @@
+use helpers::{
+  chess::{Piece, PieceName},
+  chesstactoe::{chess, tic_tac_toe, Color},
+};
+
+pub fn piece_symbol(piece: Piece) -> char {
+  match (piece.color, piece.name) {
+    (Color::White, PieceName::KING) => '♔',
+    (Color::White, PieceName::QUEEN) => '♕',
+    (Color::White, PieceName::ROOK) => '♖',
+    (Color::White, PieceName::BISHOP) => '♗',
+    (Color::White, PieceName::KNIGHT) => '♘',
+    (Color::White, PieceName::PAWN) => '♙',
+    (Color::Black, PieceName::KING) => '♚',
+    (Color::Black, PieceName::QUEEN) => '♛',
+    (Color::Black, PieceName::ROOK) => '♜',
+    (Color::Black, PieceName::BISHOP) => '♝',
+    (Color::Black, PieceName::KNIGHT) => '♞',
+    (Color::Black, PieceName::PAWN) => '♟',
+  }
+}
+
+/// Board rows from top to bottom as `side` sees them, row 0 being the first rank.
+pub fn ranks(side: Color) -> [usize; 8] {
+  match side {
+    Color::White => [7, 6, 5, 4, 3, 2, 1, 0],
+    Color::Black => [0, 1, 2, 3, 4, 5, 6, 7],
+  }
+}
+
+/// Board columns from left to right as `side` sees them, column 0 being the a file.
+pub fn files(side: Color) -> [usize; 8] {
+  match side {
+    Color::White => [0, 1, 2, 3, 4, 5, 6, 7],
+    Color::Black => [7, 6, 5, 4, 3, 2, 1, 0],
+  }
+}
+
+fn color_name(color: i32) -> &'static str {
+  if color == Color::White as i32 {
+    "White"
+  } else {
+    "Black"
+  }
+}
+
+/// Result of a single board, `None` while it's still being played.
+pub fn board_result(end: &chess::EndResult) -> Option<String> {
+  match end {
+    chess::EndResult::Color(color) => Some(format!("{} won", color_name(*color))),
+    chess::EndResult::Draw(_) => Some("Draw".to_owned()),
+    chess::EndResult::None(_) => None,
+  }
+}
+
+/// Result of the whole game, `None` while it's still being played.
+pub fn game_result(end: &tic_tac_toe::EndResult) -> Option<String> {
+  match end {
+    tic_tac_toe::EndResult::Color(color) => Some(format!("{} wins", color_name(*color))),
+    tic_tac_toe::EndResult::Draw(_) => Some("Draw".to_owned()),
+    tic_tac_toe::EndResult::None(_) => None,
+  }
+}
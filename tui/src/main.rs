@@ -0,0 +1,118 @@
+mod app;
+mod board;
+mod network;
+mod ui;
+
+use std::{error::Error, io};
+
+use app::App;
+use clap::Parser;
+use crossterm::{
+  event::{Event, EventStream, KeyCode, KeyEventKind, KeyModifiers},
+  execute,
+  terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use futures::StreamExt;
+use helpers::{
+  auth::Session,
+  chesstactoe::{auth_client::AuthClient, game_client::GameClient, LoginRequest, RegisterRequest},
+};
+use network::{Network, Update};
+use ratatui::{backend::CrosstermBackend, Terminal};
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+use tonic::transport::Channel;
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Chess-Tac-Toe terminal client")]
+struct Args {
+  /// Server to connect to
+  #[arg(
+    short,
+    long,
+    env = "CHESSTACTOE_SERVER",
+    default_value = "http://localhost:50051"
+  )]
+  server: String,
+  #[arg(short, long, env = "CHESSTACTOE_USERNAME")]
+  username: String,
+  #[arg(short, long, env = "CHESSTACTOE_PASSWORD", hide_env_values = true)]
+  password: String,
+  /// Create the account before logging in
+  #[arg(long)]
+  register: bool,
+}
+
+type Backend = CrosstermBackend<io::Stdout>;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+  let args = Args::parse();
+
+  let channel = Channel::from_shared(args.server)?.connect().await?;
+
+  let mut auth = AuthClient::new(channel.clone());
+
+  let (username, password) = (args.username, args.password);
+
+  let login = if args.register {
+    auth
+      .register(RegisterRequest { username, password })
+      .await?
+  } else {
+    auth.login(LoginRequest { username, password }).await?
+  }
+  .into_inner();
+
+  let session = Session::default();
+  session.set_token(&login.token);
+
+  let (tx, rx) = mpsc::unbounded_channel();
+
+  let network = Network::new(GameClient::with_interceptor(channel, session), tx);
+
+  let mut app = App::new(login.username, network);
+
+  enable_raw_mode()?;
+  execute!(io::stdout(), EnterAlternateScreen)?;
+
+  let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+
+  let res = run(&mut terminal, &mut app, rx).await;
+
+  disable_raw_mode()?;
+  execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+  terminal.show_cursor()?;
+
+  res
+}
+
+async fn run(
+  terminal: &mut Terminal<Backend>,
+  app: &mut App,
+  mut updates: UnboundedReceiver<Update>,
+) -> Result<(), Box<dyn Error>> {
+  let mut events = EventStream::new();
+
+  while !app.quit {
+    terminal.draw(|frame| ui::draw(frame, app))?;
+
+    tokio::select! {
+      Some(event) = events.next() => {
+        if let Event::Key(key) = event? {
+          if key.kind != KeyEventKind::Press {
+            continue;
+          }
+
+          if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            app.quit = true;
+          } else {
+            app.on_key(key.code);
+          }
+        }
+      }
+      Some(update) = updates.recv() => app.on_update(update),
+    }
+  }
+
+  Ok(())
+}
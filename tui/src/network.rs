@@ -0,0 +1,200 @@
+use helpers::{
+  auth::Session,
+  chesstactoe::{
+    game_client, join_response::GameStatus, GameSettings, JoinLobbyRequest, JoinRequest,
+    JoinResponse, MakeLobbyRequest, MovePieceRequest, MoveResult, RematchRequest,
+    SubscribeBoardRequest, SubscribeBoardResponse,
+  },
+};
+use tokio::{sync::mpsc::UnboundedSender, task::JoinHandle};
+use tonic::{codegen::InterceptedService, transport::Channel, Status};
+
+type GameClient = game_client::GameClient<InterceptedService<Channel, Session>>;
+
+/// Something the server told us, handed to the UI loop.
+#[derive(Debug)]
+pub enum Update {
+  Waiting { seat: String, code: Option<String> },
+  Started { seat: String },
+  Board(SubscribeBoardResponse),
+  Error(String),
+}
+
+/// Runs the gRPC calls in the background, reporting back through [`Update`]s.
+#[derive(Debug, Clone)]
+pub struct Network {
+  client: GameClient,
+  updates: UnboundedSender<Update>,
+}
+
+impl Network {
+  pub fn new(client: GameClient, updates: UnboundedSender<Update>) -> Self {
+    Network { client, updates }
+  }
+
+  fn send(&self, update: Update) {
+    self.updates.send(update).unwrap_or(());
+  }
+
+  fn error(&self, status: Status) {
+    self.send(Update::Error(status.message().to_owned()));
+  }
+
+  /// Waits in the matchmaking queue, then follows the game. Aborting the task leaves the queue.
+  pub fn queue(&self, settings: GameSettings) -> JoinHandle<()> {
+    let mut network = self.clone();
+
+    tokio::spawn(async move {
+      let request = JoinRequest {
+        settings: Some(settings),
+      };
+
+      match network.client.join(request).await {
+        Ok(stream) => {
+          let mut stream = stream.into_inner();
+
+          loop {
+            match stream.message().await {
+              Ok(Some(response)) => {
+                if network.started(response, None).await {
+                  return;
+                }
+              }
+              Ok(None) => return,
+              Err(status) => return network.error(status),
+            }
+          }
+        }
+        Err(status) => network.error(status),
+      }
+    })
+  }
+
+  pub fn make_lobby(&self, settings: GameSettings, public: bool) -> JoinHandle<()> {
+    let mut network = self.clone();
+
+    tokio::spawn(async move {
+      let request = MakeLobbyRequest {
+        settings: Some(settings),
+        public,
+      };
+
+      match network.client.make_lobby(request).await {
+        Ok(stream) => {
+          let mut stream = stream.into_inner();
+
+          loop {
+            match stream.message().await {
+              Ok(Some(response)) => {
+                let Some(join_response) = response.join_response else {
+                  continue;
+                };
+
+                let code = Some(response.room_id).filter(|code| !code.is_empty());
+
+                if network.started(join_response, code).await {
+                  return;
+                }
+              }
+              Ok(None) => return,
+              Err(status) => return network.error(status),
+            }
+          }
+        }
+        Err(status) => network.error(status),
+      }
+    })
+  }
+
+  pub fn join_lobby(&self, code: String) -> JoinHandle<()> {
+    let mut network = self.clone();
+
+    tokio::spawn(async move {
+      match network.client.join_lobby(JoinLobbyRequest { code }).await {
+        Ok(stream) => {
+          let mut stream = stream.into_inner();
+
+          while let Ok(Some(response)) = stream.message().await {
+            if network.started(response, None).await {
+              return;
+            }
+          }
+        }
+        Err(status) => network.error(status),
+      }
+    })
+  }
+
+  /// Handles a join response, following the board once the game is ready. Returns whether the game
+  /// has started.
+  async fn started(&mut self, response: JoinResponse, code: Option<String>) -> bool {
+    let seat = response.uuid.clone();
+
+    if response.status() != GameStatus::Ready {
+      self.send(Update::Waiting { seat, code });
+      return false;
+    }
+
+    self.send(Update::Started { seat: seat.clone() });
+
+    match self
+      .client
+      .subscribe_board(SubscribeBoardRequest { uuid: seat })
+      .await
+    {
+      Ok(stream) => {
+        let mut stream = stream.into_inner();
+
+        loop {
+          match stream.message().await {
+            Ok(Some(board)) => self.send(Update::Board(board)),
+            Ok(None) => break,
+            Err(status) => {
+              self.error(status);
+              break;
+            }
+          }
+        }
+      }
+      Err(status) => self.error(status),
+    }
+
+    true
+  }
+
+  /// Offers or accepts a rematch. The new game arrives on the current board stream.
+  pub fn rematch(&self, seat: String, decline: bool) {
+    let mut network = self.clone();
+
+    tokio::spawn(async move {
+      let request = RematchRequest {
+        uuid: seat,
+        decline,
+      };
+
+      if let Err(status) = network.client.rematch(request).await {
+        network.error(status);
+      }
+    });
+  }
+
+  pub fn make_move(&self, seat: String, board: u32, alg: String) {
+    let mut network = self.clone();
+
+    tokio::spawn(async move {
+      let request = MovePieceRequest {
+        board,
+        alg,
+        uuid: seat,
+      };
+
+      match network.client.move_piece(request).await {
+        Ok(res) if res.get_ref().successful() != MoveResult::ResultSuccessful => {
+          network.send(Update::Error(res.into_inner().reason));
+        }
+        Ok(_) => {}
+        Err(status) => network.error(status),
+      }
+    });
+  }
+}
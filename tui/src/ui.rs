@@ -0,0 +1,337 @@
+use helpers::{chess::ChessBoard, chesstactoe::Color};
+use ratatui::{
+  backend::Backend,
+  layout::{Alignment, Constraint, Direction, Layout, Rect},
+  style::{Color as TermColor, Modifier, Style},
+  text::{Line, Span},
+  widgets::{Block, Borders, Paragraph, Wrap},
+  Frame,
+};
+
+use crate::{
+  app::{format_time_control, App, Input, Screen, TIME_CONTROLS},
+  board,
+};
+
+const LIGHT_SQUARE: TermColor = TermColor::Rgb(240, 217, 181);
+const DARK_SQUARE: TermColor = TermColor::Rgb(181, 136, 99);
+
+/// Width of a mini board with its border, two columns per square.
+const BOARD_WIDTH: u16 = 8 * 2 + 2;
+const BOARD_HEIGHT: u16 = 8 + 2;
+
+pub fn draw<B: Backend>(frame: &mut Frame<B>, app: &App) {
+  let [main, input] = split(
+    Direction::Vertical,
+    frame.size(),
+    [Constraint::Min(0), Constraint::Length(3)],
+  );
+
+  match app.screen {
+    Screen::Menu => draw_menu(frame, app, main),
+    Screen::Waiting => draw_waiting(frame, app, main),
+    Screen::Game => draw_game(frame, app, main),
+  }
+
+  draw_input(frame, app, input);
+}
+
+fn split<const N: usize>(
+  direction: Direction,
+  area: Rect,
+  constraints: [Constraint; N],
+) -> [Rect; N] {
+  let chunks = Layout::default()
+    .direction(direction)
+    .constraints(constraints)
+    .split(area);
+
+  std::array::from_fn(|i| chunks[i])
+}
+
+fn key_line<'a>(key: &'a str, description: String) -> Line<'a> {
+  Line::from(vec![
+    Span::styled(
+      format!("{key:>8}  "),
+      Style::default().add_modifier(Modifier::BOLD),
+    ),
+    Span::raw(description),
+  ])
+}
+
+fn on_off(value: bool) -> &'static str {
+  if value {
+    "yes"
+  } else {
+    "no"
+  }
+}
+
+fn draw_menu<B: Backend>(frame: &mut Frame<B>, app: &App, area: Rect) {
+  let mut lines = vec![
+    Line::from(format!("Logged in as {}", app.username)),
+    Line::from(""),
+    key_line("q", "Find a game".to_owned()),
+    key_line("m", "Make a lobby".to_owned()),
+    key_line("j", "Join a lobby by code".to_owned()),
+    Line::from(""),
+    key_line(
+      "t",
+      format!(
+        "Time control: {}",
+        format_time_control(TIME_CONTROLS[app.time_control])
+      ),
+    ),
+    key_line("r", format!("Rated: {}", on_off(app.rated))),
+    key_line("p", format!("Public lobby: {}", on_off(app.public))),
+    Line::from(""),
+    key_line("Esc", "Quit".to_owned()),
+  ];
+
+  if let Some(status) = &app.status {
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+      status.clone(),
+      Style::default().fg(TermColor::Red),
+    )));
+  }
+
+  frame.render_widget(
+    Paragraph::new(lines).block(
+      Block::default()
+        .borders(Borders::ALL)
+        .title("Chess-Tac-Toe"),
+    ),
+    area,
+  );
+}
+
+fn draw_waiting<B: Backend>(frame: &mut Frame<B>, app: &App, area: Rect) {
+  let mut lines = vec![Line::from("Waiting for an opponent...")];
+
+  if let Some(code) = &app.lobby_code {
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![
+      Span::raw("Lobby code: "),
+      Span::styled(code.clone(), Style::default().add_modifier(Modifier::BOLD)),
+    ]));
+  }
+
+  lines.push(Line::from(""));
+  lines.push(key_line("Esc", "Cancel".to_owned()));
+
+  frame.render_widget(
+    Paragraph::new(lines).alignment(Alignment::Center).block(
+      Block::default()
+        .borders(Borders::ALL)
+        .title("Chess-Tac-Toe"),
+    ),
+    area,
+  );
+}
+
+fn draw_game<B: Backend>(frame: &mut Frame<B>, app: &App, area: Rect) {
+  let [boards, side] = split(
+    Direction::Horizontal,
+    area,
+    [Constraint::Length(BOARD_WIDTH * 3), Constraint::Min(24)],
+  );
+
+  if app.zoomed {
+    draw_zoomed(frame, app, boards);
+  } else {
+    draw_grid(frame, app, boards);
+  }
+
+  draw_side_panel(frame, app, side);
+}
+
+fn board_block(app: &App, board: usize) -> Block<'static> {
+  let mut title = format!("{}", board + 1);
+
+  if let Some(result) = board::board_result(&app.board_end(board)) {
+    title = format!("{title} {result}");
+  }
+
+  let style = if board == app.selected {
+    Style::default().fg(TermColor::Yellow)
+  } else {
+    Style::default()
+  };
+
+  Block::default()
+    .borders(Borders::ALL)
+    .border_style(style)
+    .title(title)
+}
+
+fn square<'a>(chess: &ChessBoard, row: usize, col: usize, width: usize) -> Span<'a> {
+  let background = if (row + col).is_multiple_of(2) {
+    DARK_SQUARE
+  } else {
+    LIGHT_SQUARE
+  };
+
+  let symbol = chess.board[row][col].map_or(' ', board::piece_symbol);
+
+  Span::styled(
+    format!("{symbol:^width$}"),
+    Style::default().bg(background).fg(TermColor::Black),
+  )
+}
+
+fn draw_grid<B: Backend>(frame: &mut Frame<B>, app: &App, area: Rect) {
+  for board in 0..9 {
+    let area = Rect {
+      x: area.x + (board % 3) as u16 * BOARD_WIDTH,
+      y: area.y + (board / 3) as u16 * BOARD_HEIGHT,
+      width: BOARD_WIDTH,
+      height: BOARD_HEIGHT,
+    }
+    .intersection(frame.size());
+
+    let chess = &app.game.chesses[board / 3][board % 3];
+
+    let lines: Vec<Line> = board::ranks(app.color)
+      .into_iter()
+      .map(|row| {
+        Line::from(
+          board::files(app.color)
+            .into_iter()
+            .map(|col| square(chess, row, col, 2))
+            .collect::<Vec<_>>(),
+        )
+      })
+      .collect();
+
+    frame.render_widget(Paragraph::new(lines).block(board_block(app, board)), area);
+  }
+}
+
+fn draw_zoomed<B: Backend>(frame: &mut Frame<B>, app: &App, area: Rect) {
+  let chess = &app.game.chesses[app.selected / 3][app.selected % 3];
+
+  let mut lines: Vec<Line> = board::ranks(app.color)
+    .into_iter()
+    .map(|row| {
+      let mut spans = vec![Span::raw(format!("{} ", row + 1))];
+
+      spans.extend(
+        board::files(app.color)
+          .into_iter()
+          .map(|col| square(chess, row, col, 3)),
+      );
+
+      Line::from(spans)
+    })
+    .collect();
+
+  lines.push(Line::from(
+    std::iter::once(Span::raw("  "))
+      .chain(
+        board::files(app.color)
+          .into_iter()
+          .map(|col| Span::raw(format!("{:^3}", (b'a' + col as u8) as char))),
+      )
+      .collect::<Vec<_>>(),
+  ));
+
+  frame.render_widget(
+    Paragraph::new(lines).block(board_block(app, app.selected)),
+    area,
+  );
+}
+
+fn color_name(color: Color) -> &'static str {
+  match color {
+    Color::White => "White",
+    Color::Black => "Black",
+  }
+}
+
+fn draw_side_panel<B: Backend>(frame: &mut Frame<B>, app: &App, area: Rect) {
+  let mut lines = vec![
+    Line::from(format!("{} ({})", app.username, color_name(app.color))),
+    Line::from(""),
+  ];
+
+  if let Some(result) = app.result() {
+    lines.push(Line::from(Span::styled(
+      result,
+      Style::default().add_modifier(Modifier::BOLD),
+    )));
+  } else if app.game.next == app.color {
+    lines.push(Line::from(Span::styled(
+      "Your move",
+      Style::default().fg(TermColor::Green),
+    )));
+  } else {
+    lines.push(Line::from(format!("{} to move", color_name(app.game.next))));
+  }
+
+  if let Some(last_move) = app
+    .state
+    .as_ref()
+    .map(|state| &state.last_move)
+    .filter(|last_move| !last_move.is_empty())
+  {
+    lines.push(Line::from(format!("Last move: {last_move}")));
+  }
+
+  if let Some(code) = &app.lobby_code {
+    lines.push(Line::from(format!("Lobby: {code}")));
+  }
+
+  match app.rematch {
+    Some(requestee) if requestee == app.color => {
+      lines.push(Line::from("Your opponent wants a rematch"));
+      lines.push(key_line("r / n", "Accept / decline".to_owned()));
+    }
+    Some(_) => {
+      lines.push(Line::from("Rematch offered"));
+      lines.push(key_line("n", "Withdraw".to_owned()));
+    }
+    None if app.result().is_some() => lines.push(key_line("r", "Offer a rematch".to_owned())),
+    None => {}
+  }
+
+  if let Some(status) = &app.status {
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+      status.clone(),
+      Style::default().fg(TermColor::Red),
+    )));
+  }
+
+  lines.extend([
+    Line::from(""),
+    key_line("1-9 hjkl", "Select board".to_owned()),
+    key_line("Enter z", "Zoom".to_owned()),
+    key_line("m :", "Enter a move".to_owned()),
+    key_line("q", "Leave".to_owned()),
+  ]);
+
+  frame.render_widget(
+    Paragraph::new(lines)
+      .wrap(Wrap { trim: false })
+      .block(Block::default().borders(Borders::ALL).title("Game")),
+    area,
+  );
+}
+
+fn draw_input<B: Backend>(frame: &mut Frame<B>, app: &App, area: Rect) {
+  let title = match app.input_mode {
+    Input::None => "",
+    Input::LobbyCode => "Lobby code",
+    Input::Move => "Move",
+  };
+
+  frame.render_widget(
+    Paragraph::new(app.input.as_str()).block(Block::default().borders(Borders::ALL).title(title)),
+    area,
+  );
+
+  if app.input_mode != Input::None {
+    frame.set_cursor(area.x + 1 + app.input.chars().count() as u16, area.y + 1);
+  }
+}